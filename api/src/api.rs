@@ -33,13 +33,14 @@
 //! The "verify" part is implemented in the consensus layer (UltraSONIC), the "transact" part is
 //! performed directly, so these two are not covered by an API.
 
+use alloc::collections::{BTreeMap, BTreeSet};
 use core::cmp::Ordering;
 use core::fmt::Debug;
 use core::hash::{Hash, Hasher};
 use core::num::ParseIntError;
 
 use aluvm::{Lib, LibId};
-use amplify::confinement::{SmallOrdMap, SmallOrdSet, TinyOrdMap, TinyOrdSet, TinyString};
+use amplify::confinement::{SmallOrdMap, SmallOrdSet, TinyOrdMap, TinyOrdSet, TinyString, TinyVec};
 use amplify::num::u256;
 use amplify::Bytes4;
 use baid64::Baid64ParseError;
@@ -52,7 +53,7 @@ use ultrasonic::{CallId, Codex, CodexId, StateData, StateValue};
 
 use crate::{
     Aggregator, RawBuilder, RawConvertor, StateArithm, StateAtom, StateBuildError, StateBuilder, StateCalc,
-    StateConvertError, StateConvertor, LIB_NAME_SONIC,
+    StateConvertError, StateConvertor, SubAggregator, LIB_NAME_SONIC,
 };
 
 /// Errors happening during parsing of a versioned contract or codex ID.
@@ -211,12 +212,28 @@ impl CommitEncode for Semantics {
     }
 }
 
+#[cfg(test)]
+thread_local! {
+    /// Number of times [`Semantics::apis_checksum`] was computed by the current thread.
+    pub(crate) static APIS_CHECKSUM_COMPUTATIONS: core::cell::Cell<usize> = const { core::cell::Cell::new(0) };
+}
+
 impl Semantics {
-    pub fn apis_checksum(&self) -> ApisChecksum { self.commit_id() }
+    pub fn apis_checksum(&self) -> ApisChecksum {
+        #[cfg(test)]
+        APIS_CHECKSUM_COMPUTATIONS.with(|count| count.set(count.get() + 1));
+        self.commit_id()
+    }
 
     /// Iterates over all APIs, including default and named ones.
     pub fn apis(&self) -> impl Iterator<Item = &Api> { [&self.default].into_iter().chain(self.custom.values()) }
 
+    /// Detects whether any of the APIs references AluVM in its state adaptors, arithmetics or
+    /// aggregators.
+    ///
+    /// If not, a runtime may skip setting up the virtual machine and loading API libraries.
+    pub fn uses_aluvm(&self) -> bool { self.apis().any(|api| !api.called_libs().is_empty()) }
+
     /// Check whether this semantics object matches codex and the provided set of libraries for it.
     pub fn check(&self, codex: &Codex) -> Result<(), SemanticError> {
         let codex_id = codex.codex_id();
@@ -232,6 +249,42 @@ impl Semantics {
             }
         }
 
+        // Check that all APIs agree on the call ids for the same methods
+        let mut methods = bmap! {};
+        for api in self.apis() {
+            for (method, call_id) in &api.verifiers {
+                if *methods.entry(method).or_insert(call_id) != call_id {
+                    return Err(SemanticError::MethodCallIdConflict(method.clone()));
+                }
+            }
+        }
+
+        // Check that default calls reference existing methods and owned state
+        for api in self.apis() {
+            let Some(call) = &api.default_call else {
+                continue;
+            };
+            if !api.verifiers.contains_key(&call.method)
+                || call.owned.as_ref().is_some_and(|name| !api.owned.contains_key(name))
+            {
+                return Err(SemanticError::InvalidDefaultCall(call.method.clone()));
+            }
+        }
+
+        // Check that aggregators do not depend on each other in a cycle
+        for api in self.apis() {
+            let mut computed = bset![];
+            for (name, _, deps) in api.aggregator_plan() {
+                if deps
+                    .iter()
+                    .any(|dep| api.aggregators.contains_key(dep) && !computed.contains(dep))
+                {
+                    return Err(SemanticError::CyclicAggregator(name));
+                }
+                computed.insert(name);
+            }
+        }
+
         // Check codex libs for redundancies and completeness
         let lib_map = self
             .codex_libs
@@ -266,42 +319,10 @@ impl Semantics {
             .map(|lib| (lib.lib_id(), lib))
             .collect::<IndexMap<_, _>>();
 
-        let mut lib_ids = indexset![];
-        for api in self.apis() {
-            for agg in api.aggregators.values() {
-                if let Aggregator::AluVM(entry) = agg {
-                    lib_ids.insert(entry.lib_id);
-                }
-            }
-            for glob in api.global.values() {
-                if let StateConvertor::AluVM(entry) = glob.convertor {
-                    lib_ids.insert(entry.lib_id);
-                }
-                if let StateBuilder::AluVM(entry) = glob.builder {
-                    lib_ids.insert(entry.lib_id);
-                }
-                if let RawConvertor::AluVM(entry) = glob.raw_convertor {
-                    lib_ids.insert(entry.lib_id);
-                }
-                if let RawBuilder::AluVM(entry) = glob.raw_builder {
-                    lib_ids.insert(entry.lib_id);
-                }
-            }
-            for owned in api.owned.values() {
-                if let StateConvertor::AluVM(entry) = owned.convertor {
-                    lib_ids.insert(entry.lib_id);
-                }
-                if let StateBuilder::AluVM(entry) = owned.builder {
-                    lib_ids.insert(entry.lib_id);
-                }
-                if let StateBuilder::AluVM(entry) = owned.witness_builder {
-                    lib_ids.insert(entry.lib_id);
-                }
-                if let StateArithm::AluVM(entry) = owned.arithmetics {
-                    lib_ids.insert(entry.lib_id);
-                }
-            }
-        }
+        let mut lib_ids = self
+            .apis()
+            .flat_map(Api::called_libs)
+            .collect::<IndexSet<_>>();
         let mut i = 0usize;
         let mut count = lib_ids.len();
         while i < count {
@@ -319,6 +340,188 @@ impl Semantics {
 
         Ok(())
     }
+
+    /// Collects AluVM libraries called from the provided APIs, including all the libraries they
+    /// depend on, out of the `available` libraries.
+    ///
+    /// Used in constructing [`Semantics::api_libs`], such that the set is complete and has no
+    /// redundant libraries, as required by [`Semantics::check`].
+    ///
+    /// # Errors
+    ///
+    /// If some of the required libraries are not present in `available`.
+    pub fn collect_libs<'a>(
+        apis: impl IntoIterator<Item = &'a Api>,
+        available: &[Lib],
+    ) -> Result<SmallOrdSet<Lib>, SemanticError> {
+        let lib_map = available
+            .iter()
+            .map(|lib| (lib.lib_id(), lib))
+            .collect::<IndexMap<_, _>>();
+
+        let mut lib_ids = apis
+            .into_iter()
+            .flat_map(Api::called_libs)
+            .collect::<IndexSet<_>>();
+        let mut libs = SmallOrdSet::new();
+        let mut i = 0usize;
+        while let Some(id) = lib_ids.get_index(i) {
+            let lib = *lib_map.get(id).ok_or(SemanticError::MissedApiLib(*id))?;
+            lib_ids.extend(lib.libs.iter().copied());
+            libs.push(lib.clone()).expect("too many API libraries");
+            i += 1;
+        }
+        Ok(libs)
+    }
+
+    /// Constructs semantics retaining only the default API and, optionally, a single named custom
+    /// API, together with the API libraries and the types they use.
+    ///
+    /// Types which are used only from inside AluVM scripts can't be detected and are not retained.
+    ///
+    /// # Errors
+    ///
+    /// If there is no custom API with the provided name.
+    pub fn project(&self, api_name: Option<&TypeName>) -> Result<Semantics, SemanticError> {
+        let mut custom = SmallOrdMap::new();
+        if let Some(name) = api_name {
+            let api = self
+                .custom
+                .get(name)
+                .ok_or_else(|| SemanticError::UnknownApi(name.clone()))?;
+            custom
+                .insert(name.clone(), api.clone())
+                .expect("single API can't exceed the confinement");
+        }
+
+        let apis = [&self.default].into_iter().chain(custom.values());
+        let available = self.api_libs.iter().cloned().collect::<Vec<_>>();
+        let api_libs = Self::collect_libs(apis.clone(), &available)?;
+
+        let sem_ids = apis
+            .flat_map(Api::referenced_types)
+            .filter(|sem_id| self.types.get(*sem_id).is_some());
+        let types = self
+            .types
+            .extract(sem_ids)
+            .expect("only known types are extracted");
+
+        Ok(Semantics {
+            version: self.version,
+            default: self.default.clone(),
+            custom,
+            codex_libs: self.codex_libs.clone(),
+            api_libs,
+            types,
+        })
+    }
+
+    /// Reports differences between this semantics and a semantics from some other version of
+    /// the same contract, which may be used, for instance, in confirming an API upgrade with the
+    /// user.
+    ///
+    /// Custom APIs are matched by their names; additions and removals are reported from the
+    /// point of view of `other` being the newer version.
+    pub fn diff(&self, other: &Semantics) -> SemanticsDiff {
+        let mut diff = SemanticsDiff { default: self.default.diff(&other.default), ..default!() };
+        for (name, api) in &self.custom {
+            match other.custom.get(name) {
+                None => {
+                    diff.removed_apis.insert(name.clone());
+                }
+                Some(other_api) => {
+                    let api_diff = api.diff(other_api);
+                    if !api_diff.is_empty() {
+                        diff.changed_apis.insert(name.clone(), api_diff);
+                    }
+                }
+            }
+        }
+        diff.added_apis = other
+            .custom
+            .keys()
+            .filter(|name| !self.custom.contains_key(*name))
+            .cloned()
+            .collect();
+        diff
+    }
+}
+
+/// Differences between two versions of contract [`Semantics`], as reported by
+/// [`Semantics::diff`].
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct SemanticsDiff {
+    /// Names of custom APIs present only in the newer semantics.
+    pub added_apis: BTreeSet<TypeName>,
+    /// Names of custom APIs present only in the older semantics.
+    pub removed_apis: BTreeSet<TypeName>,
+    /// Changes in the default API.
+    pub default: ApiDiff,
+    /// Changes in custom APIs present in both semantics; APIs without changes are omitted.
+    pub changed_apis: BTreeMap<TypeName, ApiDiff>,
+}
+
+impl SemanticsDiff {
+    /// Detects whether both semantics define the same set of APIs with the same methods and
+    /// aggregators.
+    pub fn is_empty(&self) -> bool {
+        self.added_apis.is_empty()
+            && self.removed_apis.is_empty()
+            && self.default.is_empty()
+            && self.changed_apis.is_empty()
+    }
+}
+
+/// Differences in verifier methods and aggregators between two versions of an [`Api`], as
+/// reported by [`Api::diff`].
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct ApiDiff {
+    /// Methods present only in the newer API.
+    pub added_methods: BTreeSet<MethodName>,
+    /// Methods present only in the older API.
+    pub removed_methods: BTreeSet<MethodName>,
+    /// Methods which are linked to a different verifier in the newer API.
+    pub changed_methods: BTreeSet<MethodName>,
+    /// Aggregators present only in the newer API.
+    pub added_aggregators: BTreeSet<MethodName>,
+    /// Aggregators present only in the older API.
+    pub removed_aggregators: BTreeSet<MethodName>,
+    /// Aggregators which are computed differently in the newer API.
+    pub changed_aggregators: BTreeSet<MethodName>,
+}
+
+impl ApiDiff {
+    /// Detects whether both APIs define the same verifier methods and aggregators.
+    pub fn is_empty(&self) -> bool {
+        self.added_methods.is_empty()
+            && self.removed_methods.is_empty()
+            && self.changed_methods.is_empty()
+            && self.added_aggregators.is_empty()
+            && self.removed_aggregators.is_empty()
+            && self.changed_aggregators.is_empty()
+    }
+}
+
+/// Computes names of added, removed and changed entries between two maps.
+fn diff_maps<V: Eq>(
+    old: &TinyOrdMap<MethodName, V>,
+    new: &TinyOrdMap<MethodName, V>,
+) -> (BTreeSet<MethodName>, BTreeSet<MethodName>, BTreeSet<MethodName>) {
+    let added = new
+        .keys()
+        .filter(|name| !old.contains_key(*name))
+        .cloned()
+        .collect();
+    let mut removed = bset![];
+    let mut changed = bset![];
+    for (name, val) in old {
+        match new.get(name) {
+            None => removed.insert(name.clone()),
+            Some(other) if other != val => changed.insert(name.clone()),
+            Some(_) => continue,
+        };
+    }
+    (added, removed, changed)
 }
 
 /// API is an interface implementation.
@@ -389,13 +592,208 @@ impl Hash for Api {
 impl Api {
     pub fn api_id(&self) -> StrictHash { self.commit_id() }
 
+    /// Collects ids of all AluVM libraries directly called by the API adaptors and aggregators.
+    fn called_libs(&self) -> IndexSet<LibId> {
+        let mut lib_ids = indexset![];
+        for agg in self.aggregators.values() {
+            if let Aggregator::AluVM(entry, _) = agg {
+                lib_ids.insert(entry.lib_id);
+            }
+        }
+        for glob in self.global.values() {
+            for convertor in glob.convertors() {
+                if let StateConvertor::AluVM(entry) = convertor {
+                    lib_ids.insert(entry.lib_id);
+                }
+            }
+            if let StateBuilder::AluVM(entry) = glob.builder {
+                lib_ids.insert(entry.lib_id);
+            }
+            if let RawConvertor::AluVM(entry) = glob.raw_convertor {
+                lib_ids.insert(entry.lib_id);
+            }
+            if let RawBuilder::AluVM(entry) = glob.raw_builder {
+                lib_ids.insert(entry.lib_id);
+            }
+        }
+        for owned in self.owned.values() {
+            for convertor in owned.convertors() {
+                if let StateConvertor::AluVM(entry) = convertor {
+                    lib_ids.insert(entry.lib_id);
+                }
+            }
+            if let StateBuilder::AluVM(entry) = owned.builder {
+                lib_ids.insert(entry.lib_id);
+            }
+            if let StateBuilder::AluVM(entry) = owned.witness_builder {
+                lib_ids.insert(entry.lib_id);
+            }
+            if let StateArithm::AluVM(entry) = owned.arithmetics {
+                lib_ids.insert(entry.lib_id);
+            }
+        }
+        lib_ids
+    }
+
+    /// Collects semantic ids of all types directly referenced by the API state adaptors and
+    /// aggregators.
+    fn referenced_types(&self) -> BTreeSet<SemId> {
+        let mut sem_ids = bset![];
+        let add_convertor = |sem_ids: &mut BTreeSet<SemId>, convertor: &StateConvertor| {
+            if let StateConvertor::FixedArray(_, elem, _) = convertor {
+                sem_ids.insert(*elem);
+            }
+        };
+        let add_builder = |sem_ids: &mut BTreeSet<SemId>, builder: &StateBuilder| {
+            if let StateBuilder::FixedArray(_, elem, _) = builder {
+                sem_ids.insert(*elem);
+            }
+        };
+        for glob in self.global.values() {
+            sem_ids.insert(glob.sem_id);
+            if let RawConvertor::StrictDecode(sem_id) = glob.raw_convertor {
+                sem_ids.insert(sem_id);
+            }
+            if let RawBuilder::StrictEncode(sem_id) = glob.raw_builder {
+                sem_ids.insert(sem_id);
+            }
+            glob.convertors()
+                .for_each(|convertor| add_convertor(&mut sem_ids, convertor));
+            add_builder(&mut sem_ids, &glob.builder);
+        }
+        for owned in self.owned.values() {
+            sem_ids.insert(owned.sem_id);
+            sem_ids.insert(owned.witness_sem_id);
+            owned
+                .convertors()
+                .for_each(|convertor| add_convertor(&mut sem_ids, convertor));
+            add_builder(&mut sem_ids, &owned.builder);
+            add_builder(&mut sem_ids, &owned.witness_builder);
+        }
+        for agg in self.aggregators.values() {
+            let subs = match agg {
+                Aggregator::Some(sub) | Aggregator::Take(sub) => vec![sub],
+                Aggregator::Or(some, other) => vec![some, other],
+                Aggregator::AluVM(_, sem_id) => {
+                    sem_ids.insert(*sem_id);
+                    vec![]
+                }
+                Aggregator::None => vec![],
+            };
+            for sub in subs {
+                if let SubAggregator::Const(sem_id, _)
+                | SubAggregator::Any(_, sem_id, _)
+                | SubAggregator::All(_, sem_id, _) = sub
+                {
+                    sem_ids.insert(*sem_id);
+                }
+            }
+        }
+        sem_ids
+    }
+
+    /// Provides a plan for computing aggregated state: a list of all aggregators, their definitions
+    /// and names of other aggregated state they depend on (see [`Aggregator::depends_on`]).
+    ///
+    /// The list is sorted in the order of computation, such that each aggregator follows all the
+    /// aggregators it depends on. Aggregators which participate in dependency cycles can't be
+    /// computed; they are put at the end of the list.
+    ///
+    /// [`Aggregator::AluVM`] scripts depend on all non-script aggregators, since they are computed
+    /// after them. Thus, a non-script aggregator depending on a script forms a dependency cycle.
+    pub fn aggregator_plan(&self) -> Vec<(MethodName, Aggregator, Vec<StateName>)> {
+        let non_scripts = self
+            .aggregators
+            .iter()
+            .filter(|(_, aggregator)| !aggregator.is_script())
+            .map(|(name, _)| name.clone())
+            .collect::<Vec<_>>();
+        let mut pending = self
+            .aggregators
+            .iter()
+            .map(|(name, aggregator)| {
+                let deps = if aggregator.is_script() {
+                    non_scripts.clone()
+                } else {
+                    aggregator.depends_on().cloned().collect::<Vec<_>>()
+                };
+                (name.clone(), aggregator.clone(), deps)
+            })
+            .collect::<Vec<_>>();
+        let mut plan = Vec::with_capacity(pending.len());
+        let mut resolved = bset![];
+        loop {
+            let len = pending.len();
+            pending.retain(|(name, aggregator, deps)| {
+                // Dependencies which are not aggregators are not a part of the plan
+                if deps
+                    .iter()
+                    .any(|dep| self.aggregators.contains_key(dep) && !resolved.contains(dep))
+                {
+                    return true;
+                }
+                resolved.insert(name.clone());
+                plan.push((name.clone(), aggregator.clone(), deps.clone()));
+                false
+            });
+            if pending.len() == len {
+                break;
+            }
+        }
+        plan.extend(pending);
+        plan
+    }
+
+    /// Reports differences in verifier methods and aggregators between this API and some other
+    /// (newer) version of it.
+    pub fn diff(&self, other: &Api) -> ApiDiff {
+        let (added_methods, removed_methods, changed_methods) = diff_maps(&self.verifiers, &other.verifiers);
+        let (added_aggregators, removed_aggregators, changed_aggregators) =
+            diff_maps(&self.aggregators, &other.aggregators);
+        ApiDiff {
+            added_methods,
+            removed_methods,
+            changed_methods,
+            added_aggregators,
+            removed_aggregators,
+            changed_aggregators,
+        }
+    }
+
+    /// Names of the global state which is published, i.e. always included into contract exports.
+    ///
+    /// Public-facing contract views should expose only this state.
+    pub fn published_global_names(&self) -> impl Iterator<Item = &StateName> {
+        self.global
+            .iter()
+            .filter(|(_, api)| api.published)
+            .map(|(name, _)| name)
+    }
+
+    /// Names of the global state which is not published (see [`Self::published_global_names`]).
+    pub fn private_global_names(&self) -> impl Iterator<Item = &StateName> {
+        self.global
+            .iter()
+            .filter(|(_, api)| !api.published)
+            .map(|(name, _)| name)
+    }
+
     pub fn verifier(&self, method: impl Into<MethodName>) -> Option<CallId> {
         self.verifiers.get(&method.into()).copied()
     }
 
-    pub fn convert_global(
+    /// Resolves the name of a method verified by the codex verifier with the provided call id.
+    pub fn method(&self, call_id: CallId) -> Option<&MethodName> {
+        self.verifiers
+            .iter()
+            .find(|(_, id)| **id == call_id)
+            .map(|(method, _)| method)
+    }
+
+    pub fn convert_global<'libs>(
         &self,
         data: &StateData,
+        libs: impl IntoIterator<Item = &'libs Lib> + Copy,
         sys: &TypeSystem,
     ) -> Result<Option<(StateName, StateAtom)>, StateConvertError> {
         // Here we do not yet know which state we are using, since it is encoded inside the field element
@@ -403,19 +801,25 @@ impl Api {
         // convertors check the state type. Then, we use the state name associated with the succeeding
         // convertor.
         for (name, api) in &self.global {
-            if let Some(verified) = api.convertor.convert(api.sem_id, data.value, sys)? {
-                let unverified =
-                    if let Some(raw) = data.raw.as_ref() { Some(api.raw_convertor.convert(raw, sys)?) } else { None };
-                return Ok(Some((name.clone(), StateAtom { verified, unverified })));
+            for convertor in api.convertors() {
+                if let Some(verified) = convertor.convert(api.sem_id, data.value, libs, sys)? {
+                    let unverified = if let Some(raw) = data.raw.as_ref() {
+                        Some(api.raw_convertor.convert(raw, sys)?)
+                    } else {
+                        None
+                    };
+                    return Ok(Some((name.clone(), StateAtom { verified, unverified })));
+                }
             }
         }
         // This means this state is unrelated to this API
         Ok(None)
     }
 
-    pub fn convert_owned(
+    pub fn convert_owned<'libs>(
         &self,
         value: StateValue,
+        libs: impl IntoIterator<Item = &'libs Lib> + Copy,
         sys: &TypeSystem,
     ) -> Result<Option<(StateName, StrictVal)>, StateConvertError> {
         // Here we do not yet know which state we are using, since it is encoded inside the field element
@@ -423,8 +827,10 @@ impl Api {
         // convertors check the state type. Then, we use the state name associated with the succeeding
         // convertor.
         for (name, api) in &self.owned {
-            if let Some(atom) = api.convertor.convert(api.sem_id, value, sys)? {
-                return Ok(Some((name.clone(), atom)));
+            for convertor in api.convertors() {
+                if let Some(atom) = convertor.convert(api.sem_id, value, libs, sys)? {
+                    return Ok(Some((name.clone(), atom)));
+                }
             }
         }
         // This means this state is unrelated to this API
@@ -432,11 +838,12 @@ impl Api {
     }
 
     #[allow(clippy::result_large_err)]
-    pub fn build_immutable(
+    pub fn build_immutable<'libs>(
         &self,
         name: impl Into<StateName>,
         data: StrictVal,
         raw: Option<StrictVal>,
+        libs: impl IntoIterator<Item = &'libs Lib>,
         sys: &TypeSystem,
     ) -> Result<StateData, StateBuildError> {
         let name = name.into();
@@ -444,16 +851,17 @@ impl Api {
             .global
             .get(&name)
             .ok_or(StateBuildError::UnknownStateName(name))?;
-        let value = api.builder.build(api.sem_id, data, sys)?;
+        let value = api.builder.build(api.sem_id, data, libs, sys)?;
         let raw = raw.map(|raw| api.raw_builder.build(raw, sys)).transpose()?;
         Ok(StateData { value, raw })
     }
 
     #[allow(clippy::result_large_err)]
-    pub fn build_destructible(
+    pub fn build_destructible<'libs>(
         &self,
         name: impl Into<StateName>,
         data: StrictVal,
+        libs: impl IntoIterator<Item = &'libs Lib>,
         sys: &TypeSystem,
     ) -> Result<StateValue, StateBuildError> {
         let name = name.into();
@@ -462,14 +870,15 @@ impl Api {
             .get(&name)
             .ok_or(StateBuildError::UnknownStateName(name))?;
 
-        api.builder.build(api.sem_id, data, sys)
+        api.builder.build(api.sem_id, data, libs, sys)
     }
 
     #[allow(clippy::result_large_err)]
-    pub fn build_witness(
+    pub fn build_witness<'libs>(
         &self,
         name: impl Into<StateName>,
         data: StrictVal,
+        libs: impl IntoIterator<Item = &'libs Lib>,
         sys: &TypeSystem,
     ) -> Result<StateValue, StateBuildError> {
         let name = name.into();
@@ -478,7 +887,50 @@ impl Api {
             .get(&name)
             .ok_or(StateBuildError::UnknownStateName(name))?;
 
-        api.witness_builder.build(api.witness_sem_id, data, sys)
+        api.witness_builder
+            .build(api.witness_sem_id, data, libs, sys)
+    }
+
+    /// Decodes an input witness for the owned state with a given name, which was constructed by the
+    /// witness builder of the state API (see [`Self::build_witness`]).
+    pub fn convert_witness<'libs>(
+        &self,
+        name: impl Into<StateName>,
+        value: StateValue,
+        libs: impl IntoIterator<Item = &'libs Lib>,
+        sys: &TypeSystem,
+    ) -> Result<Option<StrictVal>, StateConvertError> {
+        let name = name.into();
+        let api = self
+            .owned
+            .get(&name)
+            .ok_or(StateConvertError::UnknownStateName(name))?;
+
+        api.witness_builder
+            .to_convertor()
+            .convert(api.witness_sem_id, value, libs, sys)
+    }
+
+    /// Checks whether a value can be encoded as a verifiable part of a state with the provided name.
+    ///
+    /// The check runs the same builder as [`Self::build_destructible`] (for owned state) or
+    /// [`Self::build_immutable`] (for global state) does, discarding the result. It is intended as
+    /// a cheap pre-flight check, for instance, before creating an invoice with the value.
+    #[allow(clippy::result_large_err)]
+    pub fn can_encode<'libs>(
+        &self,
+        name: impl Into<StateName>,
+        value: &StrictVal,
+        libs: impl IntoIterator<Item = &'libs Lib>,
+        sys: &TypeSystem,
+    ) -> Result<(), StateBuildError> {
+        let name = name.into();
+        let (sem_id, builder) = match (self.owned.get(&name), self.global.get(&name)) {
+            (Some(api), _) => (api.sem_id, &api.builder),
+            (None, Some(api)) => (api.sem_id, &api.builder),
+            (None, None) => return Err(StateBuildError::UnknownStateName(name)),
+        };
+        builder.build(sem_id, value.clone(), libs, sys).map(|_| ())
     }
 
     pub fn calculate(&self, name: impl Into<StateName>) -> Result<StateCalc, StateUnknown> {
@@ -510,6 +962,12 @@ pub struct GlobalApi {
     /// structured type [`StrictVal`].
     pub convertor: StateConvertor,
 
+    /// Convertors for the state produced with previous versions of the state encoding.
+    ///
+    /// The fallback convertors are tried in order after the main [`Self::convertor`]; the first
+    /// convertor matching the state wins.
+    pub fallback_convertors: TinyVec<StateConvertor>,
+
     /// Procedure which builds a state in the form of field elements [`StateValue`] out of a
     /// structured type [`StrictVal`].
     pub builder: StateBuilder,
@@ -523,6 +981,13 @@ pub struct GlobalApi {
     pub raw_builder: RawBuilder,
 }
 
+impl GlobalApi {
+    /// Iterates over the main and all fallback state convertors, in the order they must be tried.
+    pub fn convertors(&self) -> impl Iterator<Item = &StateConvertor> {
+        [&self.convertor].into_iter().chain(&self.fallback_convertors)
+    }
+}
+
 /// API for owned (destrictible, or read-once) state.
 ///
 /// API covers two main functions: taking structured data from the user input and _building_ a valid
@@ -544,6 +1009,12 @@ pub struct OwnedApi {
     /// structured type [`StrictVal`].
     pub convertor: StateConvertor,
 
+    /// Convertors for the state produced with previous versions of the state encoding.
+    ///
+    /// The fallback convertors are tried in order after the main [`Self::convertor`]; the first
+    /// convertor matching the state wins.
+    pub fallback_convertors: TinyVec<StateConvertor>,
+
     /// Procedure which builds a state in the form of field elements [`StateValue`] out of a
     /// structured type [`StrictVal`].
     pub builder: StateBuilder,
@@ -557,6 +1028,13 @@ pub struct OwnedApi {
     pub witness_builder: StateBuilder,
 }
 
+impl OwnedApi {
+    /// Iterates over the main and all fallback state convertors, in the order they must be tried.
+    pub fn convertors(&self) -> impl Iterator<Item = &StateConvertor> {
+        [&self.convertor].into_iter().chain(&self.fallback_convertors)
+    }
+}
+
 /// Error indicating that an API was asked to convert a state which is not known to it.
 #[derive(Clone, Eq, PartialEq, Debug, Display, Error, From)]
 #[display("unknown state name '{0}'")]
@@ -576,6 +1054,16 @@ pub enum SemanticError {
     /// articles contain duplicated API {0} under a different name.
     DuplicatedApi(StrictHash),
 
+    /// method '{0}' is mapped to different call ids by different contract APIs.
+    MethodCallIdConflict(MethodName),
+
+    /// default call of a contract API uses method '{0}' or an owned state which are not defined by
+    /// the API.
+    InvalidDefaultCall(MethodName),
+
+    /// aggregator '{0}' depends on itself, directly or via other aggregators, and can't be computed.
+    CyclicAggregator(MethodName),
+
     /// library {0} is used by the contract codex verifiers but absent from the articles.
     MissedCodexLib(LibId),
 
@@ -590,4 +1078,7 @@ pub enum SemanticError {
 
     /// invalid signature over the contract articles.
     InvalidSignature,
+
+    /// contract articles don't contain API named '{0}'.
+    UnknownApi(TypeName),
 }
@@ -39,7 +39,7 @@ use ultrasonic::{
     CallId, Codex, CodexId, ContractId, ContractMeta, ContractName, Genesis, Identity, Issue, LibRepo, Opid,
 };
 
-use crate::{Api, ApisChecksum, ParseVersionedError, SemanticError, Semantics, LIB_NAME_SONIC};
+use crate::{Api, ApisChecksum, ParseVersionedError, SemanticError, Semantics, SemanticsDiff, LIB_NAME_SONIC};
 
 /// Articles id is a versioned variant for the contract id, which includes information about a
 /// specific API version.
@@ -119,6 +119,10 @@ pub struct Articles {
     sig: Option<SigBlob>,
     /// The contract issue.
     issue: Issue,
+    /// Cached checksum of the APIs from [`Self::semantics`], which must be updated each time the
+    /// semantics changes.
+    #[strict_type(skip)]
+    apis_checksum: ApisChecksum,
 }
 
 impl Articles {
@@ -131,7 +135,8 @@ impl Articles {
         sig_validator: impl FnOnce(StrictHash, &Identity, &SigBlob) -> Result<(), E>,
     ) -> Result<Self, SemanticError> {
         semantics.check(&issue.codex)?;
-        let mut me = Self { semantics, issue, sig: None };
+        let apis_checksum = semantics.apis_checksum();
+        let mut me = Self { semantics, issue, sig: None, apis_checksum };
         let id = me.articles_id().commit_id();
         if let Some(sig) = &sig {
             sig_validator(id, &me.issue.meta.issuer, sig).map_err(|_| SemanticError::InvalidSignature)?;
@@ -146,9 +151,13 @@ impl Articles {
         ArticlesId {
             contract_id: self.issue.contract_id(),
             version: self.semantics.version,
-            checksum: self.semantics.apis_checksum(),
+            checksum: self.apis_checksum,
         }
     }
+    /// Get the checksum of the contract APIs.
+    ///
+    /// Unlike [`Semantics::apis_checksum`], doesn't recompute the checksum on each call.
+    pub fn apis_checksum(&self) -> ApisChecksum { self.apis_checksum }
     /// Compute a contract id.
     pub fn contract_id(&self) -> ContractId { self.issue.contract_id() }
     /// Compute a codex id.
@@ -184,6 +193,11 @@ impl Articles {
     pub fn sig(&self) -> &Option<SigBlob> { &self.sig }
     /// Detect whether the articles are signed.
     pub fn is_signed(&self) -> bool { self.sig.is_some() }
+    /// Get the identity of the signer (the contract issuer) together with the signature over the
+    /// contract semantics, if the articles are signed.
+    pub fn signature(&self) -> Option<(&Identity, &SigBlob)> {
+        self.sig.as_ref().map(|sig| (&self.issue.meta.issuer, sig))
+    }
 
     /// Upgrades contract APIs if a newer version is available.
     ///
@@ -198,16 +212,48 @@ impl Articles {
         Ok(match (&self.sig, &other.sig) {
             (None, None) | (Some(_), Some(_)) if other.semantics.version > self.semantics.version => {
                 self.semantics = other.semantics;
+                self.apis_checksum = other.apis_checksum;
                 true
             }
             (None, Some(_)) => {
                 self.semantics = other.semantics;
+                self.apis_checksum = other.apis_checksum;
                 true
             }
             _ => false, // No upgrade
         })
     }
 
+    /// Reports differences between the semantics of these articles and some other (usually newer)
+    /// version of them, for instance, offered for [`Self::upgrade_apis`].
+    ///
+    /// The method doesn't check that both articles belong to the same contract.
+    pub fn diff_semantics(&self, other: &Articles) -> SemanticsDiff { self.semantics.diff(&other.semantics) }
+
+    /// Constructs articles for the same contract, retaining only the default API and, optionally,
+    /// a single named custom API, together with the libraries and types they use (see
+    /// [`Semantics::project`]).
+    ///
+    /// The contract id is not changed, since the APIs are not a part of it; however, the articles
+    /// id changes. Thus, the signature over the articles can't be retained, and the resulting
+    /// articles are unsigned.
+    ///
+    /// # Errors
+    ///
+    /// If there is no custom API with the provided name, or the resulting semantics doesn't pass
+    /// [`Semantics::check`].
+    pub fn project_api(&self, api_name: Option<&TypeName>) -> Result<Articles, SemanticError> {
+        let semantics = self.semantics.project(api_name)?;
+        semantics.check(&self.issue.codex)?;
+        let apis_checksum = semantics.apis_checksum();
+        Ok(Self {
+            semantics,
+            issue: self.issue.clone(),
+            sig: None,
+            apis_checksum,
+        })
+    }
+
     /// Get a [`CallId`] for a method from the default API.
     ///
     /// # Panics
@@ -260,3 +306,47 @@ impl SigBlob {
     /// If the slice length is zero or larger than 4096.
     pub fn from_vec_checked(data: Vec<u8>) -> SigBlob { Self(NonEmptyBlob::from_checked(data)) }
 }
+
+#[cfg(test)]
+mod test {
+    #![cfg_attr(coverage_nightly, coverage(off))]
+
+    use core::cell::Cell;
+    use core::convert::Infallible;
+
+    use super::*;
+    use crate::api::APIS_CHECKSUM_COMPUTATIONS;
+
+    #[test]
+    fn apis_checksum_computed_once() {
+        let codex: Codex = strict_dumb!();
+        let semantics = |version| Semantics {
+            version,
+            default: Api { codex_id: codex.codex_id(), ..strict_dumb!() },
+            ..strict_dumb!()
+        };
+        let issue = Issue { codex: codex.clone(), ..strict_dumb!() };
+        let articles = |version| {
+            Articles::with(semantics(version), issue.clone(), None, |_, _, _| Ok::<_, Infallible>(())).unwrap()
+        };
+        let computations = || APIS_CHECKSUM_COMPUTATIONS.with(Cell::get);
+
+        let checksum = semantics(0).apis_checksum();
+        let upgraded_checksum = semantics(1).apis_checksum();
+        assert_ne!(checksum, upgraded_checksum);
+
+        let start = computations();
+        let mut current = articles(0);
+        let upgraded = articles(1);
+        assert_eq!(computations(), start + 2);
+
+        for _ in 0..10 {
+            assert_eq!(current.apis_checksum(), checksum);
+            assert_eq!(current.articles_id().checksum, checksum);
+        }
+        assert!(current.upgrade_apis(upgraded).unwrap());
+        assert_eq!(current.apis_checksum(), upgraded_checksum);
+        assert_eq!(current.articles_id().checksum, upgraded_checksum);
+        assert_eq!(computations(), start + 2);
+    }
+}
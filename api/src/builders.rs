@@ -21,20 +21,27 @@
 // or implied. See the License for the specific language governing permissions and limitations under
 // the License.
 
+use std::collections::BTreeSet;
 use std::convert::Infallible;
 use std::ops::{Deref, DerefMut};
 
-use amplify::confinement::SmallVec;
+use aluvm::Lib;
+use amplify::confinement::{SmallOrdSet, SmallVec};
+use amplify::{ByteArray, Bytes32, Wrapper};
 use amplify::num::u256;
 use chrono::{DateTime, Utc};
-use strict_encoding::TypeName;
+use commit_verify::{CommitEngine, CommitId, CommitmentId, MerkleHash, ReservedBytes};
+use strict_encoding::{StrictEncode, StrictWriter, TypeName};
 use strict_types::{StrictVal, TypeSystem};
 use ultrasonic::{
     fe256, AuthToken, CallId, CellAddr, CellLock, CodexId, Consensus, ContractId, ContractMeta, ContractName, Genesis,
-    Identity, Input, Issue, Operation, StateCell, StateData, StateValue,
+    Identity, Input, Issue, Operation, Opid, StateCell, StateData, StateValue,
 };
 
-use crate::{Api, Articles, DataCell, Issuer, IssuerId, MethodName, StateAtom, StateName};
+use crate::{
+    Api, Articles, CallRequest, CallScope, CallState, DataCell, Issuer, IssuerId, Layer1, MethodName, StateAtom,
+    StateName, LIB_NAME_SONIC,
+};
 
 #[derive(Clone, PartialEq, Eq, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -76,6 +83,28 @@ impl CoreParams {
     ) {
         self.owned.push(NamedState::new_unlocked(name, auth, data));
     }
+
+    /// Checks that the parameters can be used to construct an operation.
+    ///
+    /// Each of the owned outputs must have a unique authority token, since otherwise the outputs
+    /// become indistinguishable for spending.
+    pub fn validate(&self) -> Result<(), CoreParamsError> {
+        let mut auths = BTreeSet::new();
+        for owned in &self.owned {
+            if !auths.insert(owned.state.auth) {
+                return Err(CoreParamsError::RepeatedAuthToken(owned.state.auth));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Errors detected by [`CoreParams::validate`].
+#[derive(Clone, Eq, PartialEq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum CoreParamsError {
+    /// authority token {0} is used by more than one owned output.
+    RepeatedAuthToken(AuthToken),
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
@@ -168,6 +197,42 @@ impl IssueParams {
 
     pub fn set_timestamp(&mut self, timestamp: DateTime<Utc>) { self.timestamp = Some(timestamp); }
 
+    /// Produces a call request representing the issue of a contract under the provided codex.
+    ///
+    /// Since the contract id is not known before the issue, the request scope is a contract query
+    /// in form of `codex:<codex_id>` (without the id mnemonic). The request calls the issue method;
+    /// its beneficiary is the first of the owned state entries, if any, providing the state name,
+    /// the authority token and the value. If there is no owned state, a zero authority token is
+    /// used.
+    ///
+    /// # Nota bene
+    ///
+    /// The request doesn't specify an API name, which means the default API. Since the URI form of
+    /// a request requires an API name for a method call, it must be set before converting the
+    /// request into a URI.
+    pub fn to_request(&self, codex_id: CodexId) -> CallRequest<CallScope, AuthToken> {
+        let owned = self.core.owned.first();
+        let call = match owned {
+            Some(owned) => CallState::with(self.core.method.clone(), owned.name.clone()),
+            None => CallState::new(self.core.method.clone()),
+        };
+        CallRequest {
+            scope: CallScope::ContractQuery(format!("{codex_id:-#}")),
+            layer1: Layer1::new(self.consensus, self.testnet),
+            api: None,
+            call: Some(call),
+            auth: owned.map(|owned| owned.state.auth).unwrap_or(AuthToken::from([0u8; 30])),
+            data: owned
+                .map(|owned| owned.state.data.clone())
+                .into_iter()
+                .collect(),
+            lock: None,
+            expiry: None,
+            endpoints: none!(),
+            unknown_query: none!(),
+        }
+    }
+
     pub fn set_timestamp_now(&mut self) { self.timestamp = Some(Utc::now()); }
 }
 
@@ -184,20 +249,42 @@ impl Issuer {
         self.start_issue(method, consensus, true)
     }
 
+    /// Starts an issue which computes just the contract id, without retaining genesis state in
+    /// memory; see [`StreamingIssueBuilder`] for the details.
+    pub fn start_issue_streaming(
+        self,
+        method: impl Into<MethodName>,
+        consensus: Consensus,
+        testnet: bool,
+    ) -> StreamingIssueBuilder {
+        StreamingIssueBuilder {
+            call_id: self.call_id(method),
+            issuer: self,
+            testnet,
+            consensus,
+            destructible_out: none!(),
+            immutable_out: none!(),
+        }
+    }
+
+    /// Issues a contract with the provided parameters.
+    ///
+    /// # Panics
+    ///
+    /// If the issuer doesn't match the one requested by the parameters, or if the parameters are
+    /// not valid (see [`CoreParams::validate`]).
     pub fn issue(self, params: IssueParams) -> Articles {
         if !params.issuer.check(self.issuer_id()) {
             panic!("issuer version does not match requested version");
         }
-
-        let mut builder = self.start_issue(params.core.method, params.consensus, params.testnet);
-
-        for NamedState { name, state } in params.core.global {
-            builder = builder.append(name, state.verified, state.unverified)
-        }
-        for NamedState { name, state } in params.core.owned {
-            builder = builder.assign(name, state.auth, state.data, state.lock)
+        if let Err(err) = params.core.validate() {
+            panic!("invalid issue parameters: {err}");
         }
 
+        let builder = self
+            .start_issue(params.core.method, params.consensus, params.testnet)
+            .from_snapshot(params.core.global, params.core.owned);
+
         let timestamp = params.timestamp.unwrap_or_else(Utc::now).timestamp();
         builder.finish(params.name, timestamp)
     }
@@ -213,9 +300,9 @@ pub struct IssueBuilder {
 
 impl IssueBuilder {
     pub fn append(mut self, name: impl Into<StateName>, data: StrictVal, raw: Option<StrictVal>) -> Self {
-        self.builder = self
-            .builder
-            .add_global(name, data, raw, self.issuer.default_api(), self.issuer.types());
+        let semantics = self.issuer.semantics();
+        let (api, libs, sys) = (&semantics.default, &semantics.api_libs, &semantics.types);
+        self.builder = self.builder.add_global(name, data, raw, api, libs, sys);
         self
     }
 
@@ -226,9 +313,31 @@ impl IssueBuilder {
         data: StrictVal,
         lock: Option<CellLock>,
     ) -> Self {
+        let semantics = self.issuer.semantics();
+        let (api, libs, sys) = (&semantics.default, &semantics.api_libs, &semantics.types);
         self.builder = self
             .builder
-            .add_owned(name, auth, data, lock, self.issuer.default_api(), self.issuer.types());
+            .add_owned(name, auth, data, lock, api, libs, sys);
+        self
+    }
+
+    /// Adds to the genesis a snapshot of already decoded global and owned state, for instance
+    /// imported from an external source.
+    ///
+    /// This is equivalent to calling [`Self::append`] for each of the `global` and
+    /// [`Self::assign`] for each of the `owned` state entries, in the order they are provided.
+    ///
+    /// # Panics
+    ///
+    /// If any of the state entries is not valid under the default issuer API.
+    #[allow(clippy::wrong_self_convention)]
+    pub fn from_snapshot(mut self, global: Vec<NamedState<StateAtom>>, owned: Vec<NamedState<DataCell>>) -> Self {
+        for NamedState { name, state } in global {
+            self = self.append(name, state.verified, state.unverified)
+        }
+        for NamedState { name, state } in owned {
+            self = self.assign(name, state.auth, state.data, state.lock)
+        }
         self
     }
 
@@ -249,6 +358,130 @@ impl IssueBuilder {
     }
 }
 
+/// Issue builder which doesn't retain genesis state in memory, computing only the contract id.
+///
+/// Each of the added state elements is committed to as soon as it is built, such that the builder
+/// keeps just a 32-byte merkle leaf hash per genesis output. This allows computing the id for a
+/// contract with a large genesis without holding all of its [`StateCell`]s and [`StateData`].
+///
+/// The resulting contract id is the same as the one of the [`Articles`] produced by
+/// [`IssueBuilder::finish`] for the same state and parameters.
+#[derive(Clone, Debug)]
+pub struct StreamingIssueBuilder {
+    call_id: CallId,
+    issuer: Issuer,
+    testnet: bool,
+    consensus: Consensus,
+    destructible_out: Vec<MerkleHash>,
+    immutable_out: Vec<MerkleHash>,
+}
+
+impl StreamingIssueBuilder {
+    pub fn append(mut self, name: impl Into<StateName>, data: StrictVal, raw: Option<StrictVal>) -> Self {
+        let name = name.into();
+        let data = self
+            .issuer
+            .default_api()
+            .build_immutable(name.clone(), data, raw, &self.issuer.semantics().api_libs, self.issuer.types())
+            .unwrap_or_else(|e| panic!("invalid immutable state '{name}'; {e}"));
+        self.immutable_out.push(data.commit_id());
+        self
+    }
+
+    pub fn assign(
+        mut self,
+        name: impl Into<StateName>,
+        auth: AuthToken,
+        data: StrictVal,
+        lock: Option<CellLock>,
+    ) -> Self {
+        let data = self
+            .issuer
+            .default_api()
+            .build_destructible(name, data, &self.issuer.semantics().api_libs, self.issuer.types())
+            .expect("invalid destructible state");
+        let cell = StateCell { data, auth, lock };
+        self.destructible_out.push(cell.commit_id());
+        self
+    }
+
+    /// Completes the issue, returning the id of the issued contract.
+    ///
+    /// # Panics
+    ///
+    /// If the number of genesis outputs exceeds the limit of the genesis operation.
+    pub fn finish(self, name: impl Into<TypeName>, timestamp: i64) -> ContractId {
+        if self.destructible_out.len() > u16::MAX as usize || self.immutable_out.len() > u16::MAX as usize {
+            panic!("too many state elements");
+        }
+        let meta = ContractMeta {
+            consensus: self.consensus,
+            testnet: self.testnet,
+            timestamp,
+            features: default!(),
+            name: ContractName::Named(name.into()),
+            issuer: Identity::default(),
+        };
+
+        // Upstream APIs can't commit to pre-hashed merkle leaves, so the commitments below mirror
+        // `CommitEncode` implementations for `Operation` and `Issue` from `ultrasonic` v0.12.0
+        // (with the genesis-specific contract id, nonce and inputs). Any change to them must be
+        // reflected here; the `issue_streaming` test checks both produce the same contract ids.
+        let mut engine = CommitEngine::new(Opid::TAG);
+        engine.commit_to_serialized(&ReservedBytes::<1>::default());
+        engine.commit_to_serialized(&ContractId::from_byte_array([0xFFu8; 32]));
+        engine.commit_to_serialized(&self.call_id);
+        engine.commit_to_serialized(&fe256::from(u256::ZERO));
+        engine.commit_to_serialized(&StateValue::None);
+        engine.commit_to_merkle(&SmallVec::<Input>::new());
+        engine.commit_to_merkle(&SmallVec::<CellAddr>::new());
+        engine.commit_to_serialized(&merkle_root(self.destructible_out));
+        engine.commit_to_serialized(&merkle_root(self.immutable_out));
+        engine.set_finished();
+        let genesis_id = Opid::from(engine.finish());
+
+        let mut engine = CommitEngine::new(ContractId::TAG);
+        engine.commit_to_serialized(&ReservedBytes::<1>::default());
+        engine.commit_to_serialized(&meta);
+        engine.commit_to_serialized(&self.issuer.codex_id());
+        engine.commit_to_serialized(&genesis_id);
+        engine.set_finished();
+        ContractId::from(engine.finish())
+    }
+}
+
+/// Merkle root of a collection, serialized into a commitment in the same way as
+/// [`CommitEngine::commit_to_merkle`] does.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode)]
+#[strict_type(lib = LIB_NAME_SONIC)]
+struct MerkleRoot(Bytes32);
+
+/// Computes merkle root from the leaf hashes in the same way as [`MerkleHash::merklize`] from
+/// `commit_verify` v0.12.0 does.
+fn merkle_root(leaves: Vec<MerkleHash>) -> MerkleRoot {
+    fn merklize(leaves: &[MerkleHash], depth: u8, base_width: u32) -> MerkleHash {
+        match leaves {
+            [] => MerkleHash::void(depth, base_width),
+            [leaf] => MerkleHash::single(depth, base_width, *leaf),
+            [leaf1, leaf2] => MerkleHash::branches(depth, base_width, *leaf1, *leaf2),
+            _ => {
+                let div = leaves.len() / 2 + leaves.len() % 2;
+                let branch1 = merklize(&leaves[..div], depth + 1, base_width);
+                let branch2 = merklize(&leaves[div..], depth + 1, base_width);
+                MerkleHash::branches(depth, base_width, branch1, branch2)
+            }
+        }
+    }
+
+    let base_width = leaves.len() as u32;
+    let root = match leaves.as_slice() {
+        [leaf] => *leaf,
+        _ => merklize(&leaves, 0, base_width),
+    };
+    MerkleRoot(root.into_inner())
+}
+
 #[derive(Clone, Debug)]
 pub struct Builder {
     call_id: CallId,
@@ -259,17 +492,19 @@ pub struct Builder {
 impl Builder {
     pub fn new(call_id: CallId) -> Self { Builder { call_id, destructible_out: none!(), immutable_out: none!() } }
 
-    pub fn add_global(
+    #[allow(clippy::too_many_arguments)]
+    pub fn add_global<'libs>(
         mut self,
         name: impl Into<StateName>,
         data: StrictVal,
         raw: Option<StrictVal>,
         api: &Api,
+        libs: impl IntoIterator<Item = &'libs Lib>,
         sys: &TypeSystem,
     ) -> Self {
         let name = name.into();
         let data = api
-            .build_immutable(name.clone(), data, raw, sys)
+            .build_immutable(name.clone(), data, raw, libs, sys)
             .unwrap_or_else(|e| panic!("invalid immutable state '{name}'; {e}"));
         self.immutable_out
             .push(data)
@@ -277,17 +512,19 @@ impl Builder {
         self
     }
 
-    pub fn add_owned(
+    #[allow(clippy::too_many_arguments)]
+    pub fn add_owned<'libs>(
         mut self,
         name: impl Into<StateName>,
         auth: AuthToken,
         data: StrictVal,
         lock: Option<CellLock>,
         api: &Api,
+        libs: impl IntoIterator<Item = &'libs Lib>,
         sys: &TypeSystem,
     ) -> Self {
         let data = api
-            .build_destructible(name, data, sys)
+            .build_destructible(name, data, libs, sys)
             .expect("invalid destructible state");
         let cell = StateCell { data, auth, lock };
         self.destructible_out
@@ -315,18 +552,19 @@ impl Builder {
 pub struct BuilderRef<'c> {
     type_system: &'c TypeSystem,
     api: &'c Api,
+    libs: &'c SmallOrdSet<Lib>,
     inner: Builder,
 }
 
 impl<'c> BuilderRef<'c> {
-    pub fn new(api: &'c Api, call_id: CallId, sys: &'c TypeSystem) -> Self {
-        BuilderRef { type_system: sys, api, inner: Builder::new(call_id) }
+    pub fn new(api: &'c Api, libs: &'c SmallOrdSet<Lib>, call_id: CallId, sys: &'c TypeSystem) -> Self {
+        BuilderRef { type_system: sys, api, libs, inner: Builder::new(call_id) }
     }
 
     pub fn add_global(mut self, name: impl Into<StateName>, data: StrictVal, raw: Option<StrictVal>) -> Self {
         self.inner = self
             .inner
-            .add_global(name, data, raw, self.api, self.type_system);
+            .add_global(name, data, raw, self.api, self.libs, self.type_system);
         self
     }
 
@@ -339,7 +577,7 @@ impl<'c> BuilderRef<'c> {
     ) -> Self {
         self.inner = self
             .inner
-            .add_owned(name, auth, data, lock, self.api, self.type_system);
+            .add_owned(name, auth, data, lock, self.api, self.libs, self.type_system);
         self
     }
 
@@ -349,6 +587,7 @@ impl<'c> BuilderRef<'c> {
 #[derive(Clone, Debug)]
 pub struct OpBuilder {
     contract_id: ContractId,
+    nonce: fe256,
     destructible_in: SmallVec<Input>,
     immutable_in: SmallVec<CellAddr>,
     inner: Builder,
@@ -359,34 +598,48 @@ impl OpBuilder {
         let inner = Builder::new(call_id);
         Self {
             contract_id,
+            nonce: fe256::from(u256::ZERO),
             destructible_in: none!(),
             immutable_in: none!(),
             inner,
         }
     }
 
-    pub fn add_global(
+    /// Sets the operation nonce, which defaults to zero.
+    ///
+    /// The nonce is committed to in the operation id; thus it allows producing operations with
+    /// distinct ids despite them having identical content.
+    pub fn nonce(mut self, nonce: fe256) -> Self {
+        self.nonce = nonce;
+        self
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn add_global<'libs>(
         mut self,
         name: impl Into<StateName>,
         data: StrictVal,
         raw: Option<StrictVal>,
         api: &Api,
+        libs: impl IntoIterator<Item = &'libs Lib>,
         sys: &TypeSystem,
     ) -> Self {
-        self.inner = self.inner.add_global(name, data, raw, api, sys);
+        self.inner = self.inner.add_global(name, data, raw, api, libs, sys);
         self
     }
 
-    pub fn add_owned(
+    #[allow(clippy::too_many_arguments)]
+    pub fn add_owned<'libs>(
         mut self,
         name: impl Into<StateName>,
         auth: AuthToken,
         data: StrictVal,
         lock: Option<CellLock>,
         api: &Api,
+        libs: impl IntoIterator<Item = &'libs Lib>,
         sys: &TypeSystem,
     ) -> Self {
-        self.inner = self.inner.add_owned(name, auth, data, lock, api, sys);
+        self.inner = self.inner.add_owned(name, auth, data, lock, api, libs, sys);
         self
     }
 
@@ -405,16 +658,17 @@ impl OpBuilder {
         self
     }
 
-    pub fn destroy_satisfy(
+    pub fn destroy_satisfy<'libs>(
         mut self,
         addr: CellAddr,
         name: impl Into<StateName>,
         witness: StrictVal,
         api: &Api,
+        libs: impl IntoIterator<Item = &'libs Lib>,
         sys: &TypeSystem,
     ) -> Self {
         let witness = api
-            .build_witness(name, witness, sys)
+            .build_witness(name, witness, libs, sys)
             .expect("invalid witness data");
         let input = Input { addr, witness };
         self.destructible_in
@@ -428,7 +682,7 @@ impl OpBuilder {
             version: default!(),
             contract_id: self.contract_id,
             call_id: self.inner.call_id,
-            nonce: fe256::from(u256::ZERO),
+            nonce: self.nonce,
             witness: none!(),
             destructible_in: self.destructible_in,
             immutable_in: self.immutable_in,
@@ -442,19 +696,26 @@ impl OpBuilder {
 pub struct OpBuilderRef<'c> {
     type_system: &'c TypeSystem,
     api: &'c Api,
+    libs: &'c SmallOrdSet<Lib>,
     inner: OpBuilder,
 }
 
 impl<'c> OpBuilderRef<'c> {
-    pub fn new(api: &'c Api, contract_id: ContractId, call_id: CallId, sys: &'c TypeSystem) -> Self {
+    pub fn new(
+        api: &'c Api,
+        libs: &'c SmallOrdSet<Lib>,
+        contract_id: ContractId,
+        call_id: CallId,
+        sys: &'c TypeSystem,
+    ) -> Self {
         let inner = OpBuilder::new(contract_id, call_id);
-        Self { api, type_system: sys, inner }
+        Self { api, libs, type_system: sys, inner }
     }
 
     pub fn add_global(mut self, name: impl Into<StateName>, data: StrictVal, raw: Option<StrictVal>) -> Self {
         self.inner = self
             .inner
-            .add_global(name, data, raw, self.api, self.type_system);
+            .add_global(name, data, raw, self.api, self.libs, self.type_system);
         self
     }
 
@@ -467,7 +728,15 @@ impl<'c> OpBuilderRef<'c> {
     ) -> Self {
         self.inner = self
             .inner
-            .add_owned(name, auth, data, lock, self.api, self.type_system);
+            .add_owned(name, auth, data, lock, self.api, self.libs, self.type_system);
+        self
+    }
+
+    /// Sets the operation nonce, which defaults to zero.
+    ///
+    /// See [`OpBuilder::nonce`] for the details.
+    pub fn nonce(mut self, nonce: fe256) -> Self {
+        self.inner = self.inner.nonce(nonce);
         self
     }
 
@@ -481,21 +750,43 @@ impl<'c> OpBuilderRef<'c> {
         self
     }
 
-    pub fn destroy_satisfy(
+    pub fn destroy_satisfy<'libs>(
         mut self,
         addr: CellAddr,
         name: impl Into<StateName>,
         witness: StrictVal,
         api: &Api,
+        libs: impl IntoIterator<Item = &'libs Lib>,
         sys: &TypeSystem,
     ) -> Self {
-        self.inner = self.inner.destroy_satisfy(addr, name, witness, api, sys);
+        self.inner = self
+            .inner
+            .destroy_satisfy(addr, name, witness, api, libs, sys);
         self
     }
 
     pub fn finalize(self) -> Operation { self.inner.finalize() }
 }
 
+/// Size of an operation in its strict-serialized form, which may be used as an operation weight by
+/// fee policies.
+pub trait OperationSize {
+    /// Computes the size of the strict-serialized operation by summing the encoded sizes of its
+    /// fields, without serializing it into a buffer.
+    fn strict_size(&self) -> usize;
+}
+
+impl OperationSize for Operation {
+    fn strict_size(&self) -> usize {
+        let counter = StrictWriter::counter::<{ usize::MAX }>();
+        self.strict_encode(counter)
+            .expect("counting writer doesn't error")
+            .unbox()
+            .unconfine()
+            .count
+    }
+}
+
 #[cfg(test)]
 mod test {
     #![cfg_attr(coverage_nightly, coverage(off))]
@@ -611,4 +902,53 @@ version:
         assert!(IssuerSpec::Latest(issuer_id.codex_id).check(changed_sum));
         assert!(IssuerSpec::ExactVer { codex_id: issuer_id.codex_id, version: issuer_id.version }.check(changed_sum));
     }
+
+    #[test]
+    fn operation_strict_size() {
+        let op = Operation {
+            version: default!(),
+            contract_id: ContractId::from_byte_array([0xAC; 32]),
+            call_id: 2,
+            nonce: fe256::from(7u8),
+            witness: StateValue::new(1u8, 100u64),
+            destructible_in: small_vec![Input {
+                addr: CellAddr::new(Opid::from_byte_array([0x01; 32]), 3),
+                witness: StateValue::None,
+            }],
+            immutable_in: small_vec![CellAddr::new(Opid::from_byte_array([0x02; 32]), 0)],
+            destructible_out: small_vec![
+                StateCell { data: StateValue::new(0u8, 50u64), auth: AuthToken::from([0xA1; 30]), lock: None },
+                StateCell {
+                    data: StateValue::new(0u8, 50u64),
+                    auth: AuthToken::from([0xA2; 30]),
+                    lock: Some(CellLock { aux: StateValue::Single { first: fe256::from(1u8) }, script: None }),
+                },
+            ],
+            immutable_out: small_vec![StateData {
+                value: StateValue::new(1u8, 5u64),
+                raw: Some(ultrasonic::RawData::from(small_blob!(0xCA))),
+            }],
+        };
+
+        let writer = StrictWriter::in_memory::<{ usize::MAX }>();
+        let data = op.strict_encode(writer).unwrap().unbox().unconfine();
+        assert_eq!(op.strict_size(), data.len());
+    }
+
+    #[test]
+    fn operation_nonce() {
+        let contract_id = ContractId::from_byte_array([0xAC; 32]);
+        let builder = OpBuilder::new(contract_id, 1).destroy(CellAddr::new(Opid::from_byte_array([0x01; 32]), 0));
+
+        let default = builder.clone().finalize();
+        let zero = builder.clone().nonce(fe256::from(u256::ZERO)).finalize();
+        let first = builder.clone().nonce(fe256::from(1u8)).finalize();
+        let second = builder.nonce(fe256::from(2u8)).finalize();
+
+        assert_eq!(default.opid(), zero.opid());
+        assert_eq!(first.nonce, fe256::from(1u8));
+        assert_ne!(first.opid(), default.opid());
+        assert_ne!(first.opid(), second.opid());
+        assert_eq!(first.destructible_in, second.destructible_in);
+    }
 }
@@ -57,10 +57,14 @@ mod articles;
 mod builders;
 mod state;
 
-pub use api::{Api, ApisChecksum, GlobalApi, OwnedApi, ParseVersionedError, SemanticError, Semantics, StateUnknown};
+pub use api::{
+    Api, ApiDiff, ApisChecksum, GlobalApi, OwnedApi, ParseVersionedError, SemanticError, Semantics, SemanticsDiff,
+    StateUnknown,
+};
 pub use articles::{Articles, ArticlesId, SigBlob};
 pub use builders::{
-    Builder, BuilderRef, CoreParams, IssueParams, IssuerSpec, NamedState, OpBuilder, OpBuilderRef, VersionRange,
+    Builder, BuilderRef, CoreParams, CoreParamsError, IssueBuilder, IssueParams, IssuerSpec, NamedState, OpBuilder,
+    OpBuilderRef, OperationSize, StreamingIssueBuilder, VersionRange,
 };
 pub use issuer::{Issuer, IssuerId, ISSUER_MAGIC_NUMBER, ISSUER_VERSION};
 pub use sonic_callreq::*;
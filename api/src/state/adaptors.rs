@@ -21,21 +21,44 @@
 // or implied. See the License for the specific language governing permissions and limitations under
 // the License.
 
-use std::io;
+use std::io::Cursor;
 
-use aluvm::LibSite;
+use aluvm::regs::Status;
+use aluvm::{CoreConfig, CoreExt, Lib, LibId, LibSite, Vm};
 use amplify::confinement::{Confined, ConfinedBlob};
 use amplify::num::u256;
+use indexmap::IndexMap;
 use sonic_callreq::StateName;
 use strict_encoding::{SerializeError, StreamReader};
 use strict_types::value::{EnumTag, StrictNum};
 use strict_types::{decode, typify, Cls, SemId, StrictVal, Ty, TypeSystem};
-use ultrasonic::StateValue;
+use ultrasonic::aluvm::{GfaConfig, RegE};
+use ultrasonic::{Instr, StateValue, VmContext};
 
-use crate::{fe256, StateTy, LIB_NAME_SONIC};
+use crate::{fe256, StateTy, LIB_NAME_SONIC, MAX_RAW_BYTES};
 
+/// Number of data bytes stored in a single field element.
+///
+/// The two most significant bytes are not used, such that the value always fits into the field
+/// order.
 pub(super) const USED_FIEL_BYTES: usize = u256::BYTES as usize - 2;
+/// Maximal size of the state data which can be stored in a [`StateValue`].
+///
+/// A state value has at most four field elements ([`StateValue::Quadruple`]), and the first of
+/// them is reserved for the state type, which is used by the convertors to detect which state the
+/// value belongs to; thus only three field elements remain for the data.
 pub(super) const MAX_BYTES: usize = USED_FIEL_BYTES * 3;
+/// Position of the state type bits in the first field element of [`StateBuilder::CompactTyped`]
+/// state.
+const COMPACT_TY_SHIFT: usize = USED_FIEL_BYTES * 8;
+/// Number of bits in a field element which can be used by [`StateConvertor::BitRange`].
+const FIELD_BITS: u16 = 254;
+/// Maximal length of a string stored by [`StateBuilder::Ascii`] in a single field element.
+const MAX_ASCII_LEN: u8 = USED_FIEL_BYTES as u8;
+
+/// Maximal computational complexity of [`StateConvertor::AluVM`] and [`StateBuilder::AluVM`]
+/// scripts.
+const ADAPTOR_COMPLEXITY_LIMIT: u64 = 1_000_000;
 
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 #[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
@@ -50,12 +73,44 @@ pub enum StateConvertor {
 
     #[strict_type(tag = 0x11)]
     TypedFieldEncoder(StateTy),
-    // In the future we can add more adaptors:
-    // - doing more compact encoding (storing state type in bits, not using a full field element);
-    // - using just a specific range of field element bits, not a full value - such that multiple APIs may read
-    //   different parts of the same data;
+
+    /// Decodes an enum stored as its variant tag (ordinal) in a single field element, checking the
+    /// tag against the enum variants defined in the type system.
+    #[strict_type(tag = 0x12)]
+    Enum(StateTy),
+
+    /// Decodes a fixed-size array of elements of a given type, which are densely packed into the
+    /// field elements following the state type, into a [`StrictVal::List`].
+    #[strict_type(tag = 0x13)]
+    FixedArray(StateTy, SemId, u8),
+
+    /// Decodes the data from a range of `len` bits starting at bit `offset` of the field element
+    /// following the state type, ignoring the rest of the bits.
+    ///
+    /// This allows multiple APIs to read different parts of the same state value.
+    #[strict_type(tag = 0x14)]
+    BitRange { ty: StateTy, offset: u16, len: u16 },
+
+    /// Decodes the data stored in a compact form by [`StateBuilder::CompactTyped`], where the state
+    /// type occupies the most significant bits of the first field element instead of a whole field
+    /// element.
+    #[strict_type(tag = 0x15)]
+    CompactTyped(u16),
+
+    /// Decodes a printable ASCII string of at most `max_len` characters stored by
+    /// [`StateBuilder::Ascii`] into a [`StrictVal::String`].
+    #[strict_type(tag = 0x16)]
+    Ascii(StateTy, u8),
+
     /// Execute a custom function.
-    // AluVM is reserved for the future. We need it here to avoid breaking changes.
+    ///
+    /// The script receives the state value as a witness, which can be loaded into `EA`-`ED`
+    /// registers with `ldw` instruction. On successful completion, the script must put the
+    /// strict-serialized state data into `EA`-`EC` registers, 30 bytes per register, leaving the
+    /// registers following the data unset. The data are then deserialized using the state semantic
+    /// type.
+    ///
+    /// If the script fails, the state is considered to be not related to the convertor.
     #[strict_type(tag = 0xFF)]
     AluVM(
         /// The entry point to the script (virtual machine uses libraries from
@@ -65,18 +120,27 @@ pub enum StateConvertor {
 }
 
 impl StateConvertor {
-    pub fn convert(
+    pub fn convert<'libs>(
         &self,
         sem_id: SemId,
         value: StateValue,
+        libs: impl IntoIterator<Item = &'libs Lib>,
         sys: &TypeSystem,
     ) -> Result<Option<StrictVal>, StateConvertError> {
         match self {
             Self::Unit if StateValue::None == value => Ok(Some(StrictVal::Unit)),
-            Self::Unit => Err(StateConvertError::UnitState),
+            Self::Unit => {
+                let ty = value.get(0).map(|el| el.to_u256()).unwrap_or_default();
+                Err(StateConvertError::NonUnitState(ty))
+            }
             Self::TypedEncoder(ty) => typed_convert(*ty, sem_id, value, sys),
             Self::TypedFieldEncoder(ty) => typed_field_convert(*ty, sem_id, value, sys),
-            Self::AluVM(_) => Err(StateConvertError::Unsupported),
+            Self::Enum(ty) => enum_convert(*ty, sem_id, value, sys),
+            Self::FixedArray(ty, elem, count) => array_convert(*ty, *elem, *count, value, sys),
+            Self::BitRange { ty, offset, len } => bits_convert(*ty, *offset, *len, sem_id, value, sys),
+            Self::CompactTyped(ty) => compact_convert(*ty, sem_id, value, sys),
+            Self::Ascii(ty, max_len) => ascii_convert(*ty, *max_len, value),
+            Self::AluVM(entry) => aluvm_convert(*entry, sem_id, value, libs, sys),
         }
     }
 }
@@ -94,10 +158,48 @@ pub enum StateBuilder {
 
     #[strict_type(tag = 0x11)]
     TypedFieldEncoder(StateTy),
-    // In the future we can add more adaptors:
-    // - doing more compact encoding (storing state type in bits, not using a full field element);
+
+    /// Stores an enum as its variant tag (ordinal) in a single field element, checking the tag
+    /// against the enum variants defined in the type system.
+    #[strict_type(tag = 0x12)]
+    Enum(StateTy),
+
+    /// Densely packs a fixed-size array of elements of a given type, provided as a
+    /// [`StrictVal::List`], into the field elements following the state type.
+    #[strict_type(tag = 0x13)]
+    FixedArray(StateTy, SemId, u8),
+
+    /// Stores the data in a range of `len` bits starting at bit `offset` of the field element
+    /// following the state type, leaving the rest of the bits zeroed.
+    #[strict_type(tag = 0x14)]
+    BitRange { ty: StateTy, offset: u16, len: u16 },
+
+    /// Strict-serializes the data, like [`Self::TypedEncoder`] does, but stores the state type in
+    /// the two most significant bytes of the first field element (which are not used by the data),
+    /// instead of a separate field element.
+    ///
+    /// This saves a field element per state value, at the cost of limiting the state type to 16
+    /// bits; the data capacity remains the same as of [`Self::TypedEncoder`]. For the fields with
+    /// an order below 2^256 the state type must be small enough for the first field element to fit
+    /// into the field order.
+    #[strict_type(tag = 0x15)]
+    CompactTyped(u16),
+
+    /// Packs a string of at most `max_len` characters, provided as a [`StrictVal::String`], into
+    /// the field element following the state type, one byte per character.
+    ///
+    /// The string must consist of printable ASCII characters only (like tickers or codes), and the
+    /// `max_len` must not exceed 30 characters, which fit into a single field element. The semantic
+    /// type of the state is not used by the encoding.
+    #[strict_type(tag = 0x16)]
+    Ascii(StateTy, u8),
+
     /// Execute a custom function.
-    // AluVM is reserved for the future. We need it here to avoid breaking changes.
+    ///
+    /// The script receives the strict-serialized state data as a witness, 30 bytes per field
+    /// element, which can be loaded into `EA`-`ED` registers with `ldw` instruction. On successful
+    /// completion, the script must put the resulting state value into `EA`-`ED` registers, leaving
+    /// the registers following the value unset.
     #[strict_type(tag = 0xFF)]
     AluVM(
         /// The entry point to the script (virtual machine uses libraries from
@@ -107,18 +209,60 @@ pub enum StateBuilder {
 }
 
 impl StateBuilder {
+    /// Constructs a convertor which is able to decode the state produced by this builder.
+    ///
+    /// For [`Self::AluVM`] the convertor runs the same script, thus the script must be its own
+    /// inverse (like an identity function) for the state to round-trip.
+    pub fn to_convertor(&self) -> StateConvertor {
+        match self {
+            Self::Unit => StateConvertor::Unit,
+            Self::TypedEncoder(ty) => StateConvertor::TypedEncoder(*ty),
+            Self::TypedFieldEncoder(ty) => StateConvertor::TypedFieldEncoder(*ty),
+            Self::Enum(ty) => StateConvertor::Enum(*ty),
+            Self::FixedArray(ty, elem, count) => StateConvertor::FixedArray(*ty, *elem, *count),
+            Self::BitRange { ty, offset, len } => StateConvertor::BitRange { ty: *ty, offset: *offset, len: *len },
+            Self::CompactTyped(ty) => StateConvertor::CompactTyped(*ty),
+            Self::Ascii(ty, max_len) => StateConvertor::Ascii(*ty, *max_len),
+            Self::AluVM(entry) => StateConvertor::AluVM(*entry),
+        }
+    }
+
     #[allow(clippy::result_large_err)]
-    pub fn build(&self, sem_id: SemId, value: StrictVal, sys: &TypeSystem) -> Result<StateValue, StateBuildError> {
+    pub fn build<'libs>(
+        &self,
+        sem_id: SemId,
+        value: StrictVal,
+        libs: impl IntoIterator<Item = &'libs Lib>,
+        sys: &TypeSystem,
+    ) -> Result<StateValue, StateBuildError> {
+        // Enum ordinals are checked against the type before typification, which would otherwise
+        // report a less specific error.
+        if let Self::Enum(ty) = self {
+            return enum_build(*ty, sem_id, value, sys);
+        }
+        // Array elements are typified one by one, since the state type is the element type.
+        if let Self::FixedArray(ty, elem, count) = self {
+            return array_build(*ty, *elem, *count, value, sys);
+        }
+        // ASCII strings are validated by the encoding itself, independently of the semantic type.
+        if let Self::Ascii(ty, max_len) = self {
+            return ascii_build(*ty, *max_len, value);
+        }
         let typed = sys.typify(value.clone(), sem_id)?;
         Ok(match self {
             Self::Unit if typed.as_val() == &StrictVal::Unit => StateValue::None,
-            Self::Unit => return Err(StateBuildError::InvalidUnit),
-            Self::TypedEncoder(ty) => {
-                let ser = sys.strict_serialize_value::<MAX_BYTES>(&typed)?;
-                typed_build(*ty, ser)
-            }
+            Self::Unit => return Err(StateBuildError::InvalidUnit(value)),
+            Self::TypedEncoder(ty) => typed_build(*ty, serialize(&typed, sys)?),
             Self::TypedFieldEncoder(ty) => typed_field_build(*ty, value)?,
-            Self::AluVM(_) => return Err(StateBuildError::Unsupported),
+            Self::BitRange { ty, offset, len } => bits_build(*ty, *offset, *len, serialize(&typed, sys)?)?,
+            Self::CompactTyped(ty) => compact_build(*ty, serialize(&typed, sys)?),
+            Self::Enum(_) | Self::FixedArray(..) | Self::Ascii(..) => {
+                unreachable!("state is built before typification")
+            }
+            Self::AluVM(entry) => {
+                let data = packed_build(serialize(&typed, sys)?);
+                run_script(*entry, data, libs).ok_or(StateBuildError::ScriptFailure)?
+            }
         })
     }
 }
@@ -132,9 +276,11 @@ pub enum StateBuildError {
     #[from]
     Typify(typify::Error),
 
-    #[from(io::Error)]
-    #[display("state data is too large to be encoded")]
-    TooLarge,
+    #[display("state data is too large to be encoded ({0} bytes, while at most {MAX_BYTES} bytes are allowed)")]
+    TooLarge(usize),
+
+    #[display("raw state data is too large ({0} bytes, while at most {MAX_RAW_BYTES} bytes are allowed)")]
+    RawTooLarge(usize),
 
     #[display("state data ({0:?}) have an unsupported type for the encoding")]
     UnsupportedValue(StrictVal),
@@ -142,11 +288,38 @@ pub enum StateBuildError {
     #[from]
     Serialize(SerializeError),
 
-    #[display("the provided value doesn't match the required unit type")]
-    InvalidUnit,
+    #[display("the provided value ({0:?}) doesn't match the required unit type")]
+    InvalidUnit(StrictVal),
+
+    #[display("type {0} is not an enum")]
+    NotEnum(SemId),
+
+    #[display("enum variant {0:?} is not defined by the enum type")]
+    InvalidEnumOrdinal(EnumTag),
+
+    #[display("fixed-size array requires {expected} elements, while {found} elements were provided")]
+    ArrayLenMismatch { expected: u8, found: usize },
+
+    #[display("invalid range of {len} bits at offset {offset}, exceeding {FIELD_BITS} bits or empty")]
+    InvalidBitRange { offset: u16, len: u16 },
+
+    #[display("state data don't fit into {0} bits")]
+    BitRangeOverflow(u16),
+
+    #[display("ASCII string length limit {0} exceeds {MAX_ASCII_LEN} characters")]
+    InvalidAsciiLen(u8),
+
+    #[display("string {0:?} contains characters which are not printable ASCII")]
+    NonAscii(String),
+
+    #[display("string of {len} characters exceeds the limit of {max} characters")]
+    AsciiTooLong { len: usize, max: u8 },
 
     #[display("AluVM is not yet supported for a state builder.")]
     Unsupported,
+
+    #[display("AluVM state builder script has failed")]
+    ScriptFailure,
 }
 
 #[derive(Clone, Eq, PartialEq, Debug, Display, Error, From)]
@@ -164,21 +337,69 @@ pub enum StateConvertError {
     #[display("state has no data")]
     UnitState,
 
+    #[display("state of type {0} is provided where a unit state is expected")]
+    NonUnitState(StateTy),
+
     #[display("unknown type {0}")]
     TypeUnknown(SemId),
 
     #[display("type of class {0} is not supported by field-based convertor")]
     TypeClassUnsupported(Cls),
 
-    #[display("number of fields doesn't match the number of fields in the type")]
-    TypeFieldCountMismatch,
+    #[display("number of fields in the state of type {0} doesn't match the number of fields in the type")]
+    TypeFieldCountMismatch(StateTy),
+
+    #[display("enum ordinal {0} is out of the range of the enum variants")]
+    InvalidEnumOrdinal(u256),
+
+    #[display("invalid range of {len} bits at offset {offset}, exceeding {FIELD_BITS} bits or empty")]
+    InvalidBitRange { offset: u16, len: u16 },
+
+    #[display("ASCII string length limit {0} exceeds {MAX_ASCII_LEN} characters")]
+    InvalidAsciiLen(u8),
+
+    #[display("state data are not a printable ASCII string of at most {0} characters")]
+    InvalidAscii(u8),
 
     #[display("AluVM is not yet supported for a state conversion.")]
     Unsupported,
+
+    #[display("AluVM state conversion script has produced data exceeding {MAX_BYTES} bytes")]
+    ScriptDataTooLarge,
+}
+
+/// Produces a hex dump of a state value for debugging purposes.
+///
+/// Each field element is represented by its 64-character hexadecimal value, and the elements are
+/// separated by colons. The first element holds the state type (unless the state is a unit, which
+/// is represented by an empty string).
+pub fn debug_hex(value: &StateValue) -> String {
+    (*value)
+        .into_iter()
+        .map(|el| format!("{:064x}", el.to_u256()))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+/// Encodes a strict value of a given semantic type into a state value using the provided state
+/// builder, without a need to construct an [`crate::Api`].
+///
+/// This is a standalone form of [`StateBuilder::build`], useful for testing and tooling; the
+/// resulting state can be decoded back with the convertor returned by
+/// [`StateBuilder::to_convertor`].
+#[allow(clippy::result_large_err)]
+pub fn encode_state<'libs>(
+    builder: &StateBuilder,
+    sem_id: SemId,
+    value: StrictVal,
+    libs: impl IntoIterator<Item = &'libs Lib>,
+    sys: &TypeSystem,
+) -> Result<StateValue, StateBuildError> {
+    builder.build(sem_id, value, libs, sys)
 }
 
 // Simplify newtype-like tuples
-fn reduce_tuples(mut val: StrictVal) -> StrictVal {
+pub(super) fn reduce_tuples(mut val: StrictVal) -> StrictVal {
     loop {
         if let StrictVal::Tuple(ref mut vec) = val {
             if vec.len() == 1 {
@@ -202,23 +423,98 @@ fn typed_convert(
         return Ok(None);
     }
 
+    let (buf, used_bytes) = typed_data(value);
+    let mut cursor = StreamReader::cursor::<MAX_BYTES>(&buf[..used_bytes]);
+    let mut val = sys.strict_read_type(sem_id, &mut cursor)?.unbox();
+    check_consumed(cursor, used_bytes)?;
+
+    val = reduce_tuples(val);
+
+    Ok(Some(val))
+}
+
+/// Collects data bytes from the field elements following the state type.
+fn typed_data(value: StateValue) -> ([u8; MAX_BYTES], usize) { packed_data(value.into_iter().skip(1)) }
+
+/// Collects data bytes from at most three field elements.
+fn packed_data(elems: impl IntoIterator<Item = fe256>) -> ([u8; MAX_BYTES], usize) {
     let mut buf = [0u8; MAX_BYTES];
-    let mut i = 1u8;
-    while let Some(el) = value.get(i) {
-        let from = USED_FIEL_BYTES * (i - 1) as usize;
-        let to = USED_FIEL_BYTES * i as usize;
-        buf[from..to].copy_from_slice(&el.to_u256().to_le_bytes()[..USED_FIEL_BYTES]);
-        i += 1;
-    }
-    let used_bytes = USED_FIEL_BYTES * (i - 1) as usize;
-    debug_assert!(i <= 4);
+    let mut used_bytes = 0usize;
+    for el in elems {
+        let to = used_bytes + USED_FIEL_BYTES;
+        buf[used_bytes..to].copy_from_slice(&el.to_u256().to_le_bytes()[..USED_FIEL_BYTES]);
+        used_bytes = to;
+    }
     debug_assert!(used_bytes <= MAX_BYTES);
+    (buf, used_bytes)
+}
 
+/// Runs an AluVM script providing it with a `witness`, and returns the state value left by the
+/// script in `EA`-`ED` registers.
+///
+/// Returns `None` if the script fails.
+fn run_script<'libs>(
+    entry: LibSite,
+    witness: StateValue,
+    libs: impl IntoIterator<Item = &'libs Lib>,
+) -> Option<StateValue> {
+    let libs = libs
+        .into_iter()
+        .map(|lib| (lib.lib_id(), lib))
+        .collect::<IndexMap<_, _>>();
+    let context = VmContext {
+        witness,
+        destructible_input: &[],
+        immutable_input: &[],
+        destructible_output: &[],
+        immutable_output: &[],
+    };
+    let mut vm = Vm::<Instr<LibId>>::with(
+        CoreConfig { halt: true, complexity_lim: Some(ADAPTOR_COMPLEXITY_LIMIT) },
+        GfaConfig::default(),
+    );
+    if vm.exec(entry, &context, |id| libs.get(&id)) == Status::Fail {
+        return None;
+    }
+    // Registers following the first unset one are ignored.
+    let elems = [RegE::EA, RegE::EB, RegE::EC, RegE::ED]
+        .into_iter()
+        .map_while(|reg| vm.core.cx.get(reg))
+        .collect::<Vec<_>>();
+    Some(StateValue::from_iter(elems))
+}
+
+fn aluvm_convert<'libs>(
+    entry: LibSite,
+    sem_id: SemId,
+    value: StateValue,
+    libs: impl IntoIterator<Item = &'libs Lib>,
+    sys: &TypeSystem,
+) -> Result<Option<StrictVal>, StateConvertError> {
+    let Some(data) = run_script(entry, value, libs) else {
+        // The script has not recognized the state
+        return Ok(None);
+    };
+    script_decode(data, sem_id, sys).map(Some)
+}
+
+/// Decodes a value of a given semantic type from the data left by an AluVM script in the `EA`-`EC`
+/// registers.
+pub(super) fn script_decode(data: StateValue, sem_id: SemId, sys: &TypeSystem) -> Result<StrictVal, StateConvertError> {
+    if data.get(3).is_some() {
+        return Err(StateConvertError::ScriptDataTooLarge);
+    }
+
+    let (buf, used_bytes) = packed_data(data);
     let mut cursor = StreamReader::cursor::<MAX_BYTES>(&buf[..used_bytes]);
-    let mut val = sys.strict_read_type(sem_id, &mut cursor)?.unbox();
+    let val = sys.strict_read_type(sem_id, &mut cursor)?.unbox();
+    check_consumed(cursor, used_bytes)?;
 
-    // We check here that we have reached the end of the buffer data,
-    // and the rest of the elements are zeros.
+    Ok(reduce_tuples(val))
+}
+
+/// Checks that we have reached the end of the buffer data, and the rest of the elements are zeros.
+fn check_consumed(cursor: StreamReader<Cursor<&[u8]>>, used_bytes: usize) -> Result<(), StateConvertError> {
     let cursor = cursor.unconfine();
     let position = cursor.position() as usize;
     let data = cursor.into_inner();
@@ -227,10 +523,120 @@ fn typed_convert(
             return Err(StateConvertError::NotEntirelyConsumed);
         }
     }
+    Ok(())
+}
 
-    val = reduce_tuples(val);
+fn array_convert(
+    ty: StateTy,
+    elem: SemId,
+    count: u8,
+    value: StateValue,
+    sys: &TypeSystem,
+) -> Result<Option<StrictVal>, StateConvertError> {
+    let from_ty = value.get(0).ok_or(StateConvertError::UnitState)?.to_u256();
+    // State type does not match
+    if from_ty != ty {
+        return Ok(None);
+    }
 
-    Ok(Some(val))
+    let (buf, used_bytes) = typed_data(value);
+    let mut cursor = StreamReader::cursor::<MAX_BYTES>(&buf[..used_bytes]);
+    let mut items = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let val = sys.strict_read_type(elem, &mut cursor)?.unbox();
+        items.push(reduce_tuples(val));
+    }
+    check_consumed(cursor, used_bytes)?;
+
+    Ok(Some(StrictVal::List(items)))
+}
+
+fn compact_convert(
+    ty: u16,
+    sem_id: SemId,
+    value: StateValue,
+    sys: &TypeSystem,
+) -> Result<Option<StrictVal>, StateConvertError> {
+    let first = value.get(0).ok_or(StateConvertError::UnitState)?.to_u256();
+    // State type does not match
+    if first >> COMPACT_TY_SHIFT != u256::from(ty) {
+        return Ok(None);
+    }
+    // The data never take the fourth field element
+    if value.get(3).is_some() {
+        return Err(StateConvertError::NotEntirelyConsumed);
+    }
+
+    let (buf, used_bytes) = packed_data(value);
+    let mut cursor = StreamReader::cursor::<MAX_BYTES>(&buf[..used_bytes]);
+    let val = sys.strict_read_type(sem_id, &mut cursor)?.unbox();
+    check_consumed(cursor, used_bytes)?;
+
+    Ok(Some(reduce_tuples(val)))
+}
+
+/// Checks that a byte is a printable ASCII character, which can be stored by
+/// [`StateBuilder::Ascii`].
+fn is_printable_ascii(byte: u8) -> bool { byte.is_ascii_graphic() || byte == b' ' }
+
+fn ascii_convert(ty: StateTy, max_len: u8, value: StateValue) -> Result<Option<StrictVal>, StateConvertError> {
+    if max_len > MAX_ASCII_LEN {
+        return Err(StateConvertError::InvalidAsciiLen(max_len));
+    }
+    let from_ty = value.get(0).ok_or(StateConvertError::UnitState)?.to_u256();
+    // State type does not match
+    if from_ty != ty {
+        return Ok(None);
+    }
+
+    let StateValue::Double { second, .. } = value else {
+        return Err(StateConvertError::TypeFieldCountMismatch(from_ty));
+    };
+    let bytes = second.to_u256().to_le_bytes();
+    // The string is padded with zeros, which are not printable characters
+    let len = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+    if bytes[len..].iter().any(|b| *b != 0) {
+        return Err(StateConvertError::NotEntirelyConsumed);
+    }
+    let s = &bytes[..len];
+    if len > max_len as usize || !s.iter().copied().all(is_printable_ascii) {
+        return Err(StateConvertError::InvalidAscii(max_len));
+    }
+
+    Ok(Some(StrictVal::String(s.iter().copied().map(char::from).collect())))
+}
+
+/// Checks that a bit range is non-empty and fits into a field element.
+fn is_valid_bit_range(offset: u16, len: u16) -> bool { len > 0 && offset as u32 + len as u32 <= FIELD_BITS as u32 }
+
+fn bits_convert(
+    ty: StateTy,
+    offset: u16,
+    len: u16,
+    sem_id: SemId,
+    value: StateValue,
+    sys: &TypeSystem,
+) -> Result<Option<StrictVal>, StateConvertError> {
+    if !is_valid_bit_range(offset, len) {
+        return Err(StateConvertError::InvalidBitRange { offset, len });
+    }
+    let from_ty = value.get(0).ok_or(StateConvertError::UnitState)?.to_u256();
+    // State type does not match
+    if from_ty != ty {
+        return Ok(None);
+    }
+
+    let data = value
+        .get(1)
+        .ok_or(StateConvertError::TypeFieldCountMismatch(from_ty))?
+        .to_u256();
+    let mask = (u256::ONE << len as usize) - u256::ONE;
+    let bytes = ((data >> offset as usize) & mask).to_le_bytes();
+    let mut cursor = StreamReader::cursor::<MAX_BYTES>(&bytes[..]);
+    let val = sys.strict_read_type(sem_id, &mut cursor)?.unbox();
+    check_consumed(cursor, bytes.len())?;
+
+    Ok(Some(reduce_tuples(val)))
 }
 
 fn typed_field_convert(
@@ -255,11 +661,11 @@ fn typed_field_convert(
     };
 
     if fields.len() != value.into_iter().count() - 1 {
-        return Err(StateConvertError::TypeFieldCountMismatch);
+        return Err(StateConvertError::TypeFieldCountMismatch(from_ty));
     }
 
     let mut items = vec![];
-    for (el, sem_id) in value.into_iter().skip(1).zip(fields.into_iter()) {
+    for (el, sem_id) in value.into_iter().skip(1).zip(fields) {
         let mut cursor = StreamReader::cursor::<MAX_BYTES>(el.to_u256().to_le_bytes());
         let val = sys.strict_read_type(sem_id, &mut cursor)?.unbox();
         items.push(val);
@@ -283,6 +689,161 @@ fn typed_field_convert(
     Ok(Some(val))
 }
 
+fn enum_convert(
+    ty: StateTy,
+    sem_id: SemId,
+    value: StateValue,
+    sys: &TypeSystem,
+) -> Result<Option<StrictVal>, StateConvertError> {
+    let from_ty = value.get(0).ok_or(StateConvertError::UnitState)?.to_u256();
+    // State type does not match
+    if from_ty != ty {
+        return Ok(None);
+    }
+
+    let ty = sys
+        .get(sem_id)
+        .ok_or(StateConvertError::TypeUnknown(sem_id))?;
+    let Ty::Enum(variants) = ty else {
+        return Err(StateConvertError::TypeClassUnsupported(ty.cls()));
+    };
+    let StateValue::Double { second, .. } = value else {
+        return Err(StateConvertError::TypeFieldCountMismatch(from_ty));
+    };
+    let ord = second.to_u256();
+    let name = (ord <= u256::from(u8::MAX))
+        .then(|| ord.low_u64() as u8)
+        .and_then(|tag| variants.name_by_tag(tag))
+        .ok_or(StateConvertError::InvalidEnumOrdinal(ord))?;
+
+    Ok(Some(StrictVal::Enum(EnumTag::Name(name.clone()))))
+}
+
+#[allow(clippy::result_large_err)]
+fn enum_build(ty: StateTy, sem_id: SemId, val: StrictVal, sys: &TypeSystem) -> Result<StateValue, StateBuildError> {
+    let Some(Ty::Enum(variants)) = sys.get(sem_id) else {
+        return Err(StateBuildError::NotEnum(sem_id));
+    };
+    let tag = match reduce_tuples(val) {
+        StrictVal::Enum(EnumTag::Ord(tag)) if variants.has_tag(tag) => tag,
+        StrictVal::Enum(EnumTag::Name(name)) if variants.has_name(&name) => {
+            variants.tag_by_name(&name).expect("variant is present")
+        }
+        StrictVal::Enum(tag) => return Err(StateBuildError::InvalidEnumOrdinal(tag)),
+        val => return Err(StateBuildError::UnsupportedValue(val)),
+    };
+    Ok(StateValue::Double { first: fe256::from(ty), second: fe256::from(tag) })
+}
+
+#[allow(clippy::result_large_err)]
+fn array_build(
+    ty: StateTy,
+    elem: SemId,
+    count: u8,
+    val: StrictVal,
+    sys: &TypeSystem,
+) -> Result<StateValue, StateBuildError> {
+    let StrictVal::List(items) = val else {
+        return Err(StateBuildError::UnsupportedValue(val));
+    };
+    if items.len() != count as usize {
+        return Err(StateBuildError::ArrayLenMismatch { expected: count, found: items.len() });
+    }
+    let mut ser = Vec::new();
+    for item in items {
+        let typed = sys.typify(item, elem)?;
+        sys.strict_write_value(&typed, &mut ser).map_err(SerializeError::from)?;
+    }
+    // We check the size before confining the data, such that the error can report it.
+    if ser.len() > MAX_BYTES {
+        return Err(StateBuildError::TooLarge(ser.len()));
+    }
+    Ok(typed_build(ty, Confined::from_checked(ser)))
+}
+
+#[allow(clippy::result_large_err)]
+fn serialize(typed: &typify::TypedVal, sys: &TypeSystem) -> Result<ConfinedBlob<0, MAX_BYTES>, StateBuildError> {
+    let mut ser = Vec::new();
+    sys.strict_write_value(typed, &mut ser)
+        .map_err(SerializeError::from)?;
+    // We check the size before confining the data, such that the error can report it.
+    if ser.len() > MAX_BYTES {
+        return Err(StateBuildError::TooLarge(ser.len()));
+    }
+    Ok(Confined::from_checked(ser))
+}
+
+#[allow(clippy::result_large_err)]
+fn bits_build(
+    ty: StateTy,
+    offset: u16,
+    len: u16,
+    ser: ConfinedBlob<0, MAX_BYTES>,
+) -> Result<StateValue, StateBuildError> {
+    if !is_valid_bit_range(offset, len) {
+        return Err(StateBuildError::InvalidBitRange { offset, len });
+    }
+    if ser.len() > u256::BYTES as usize {
+        return Err(StateBuildError::BitRangeOverflow(len));
+    }
+    let mut buf = [0u8; u256::BYTES as usize];
+    buf[..ser.len()].copy_from_slice(&ser);
+    let data = u256::from_le_bytes(buf);
+    if data >> len as usize != u256::ZERO {
+        return Err(StateBuildError::BitRangeOverflow(len));
+    }
+    Ok(StateValue::Double {
+        first: fe256::from(ty),
+        second: fe256::from(data << offset as usize),
+    })
+}
+
+#[allow(clippy::result_large_err)]
+fn ascii_build(ty: StateTy, max_len: u8, val: StrictVal) -> Result<StateValue, StateBuildError> {
+    if max_len > MAX_ASCII_LEN {
+        return Err(StateBuildError::InvalidAsciiLen(max_len));
+    }
+    let s = match reduce_tuples(val) {
+        StrictVal::String(s) => s,
+        val => return Err(StateBuildError::UnsupportedValue(val)),
+    };
+    if !s.bytes().all(is_printable_ascii) {
+        return Err(StateBuildError::NonAscii(s));
+    }
+    if s.len() > max_len as usize {
+        return Err(StateBuildError::AsciiTooLong { len: s.len(), max: max_len });
+    }
+    let mut buf = [0u8; u256::BYTES as usize];
+    buf[..s.len()].copy_from_slice(s.as_bytes());
+    Ok(StateValue::Double {
+        first: fe256::from(ty),
+        second: fe256::from(u256::from_le_bytes(buf)),
+    })
+}
+
+fn compact_build(ty: u16, ser: ConfinedBlob<0, MAX_BYTES>) -> StateValue {
+    let mut elems = packed_build(ser)
+        .into_iter()
+        .map(|el| el.to_u256())
+        .collect::<Vec<_>>();
+    if elems.is_empty() {
+        elems.push(u256::ZERO);
+    }
+    elems[0] |= u256::from(ty) << COMPACT_TY_SHIFT;
+    StateValue::from_iter(elems)
+}
+
+/// Packs data bytes into field elements, without prefixing them with a state type.
+fn packed_build(ser: ConfinedBlob<0, MAX_BYTES>) -> StateValue {
+    ser.chunks(USED_FIEL_BYTES)
+        .map(|chunk| {
+            let mut buf = [0u8; u256::BYTES as usize];
+            buf[..chunk.len()].copy_from_slice(chunk);
+            u256::from_le_bytes(buf)
+        })
+        .collect()
+}
+
 fn typed_build(ty: StateTy, ser: ConfinedBlob<0, MAX_BYTES>) -> StateValue {
     let mut elems = Vec::with_capacity(4);
     elems.push(ty);
@@ -307,6 +868,8 @@ fn typed_field_build(ty: StateTy, val: StrictVal) -> Result<StateValue, StateBui
             typed_build(ty, Confined::from_iter_checked(s.as_bytes().iter().cloned()))
         }
         StrictVal::Bytes(b) if b.len() < MAX_BYTES => typed_build(ty, Confined::from_checked(b.0)),
+        StrictVal::String(s) => return Err(StateBuildError::TooLarge(s.len())),
+        StrictVal::Bytes(b) => return Err(StateBuildError::TooLarge(b.len())),
         StrictVal::Struct(fields) if fields.len() <= 3 => typed_field_build_items(ty, fields.into_values())?,
         StrictVal::Enum(EnumTag::Ord(tag)) => StateValue::Double { first: fe256::from(ty), second: fe256::from(tag) },
         StrictVal::List(items) | StrictVal::Set(items) | StrictVal::Tuple(items) if items.len() <= 3 => {
@@ -347,6 +910,8 @@ fn typed_field_build_item(val: StrictVal) -> Result<Option<u256>, StateBuildErro
             buf[..b.len()].copy_from_slice(&b.0);
             Some(u256::from_le_bytes(buf))
         }
+        StrictVal::String(s) => return Err(StateBuildError::TooLarge(s.len())),
+        StrictVal::Bytes(b) => return Err(StateBuildError::TooLarge(b.len())),
         StrictVal::Enum(EnumTag::Ord(tag)) => Some(u256::from(tag)),
         _ => return Err(StateBuildError::UnsupportedValue(val)),
     })
@@ -356,10 +921,14 @@ fn typed_field_build_item(val: StrictVal) -> Result<Option<u256>, StateBuildErro
 mod tests {
     #![cfg_attr(coverage_nightly, coverage(off))]
 
+    use amplify::confinement::MediumBlob;
+    use strict_encoding::StrictDumb;
     use strict_types::stl::std_stl;
     use strict_types::{LibBuilder, SymbolicSys, SystemBuilder, TypeLib};
+    use ultrasonic::uasm;
 
     use super::*;
+    use crate::RawBuilder;
 
     pub const LIB_NAME_TEST: &str = "Test";
 
@@ -396,9 +965,38 @@ mod tests {
         pub party_id: PartyId,
     }
 
+    #[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = LIB_NAME_TEST)]
+    pub struct Oversized([u8; 100]);
+
+    #[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
+    #[strict_type(lib = LIB_NAME_TEST)]
+    pub struct Memo {
+        pub nonce: u64,
+        pub memo: [u8; 64],
+    }
+
+    #[derive(Clone, Eq, PartialEq, Hash, Debug)]
+    #[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+    #[strict_type(lib = LIB_NAME_TEST)]
+    pub struct Bulk(MediumBlob);
+
+    impl StrictDumb for Oversized {
+        fn strict_dumb() -> Self { Self([0u8; 100]) }
+    }
+
+    impl StrictDumb for Memo {
+        fn strict_dumb() -> Self { Self { nonce: 0, memo: [0u8; 64] } }
+    }
+
     pub fn stl() -> TypeLib {
         LibBuilder::with(libname!(LIB_NAME_TEST), [std_stl().to_dependency_types()])
             .transpile::<CastVote>()
+            .transpile::<Oversized>()
+            .transpile::<Memo>()
+            .transpile::<Bulk>()
             .compile()
             .expect("invalid Test type library")
     }
@@ -442,13 +1040,13 @@ mod tests {
 
         let ty = types.get(name);
         let val = StateConvertor::TypedEncoder(u256::ONE)
-            .convert(ty, src, &types.type_system())
+            .convert(ty, src, [], &types.type_system())
             .unwrap()
             .unwrap();
         assert_eq!(val, dst);
 
         let res = StateBuilder::TypedEncoder(u256::ONE)
-            .build(ty, dst, &types.type_system())
+            .build(ty, dst, [], &types.type_system())
             .unwrap();
         assert_eq!(res, src);
     }
@@ -458,13 +1056,13 @@ mod tests {
 
         let ty = types.get(name);
         let val = StateConvertor::TypedFieldEncoder(u256::ONE)
-            .convert(ty, src1, &types.type_system())
+            .convert(ty, src1, [], &types.type_system())
             .unwrap()
             .unwrap();
         assert_eq!(val, dst);
 
         let res = StateBuilder::TypedFieldEncoder(u256::ONE)
-            .build(ty, src2, &types.type_system())
+            .build(ty, src2, [], &types.type_system())
             .unwrap();
         assert_eq!(res, src1);
     }
@@ -478,12 +1076,23 @@ mod tests {
         );
     }
 
+    #[test]
+    fn unit() {
+        let types = Types::new();
+        let sys = types.type_system();
+        assert_eq!(StateBuilder::Unit.build(SemId::unit(), StrictVal::Unit, [], &sys), Ok(StateValue::None));
+        assert_eq!(
+            StateBuilder::Unit.build(types.get("Std.Bool"), svenum!("true"), [], &sys),
+            Err(StateBuildError::InvalidUnit(svenum!("true")))
+        );
+    }
+
     #[test]
     #[should_panic(expected = "Decode(Decode(Io(Kind(UnexpectedEof))))")]
     fn typed_convert_lack() {
         let types = Types::new();
         StateConvertor::TypedEncoder(u256::ONE)
-            .convert(types.get("Std.Bool"), StateValue::Single { first: fe256::from(1u8) }, &types.type_system())
+            .convert(types.get("Std.Bool"), StateValue::Single { first: fe256::from(1u8) }, [], &types.type_system())
             .unwrap();
     }
 
@@ -499,6 +1108,7 @@ mod tests {
                     second: fe256::from(1u8),
                     third: fe256::from(1u8),
                 },
+                [],
                 &types.type_system(),
             )
             .unwrap();
@@ -527,6 +1137,7 @@ mod tests {
             .convert(
                 types.get("Std.Bool"),
                 StateValue::Double { first: fe256::from(1u8), second: fe256::from(1u8) },
+                [],
                 &types.type_system(),
             )
             .unwrap();
@@ -538,7 +1149,12 @@ mod tests {
     fn typed_field_convert_lack() {
         let types = Types::new();
         StateConvertor::TypedFieldEncoder(u256::ONE)
-            .convert(types.get("Test.CastVote"), StateValue::Single { first: fe256::from(1u8) }, &types.type_system())
+            .convert(
+                types.get("Test.CastVote"),
+                StateValue::Single { first: fe256::from(1u8) },
+                [],
+                &types.type_system(),
+            )
             .unwrap();
     }
 
@@ -554,6 +1170,7 @@ mod tests {
                     second: fe256::from(1u8),
                     third: fe256::from(1u8),
                 },
+                [],
                 &types.type_system(),
             )
             .unwrap();
@@ -574,8 +1191,390 @@ mod tests {
                     third: fe256::from(5u8),
                     fourth: fe256::from(1u8),
                 },
+                [],
                 &types.type_system(),
             )
             .unwrap();
     }
+
+    #[test]
+    fn enum_ordinal() {
+        let types = Types::new();
+        let sem_id = types.get("Test.Vote");
+        let state = StateValue::Double { first: fe256::from(1u8), second: fe256::from(1u8) };
+
+        let res = StateBuilder::Enum(u256::ONE)
+            .build(sem_id, svenum!(1), [], &types.type_system())
+            .unwrap();
+        assert_eq!(res, state);
+        let res = StateBuilder::Enum(u256::ONE)
+            .build(sem_id, svenum!("pro"), [], &types.type_system())
+            .unwrap();
+        assert_eq!(res, state);
+
+        let val = StateConvertor::Enum(u256::ONE)
+            .convert(sem_id, state, [], &types.type_system())
+            .unwrap();
+        assert_eq!(val, Some(svenum!("pro")));
+    }
+
+    #[test]
+    fn enum_ordinal_out_of_range() {
+        let types = Types::new();
+        let sem_id = types.get("Test.Vote");
+
+        let err = StateBuilder::Enum(u256::ONE)
+            .build(sem_id, svenum!(5), [], &types.type_system())
+            .unwrap_err();
+        assert_eq!(err, StateBuildError::InvalidEnumOrdinal(EnumTag::Ord(5)));
+
+        let err = StateConvertor::Enum(u256::ONE)
+            .convert(
+                sem_id,
+                StateValue::Double { first: fe256::from(1u8), second: fe256::from(5u8) },
+                [],
+                &types.type_system(),
+            )
+            .unwrap_err();
+        assert_eq!(err, StateConvertError::InvalidEnumOrdinal(u256::from(5u8)));
+
+        let err = StateBuilder::Enum(u256::ONE)
+            .build(types.get("Test.PartyId"), svnum!(1u64), [], &types.type_system())
+            .unwrap_err();
+        assert_eq!(err, StateBuildError::NotEnum(types.get("Test.PartyId")));
+    }
+
+    #[test]
+    fn fixed_array() {
+        let types = Types::new();
+        let sys = types.type_system();
+        let elem = types.get("Test.VoteId");
+        let src = svlist!([1u64, 2u64, 3u64]);
+
+        let state = StateBuilder::FixedArray(u256::ONE, elem, 3)
+            .build(SemId::unit(), src.clone(), [], &sys)
+            .unwrap();
+        let mut data = [0u8; 32];
+        data[..8].copy_from_slice(&1u64.to_le_bytes());
+        data[8..16].copy_from_slice(&2u64.to_le_bytes());
+        data[16..24].copy_from_slice(&3u64.to_le_bytes());
+        assert_eq!(state, StateValue::Double {
+            first: fe256::from(1u8),
+            second: fe256::from(u256::from_le_bytes(data))
+        });
+
+        let val = StateConvertor::FixedArray(u256::ONE, elem, 3)
+            .convert(SemId::unit(), state, [], &sys)
+            .unwrap();
+        assert_eq!(val, Some(src));
+
+        let err = StateConvertor::FixedArray(u256::ONE, elem, 2)
+            .convert(SemId::unit(), state, [], &sys)
+            .unwrap_err();
+        assert_eq!(err, StateConvertError::NotEntirelyConsumed);
+    }
+
+    #[test]
+    fn fixed_array_invalid() {
+        let types = Types::new();
+        let sys = types.type_system();
+
+        let err = StateBuilder::FixedArray(u256::ONE, types.get("Test.VoteId"), 3)
+            .build(SemId::unit(), svlist!([1u64, 2u64]), [], &sys)
+            .unwrap_err();
+        assert_eq!(err, StateBuildError::ArrayLenMismatch { expected: 3, found: 2 });
+
+        let vote = ston!(voteId 3u8, vote svenum!(1), partyId 5u8);
+        let err = StateBuilder::FixedArray(u256::ONE, types.get("Test.CastVote"), 6)
+            .build(SemId::unit(), StrictVal::List(vec![vote; 6]), [], &sys)
+            .unwrap_err();
+        assert_eq!(err, StateBuildError::TooLarge(102));
+    }
+
+    #[test]
+    fn bit_range() {
+        let types = Types::new();
+        let sys = types.type_system();
+        let amount = StateBuilder::BitRange { ty: u256::ONE, offset: 0, len: 32 };
+        let tag = StateBuilder::BitRange { ty: u256::ONE, offset: 32, len: 8 };
+
+        let amount_state = amount
+            .build(types.get("Test.PartyId"), svnum!(0xDEADBEEFu64), [], &sys)
+            .unwrap();
+        let tag_state = tag
+            .build(types.get("Test.Vote"), svenum!("pro"), [], &sys)
+            .unwrap();
+        assert_eq!(tag_state, StateValue::Double { first: fe256::from(1u8), second: fe256::from(1u64 << 32) });
+
+        // Both subfields are packed into the same field element
+        let packed = amount_state.get(1).unwrap().to_u256() | tag_state.get(1).unwrap().to_u256();
+        let state = StateValue::Double { first: fe256::from(1u8), second: fe256::from(packed) };
+        assert_eq!(
+            amount
+                .to_convertor()
+                .convert(types.get("Test.PartyId"), state, [], &sys),
+            Ok(Some(svnum!(0xDEADBEEFu64)))
+        );
+        assert_eq!(
+            tag.to_convertor()
+                .convert(types.get("Test.Vote"), state, [], &sys),
+            Ok(Some(svenum!("pro")))
+        );
+
+        let other = StateValue::Double { first: fe256::from(2u8), second: fe256::from(packed) };
+        assert_eq!(
+            tag.to_convertor()
+                .convert(types.get("Test.Vote"), other, [], &sys),
+            Ok(None)
+        );
+    }
+
+    #[test]
+    fn bit_range_invalid() {
+        let types = Types::new();
+        let sys = types.type_system();
+        let sem_id = types.get("Test.PartyId");
+        let state = StateValue::Double { first: fe256::from(1u8), second: fe256::from(1u8) };
+
+        for (offset, len) in [(0, 0), (250, 5), (u16::MAX, u16::MAX)] {
+            assert_eq!(
+                StateBuilder::BitRange { ty: u256::ONE, offset, len }.build(sem_id, svnum!(1u64), [], &sys),
+                Err(StateBuildError::InvalidBitRange { offset, len })
+            );
+            assert_eq!(
+                StateConvertor::BitRange { ty: u256::ONE, offset, len }.convert(sem_id, state, [], &sys),
+                Err(StateConvertError::InvalidBitRange { offset, len })
+            );
+        }
+        assert_eq!(
+            StateBuilder::BitRange { ty: u256::ONE, offset: 0, len: 32 }.build(sem_id, svnum!(1u64 << 32), [], &sys),
+            Err(StateBuildError::BitRangeOverflow(32))
+        );
+        assert_eq!(
+            StateConvertor::BitRange { ty: u256::ONE, offset: 0, len: 32 }.convert(
+                sem_id,
+                StateValue::Single { first: fe256::from(1u8) },
+                [],
+                &sys
+            ),
+            Err(StateConvertError::TypeFieldCountMismatch(u256::ONE))
+        );
+    }
+
+    #[test]
+    fn typed_max_size() {
+        let types = Types::new();
+        let sys = types.type_system();
+        let sem_id = types.get("Test.Memo");
+        let memo = ston!(nonce 5u64, memo svbytes!([0xAFu8; 64]));
+
+        let state = StateBuilder::TypedEncoder(u256::ONE)
+            .build(sem_id, memo.clone(), [], &sys)
+            .unwrap();
+        // 72 bytes of data occupy all three field elements following the state type
+        let StateValue::Quadruple { first, fourth, .. } = state else {
+            panic!("state value {state:?} must use all four field elements");
+        };
+        assert_eq!(first, fe256::from(1u8));
+        assert_ne!(fourth, fe256::from(0u8));
+
+        let val = StateConvertor::TypedEncoder(u256::ONE)
+            .convert(sem_id, state, [], &sys)
+            .unwrap();
+        assert_eq!(val, Some(memo));
+    }
+
+    #[test]
+    fn compact_typed() {
+        let types = Types::new();
+        let sys = types.type_system();
+
+        for ty in [0u16, 1, 0x3FFF] {
+            for (name, val, len) in [
+                ("Test.CastVote", ston!(voteId 3u64, vote svenum!("pro"), partyId 5u64), 1),
+                ("Test.Memo", ston!(nonce 5u64, memo svbytes!([0xAFu8; 64])), 3),
+            ] {
+                let sem_id = types.get(name);
+                let builder = StateBuilder::CompactTyped(ty);
+                let state = builder.build(sem_id, val.clone(), [], &sys).unwrap();
+                assert_eq!(state.into_iter().count(), len);
+                assert_eq!(state.get(0).unwrap().to_u256() >> 240, u256::from(ty));
+
+                let typed = StateBuilder::TypedEncoder(u256::from(ty))
+                    .build(sem_id, val.clone(), [], &sys)
+                    .unwrap();
+                assert_eq!(typed.into_iter().count(), len + 1);
+
+                // Both encodings must decode into the same value
+                let expected = StateConvertor::TypedEncoder(u256::from(ty))
+                    .convert(sem_id, typed, [], &sys)
+                    .unwrap();
+                assert!(expected.is_some());
+                let convertor = builder.to_convertor();
+                assert_eq!(convertor.convert(sem_id, state, [], &sys), Ok(expected));
+                assert_eq!(StateConvertor::CompactTyped(ty + 1).convert(sem_id, state, [], &sys), Ok(None));
+            }
+        }
+    }
+
+    #[test]
+    fn compact_typed_excess() {
+        let types = Types::new();
+        let state = StateValue::from_iter([1u8, 0, 0, 0].map(u256::from));
+        assert_eq!(
+            StateConvertor::CompactTyped(0).convert(types.get("Test.VoteId"), state, [], &types.type_system()),
+            Err(StateConvertError::NotEntirelyConsumed)
+        );
+    }
+
+    #[test]
+    fn ascii() {
+        let sys = Types::new().type_system();
+        let builder = StateBuilder::Ascii(u256::ONE, 8);
+        for ticker in ["DEMO", "", "USD-T 01"] {
+            let state = builder
+                .build(SemId::unit(), StrictVal::str(ticker), [], &sys)
+                .unwrap();
+            assert_eq!(state.into_iter().count(), 2);
+            let convertor = builder.to_convertor();
+            assert_eq!(convertor.convert(SemId::unit(), state, [], &sys), Ok(Some(StrictVal::str(ticker))));
+            assert_eq!(StateConvertor::Ascii(u256::from(2u8), 8).convert(SemId::unit(), state, [], &sys), Ok(None));
+        }
+
+        let state = StateBuilder::Ascii(u256::ONE, 30)
+            .build(SemId::unit(), StrictVal::str("A".repeat(30)), [], &sys)
+            .unwrap();
+        assert_eq!(
+            StateConvertor::Ascii(u256::ONE, 8).convert(SemId::unit(), state, [], &sys),
+            Err(StateConvertError::InvalidAscii(8))
+        );
+    }
+
+    #[test]
+    fn ascii_invalid() {
+        let sys = Types::new().type_system();
+        let builder = StateBuilder::Ascii(u256::ONE, 8);
+        assert_eq!(
+            builder.build(SemId::unit(), StrictVal::str("DÉMO"), [], &sys),
+            Err(StateBuildError::NonAscii(s!("DÉMO")))
+        );
+        assert_eq!(
+            builder.build(SemId::unit(), StrictVal::str("DEMO\0"), [], &sys),
+            Err(StateBuildError::NonAscii(s!("DEMO\0")))
+        );
+        assert_eq!(
+            builder.build(SemId::unit(), StrictVal::str("DEMOCRACY"), [], &sys),
+            Err(StateBuildError::AsciiTooLong { len: 9, max: 8 })
+        );
+        assert_eq!(
+            builder.build(SemId::unit(), svnum!(5u64), [], &sys),
+            Err(StateBuildError::UnsupportedValue(svnum!(5u64)))
+        );
+        assert_eq!(
+            StateBuilder::Ascii(u256::ONE, 31).build(SemId::unit(), StrictVal::str("DEMO"), [], &sys),
+            Err(StateBuildError::InvalidAsciiLen(31))
+        );
+
+        let state = StateValue::Double {
+            first: fe256::from(1u8),
+            second: fe256::from(u256::from(0xC9u8)),
+        };
+        assert_eq!(
+            StateConvertor::Ascii(u256::ONE, 8).convert(SemId::unit(), state, [], &sys),
+            Err(StateConvertError::InvalidAscii(8))
+        );
+    }
+
+    #[test]
+    fn typed_build_too_large() {
+        let types = Types::new();
+        let err = StateBuilder::TypedEncoder(u256::ONE)
+            .build(types.get("Test.Oversized"), svbytes!([0xAFu8; 100]), [], &types.type_system())
+            .unwrap_err();
+        assert_eq!(err, StateBuildError::TooLarge(100));
+    }
+
+    #[test]
+    fn raw_build_too_large() {
+        let types = Types::new();
+        let builder = RawBuilder::StrictEncode(types.get("Test.Bulk"));
+        let raw = builder
+            .build(svbytes!(vec![0xAFu8; 1000]), &types.type_system())
+            .unwrap();
+        assert_eq!(raw[..].len(), 1003);
+
+        let err = builder
+            .build(svbytes!(vec![0xAFu8; MAX_RAW_BYTES]), &types.type_system())
+            .unwrap_err();
+        assert_eq!(err, StateBuildError::RawTooLarge(MAX_RAW_BYTES + 3));
+        assert_eq!(err.to_string(), "raw state data is too large (65538 bytes, while at most 65535 bytes are allowed)");
+    }
+
+    #[test]
+    fn typed_field_build_too_large() {
+        let types = Types::new();
+        let err = StateBuilder::TypedFieldEncoder(u256::ONE)
+            .build(types.get("Test.Oversized"), svbytes!([0xAFu8; 100]), [], &types.type_system())
+            .unwrap_err();
+        assert_eq!(err, StateBuildError::TooLarge(100));
+    }
+
+    #[test]
+    fn aluvm_identity() {
+        let types = Types::new();
+        let sys = types.type_system();
+        let sem_id = types.get("Test.CastVote");
+        let lib = Lib::assemble(&uasm! {
+            ldw;
+            stop;
+        })
+        .unwrap();
+        let builder = StateBuilder::AluVM(LibSite::new(lib.lib_id(), 0));
+        let convertor = builder.to_convertor();
+
+        let vote = ston!(voteId 3u8, vote svenum!(1), partyId 5u8);
+        let state = builder.build(sem_id, vote.clone(), [&lib], &sys).unwrap();
+        let typed = StateBuilder::TypedEncoder(u256::ONE)
+            .build(sem_id, vote.clone(), [], &sys)
+            .unwrap();
+        // The identity script keeps the data without the state type
+        assert_eq!(state, typed.into_iter().skip(1).collect());
+
+        let val = convertor.convert(sem_id, state, [&lib], &sys).unwrap();
+        let expected = StateConvertor::TypedEncoder(u256::ONE)
+            .convert(sem_id, typed, [], &sys)
+            .unwrap();
+        assert_eq!(val, expected);
+        assert_eq!(val.unwrap().unwrap_struct("vote"), &svenum!("pro"));
+
+        // The library is not known
+        assert_eq!(convertor.convert(sem_id, state, [], &sys), Ok(None));
+        assert_eq!(builder.build(sem_id, vote, [], &sys), Err(StateBuildError::ScriptFailure));
+    }
+
+    #[test]
+    fn aluvm_too_large() {
+        let types = Types::new();
+        let lib = Lib::assemble(&uasm! {
+            ldw;
+            stop;
+        })
+        .unwrap();
+        let convertor = StateConvertor::AluVM(LibSite::new(lib.lib_id(), 0));
+        let state = StateValue::from_iter([1u8, 0, 0, 0].map(u256::from));
+        assert_eq!(
+            convertor.convert(types.get("Test.VoteId"), state, [&lib], &types.type_system()),
+            Err(StateConvertError::ScriptDataTooLarge)
+        );
+    }
+
+    #[test]
+    fn debug_hex_dump() {
+        assert_eq!(debug_hex(&StateValue::None), "");
+        assert_eq!(
+            debug_hex(&StateValue::Double { first: fe256::from(1u8), second: fe256::from(0xABCDu16) }),
+            "0000000000000000000000000000000000000000000000000000000000000001:\
+             000000000000000000000000000000000000000000000000000000000000abcd"
+        );
+    }
 }
@@ -23,17 +23,28 @@
 
 use alloc::collections::BTreeMap;
 
-use aluvm::{Lib, LibId, LibSite};
-use amplify::confinement::TinyBlob;
+use aluvm::regs::Status;
+use aluvm::{CoreConfig, CoreExt, Lib, LibId, LibSite, Vm};
+use amplify::confinement::{TinyBlob, TinyString};
+use amplify::num::u256;
+use commit_verify::{DigestExt, Sha256};
 use indexmap::IndexMap;
 use sonic_callreq::StateName;
-use strict_encoding::StrictDumb;
+use strict_encoding::{StrictDumb, VariantName};
 use strict_types::value::{EnumTag, StrictNum};
 use strict_types::{SemId, StrictVal, TypeSystem};
-use ultrasonic::CellAddr;
+use ultrasonic::aluvm::{GfaConfig, RegE};
+use ultrasonic::{fe256, CellAddr, Instr, StateData, StateValue, VmContext};
 
+use super::adaptors::{reduce_tuples, script_decode};
 use crate::{StateAtom, LIB_NAME_SONIC};
 
+/// Tag used for computing state name identifiers for [`Aggregator::AluVM`] scripts.
+const ALUVM_STATE_ID_TAG: &str = "urn:ubideco:sonic:state-name#2025-06-01";
+
+/// Maximal computational complexity of [`Aggregator::AluVM`] scripts.
+const AGGREGATOR_COMPLEXITY_LIMIT: u64 = 10_000_000;
+
 /// Structure which allows applying aggregators either to a global or a different aggregated
 /// state.
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
@@ -52,6 +63,50 @@ pub enum StateSelector {
     Aggregated(StateName),
 }
 
+/// Errors happening during computing an aggregated state (see [`Aggregator::try_aggregate`]).
+#[derive(Clone, Eq, PartialEq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum AggregateError {
+    /// global state '{0}' is absent.
+    GlobalAbsent(StateName),
+
+    /// aggregated state '{0}' is absent.
+    AggregatedAbsent(StateName),
+
+    /// global state '{0}' has no elements.
+    NoElements(StateName),
+
+    /// global state '{0}' has multiple elements, while a single element is expected.
+    MultipleElements(StateName),
+
+    /// global state '{0}' has no element at position {1}.
+    OutOfRange(StateName, u16),
+
+    /// state '{0}' is not an optional value with some data.
+    NotSome(StateName),
+
+    /// state '{0}' contains a value which is not an integer of the required type.
+    NotInteger(StateName),
+
+    /// state '{0}' contains a value which is not a string.
+    NotString(StateName),
+
+    /// reference value can't be deserialized as a value of type {0}.
+    InvalidReference(SemId),
+
+    /// integer overflow or division by zero during state aggregation.
+    Overflow,
+
+    /// AluVM aggregator script has failed.
+    VmFailure,
+
+    /// AluVM aggregator script has not put a resulting value into `EA` register.
+    VmNoResult,
+
+    /// AluVM aggregator script has produced a value which is not a valid value of type {0}.
+    VmInvalidResult(SemId),
+}
+
 /// A set of pre-defined top-level state aggregators (see [`crate::Api::aggregators`].
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 #[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
@@ -88,17 +143,58 @@ pub enum Aggregator {
     ),
 
     /// Execute a custom function on the state.
+    ///
+    /// The script is run by zk-AluVM using the UltraSONIC instruction set (the same one used by
+    /// the codex verifiers), with the following convention:
+    /// - the verifiable part of each global state element which is an unsigned integer is provided
+    ///   as a read-only input, accessible with `ldi immutable` instruction;
+    /// - each of the aggregated state values which is an unsigned integer is provided as an output,
+    ///   accessible with `ldo immutable` instruction;
+    /// - both are represented as [`StateValue::Double`], where the first element is the state name
+    ///   identifier (see [`Aggregator::aluvm_state_id`]) and the second one is the value;
+    /// - global state elements are ordered by the state name identifiers, and then according to the
+    ///   global state element order; the aggregated state values are ordered by the state name
+    ///   identifiers;
+    /// - on successful completion, the script must put the strict-encoded resulting value into
+    ///   `EA`-`EC` registers (30 bytes per register, little-endian, unused bytes set to zero) and
+    ///   clear the register following the last one it has used; the value is decoded using the
+    ///   provided semantic type.
+    ///
+    /// Script aggregators are computed after all other aggregators of the API, and receive only
+    /// their values as the aggregated state: they never see their own value or the values of other
+    /// script aggregators.
+    ///
+    /// The aggregated state is not produced if the script fails, or the `EA` register is not set,
+    /// or the data in registers can't be decoded as a value of the provided type.
     #[strict_type(tag = 0xFF)]
     AluVM(
         /// The entry point to the script (virtual machine uses libraries from
         /// [`crate::Semantics`]).
         LibSite,
+        /// The type of the produced value.
+        SemId,
     ),
 }
 
 impl Aggregator {
+    /// Computes an identifier for a state name, which is used to refer to the state from
+    /// [`Aggregator::AluVM`] scripts.
+    ///
+    /// The identifier is a tagged SHA256 hash of the state name, truncated to 30 bytes such that
+    /// it fits into any of the supported finite fields.
+    pub fn aluvm_state_id(name: &StateName) -> fe256 {
+        let mut hasher = Sha256::from_tag(ALUVM_STATE_ID_TAG);
+        hasher.input_raw(name.as_bytes());
+        let mut id = [0u8; 32];
+        id[..30].copy_from_slice(&hasher.finish()[..30]);
+        fe256::from(u256::from_le_bytes(id))
+    }
+
     /// Returns names of the other computed state which this aggregator depends on
     /// and which needs to be computed before running this aggregator.
+    ///
+    /// [`Self::AluVM`] scripts don't report their dependencies; they implicitly depend on all
+    /// non-script aggregators (see [`crate::Api::aggregator_plan`]).
     pub fn depends_on(&self) -> impl Iterator<Item = &StateName> {
         match self {
             Self::Some(sub) | Self::Take(sub) => sub.depends_on(),
@@ -107,16 +203,20 @@ impl Aggregator {
                 deps.append(&mut other.depends_on());
                 deps
             }
-            Self::None | Self::AluVM(_) => vec![],
+            Self::None | Self::AluVM(..) => vec![],
         }
         .into_iter()
     }
 
+    /// Detects whether the aggregator is an [`Self::AluVM`] script.
+    pub fn is_script(&self) -> bool { matches!(self, Self::AluVM(..)) }
+
     /// Compute state via applying some aggregator function.
     ///
     /// # Returns
     ///
-    /// Aggregated state value. If the computing fails due to any exception, `None`.
+    /// Aggregated state value. If the computing fails due to any exception, `None`; use
+    /// [`Self::try_aggregate`] to learn the reason of the failure.
     pub fn aggregate<'libs>(
         &self,
         global: &BTreeMap<StateName, BTreeMap<CellAddr, StateAtom>>,
@@ -124,31 +224,92 @@ impl Aggregator {
         libs: impl IntoIterator<Item = &'libs Lib>,
         types: &TypeSystem,
     ) -> Option<StrictVal> {
+        self.try_aggregate(global, aggregated, libs, types).ok()
+    }
+
+    /// Compute state via applying some aggregator function, reporting the reason of a failure.
+    ///
+    /// # Returns
+    ///
+    /// Aggregated state value, or an error explaining why the state can't be computed. For
+    /// [`Self::Or`], if both sub-aggregators fail, the error of the second one is returned.
+    pub fn try_aggregate<'libs>(
+        &self,
+        global: &BTreeMap<StateName, BTreeMap<CellAddr, StateAtom>>,
+        aggregated: &BTreeMap<StateName, StrictVal>,
+        libs: impl IntoIterator<Item = &'libs Lib>,
+        types: &TypeSystem,
+    ) -> Result<StrictVal, AggregateError> {
         match self {
-            Self::None => Some(StrictVal::none()),
+            Self::None => Ok(StrictVal::none()),
 
-            Self::Take(sub) => sub.aggregate(global, aggregated, types),
+            Self::Take(sub) => sub.try_aggregate(global, aggregated, types),
 
-            Self::Some(sub) => Some(match sub.aggregate(global, aggregated, types) {
-                Some(val) => StrictVal::some(val),
-                None => StrictVal::none(),
+            Self::Some(sub) => Ok(match sub.try_aggregate(global, aggregated, types) {
+                Ok(val) => StrictVal::some(val),
+                Err(_) => StrictVal::none(),
             }),
 
             Self::Or(some, other) => some
-                .aggregate(global, aggregated, types)
-                .or_else(|| other.aggregate(global, aggregated, types)),
+                .try_aggregate(global, aggregated, types)
+                .or_else(|_| other.try_aggregate(global, aggregated, types)),
 
-            Self::AluVM(entry) => {
+            Self::AluVM(entry, sem_id) => {
                 let libs = libs
                     .into_iter()
                     .map(|lib| (lib.lib_id(), lib))
                     .collect::<IndexMap<_, _>>();
-                let mut vm = aluvm::Vm::<aluvm::isa::Instr<LibId>>::new();
-                // For now, we ignore all computations and return `None` anyway.
-                // This leaves a way to add proper VM computing in the future
-                // in a backward-compatible way.
-                let _status = vm.exec(*entry, &(), |id| libs.get(&id));
-                None
+
+                let to_u64 = |val: &StrictVal| match val {
+                    StrictVal::Number(StrictNum::Uint(val)) => Some(*val),
+                    _ => None,
+                };
+                let mut global = global
+                    .iter()
+                    .map(|(name, state)| (Self::aluvm_state_id(name), state))
+                    .collect::<Vec<_>>();
+                global.sort_by_key(|(id, _)| id.to_u256());
+                let immutable_input = global
+                    .into_iter()
+                    .flat_map(|(id, state)| {
+                        state
+                            .values()
+                            .filter_map(move |atom| Some(StateValue::new(id, to_u64(&atom.verified)?)))
+                    })
+                    .collect::<Vec<_>>();
+                let mut immutable_output = aggregated
+                    .iter()
+                    .filter_map(|(name, val)| Some(StateData::new(Self::aluvm_state_id(name), to_u64(val)?)))
+                    .collect::<Vec<_>>();
+                immutable_output.sort_by_key(|data| data.value.get(0).map(|id| id.to_u256()));
+
+                let context = VmContext {
+                    witness: StateValue::None,
+                    destructible_input: &[],
+                    immutable_input: &immutable_input,
+                    destructible_output: &[],
+                    immutable_output: &immutable_output,
+                };
+                let mut vm = Vm::<Instr<LibId>>::with(
+                    CoreConfig {
+                        halt: true,
+                        complexity_lim: Some(AGGREGATOR_COMPLEXITY_LIMIT),
+                    },
+                    GfaConfig::default(),
+                );
+                if vm.exec(*entry, &context, |id| libs.get(&id)) == Status::Fail {
+                    return Err(AggregateError::VmFailure);
+                }
+                // Registers following the first unset one are ignored.
+                let elems = [RegE::EA, RegE::EB, RegE::EC, RegE::ED]
+                    .into_iter()
+                    .map_while(|reg| vm.core.cx.get(reg))
+                    .collect::<Vec<_>>();
+                if elems.is_empty() {
+                    return Err(AggregateError::VmNoResult);
+                }
+                script_decode(StateValue::from_iter(elems), *sem_id, types)
+                    .map_err(|_| AggregateError::VmInvalidResult(*sem_id))
             }
         }
     }
@@ -206,6 +367,13 @@ pub enum SubAggregator {
     /// Fails if the global state is not defined, i.e., has zero elements,
     /// or if the nth-element is empty.
     ///
+    /// # Position bounds
+    ///
+    /// The position is zero-based and is limited to `u16`, thus only the first 65536 elements can
+    /// be addressed. A position which is out of range of the state elements is not an error, but
+    /// an aggregation failure: the state is not produced and no default value is used (use
+    /// [`Aggregator::Or`] if a fallback is required).
+    ///
     /// # Nota bene
     ///
     /// The global state does not have only a partial ordering (it is a lattice).
@@ -241,6 +409,13 @@ pub enum SubAggregator {
     /// Fails if the global state is not defined, i.e., has zero elements,
     /// or if the nth-element is empty.
     ///
+    /// # Position bounds
+    ///
+    /// The position is zero-based and is limited to `u16`, thus only the first 65536 elements can
+    /// be addressed. A position which is out of range of the state elements is not an error, but
+    /// an aggregation failure: the state is not produced and no default value is used (use
+    /// [`Aggregator::Or`] if a fallback is required).
+    ///
     /// # Nota bene
     ///
     /// The global state does not have only a partial ordering (it is a lattice).
@@ -254,55 +429,79 @@ pub enum SubAggregator {
     #[strict_type(tag = 7)]
     NthBack(StateName, u16),
 
-    /// Integer-negate state.
+    /// Integer-negate state, producing a signed integer.
     ///
     /// Fails if the state is not defined or contains multiple elements.
-    /// Also fails if the state is not an unsigned 64-bit integer or is greater than `i64::MAX`.
+    /// Also fails if the state is not a 64-bit integer (signed or unsigned), or there is an
+    /// overflow (including unsigned values greater than `i64::MAX`).
     #[strict_type(tag = 0x10)]
     Neg(StateSelector),
 
     /// Sum two states of different types, expecting them to be integers.
     ///
+    /// If both states are unsigned integers, the result is an unsigned integer; otherwise, both
+    /// states are converted into signed integers and the result is a signed integer.
+    ///
     /// Fails if any of the state is not defined or contains multiple elements.
-    /// Also fails if the state is not an unsigned 64-bit integer or there is an overflow.
+    /// Also fails if the state is not a 64-bit integer (signed or unsigned) or there is an
+    /// overflow.
     #[strict_type(tag = 0x11)]
     Add(StateSelector, StateSelector),
 
     /// Substracts the second state from the first state, expecting both to be integers.
     ///
+    /// If both states are unsigned integers, the result is an unsigned integer; otherwise, both
+    /// states are converted into signed integers and the result is a signed integer.
+    ///
     /// Fails if any of the state is not defined or contains multiple elements.
-    /// Also fails if the state is not an unsigned 64-bit integer or there is an overflow.
+    /// Also fails if the state is not a 64-bit integer (signed or unsigned) or there is an
+    /// overflow.
     #[strict_type(tag = 0x12)]
     Sub(StateSelector, StateSelector),
 
     /// Product two states of different types, expecting them to be integers.
     ///
+    /// If both states are unsigned integers, the result is an unsigned integer; otherwise, both
+    /// states are converted into signed integers and the result is a signed integer.
+    ///
     /// Fails if any of the state is not defined or contains multiple elements.
-    /// Also fails if the state is not an unsigned 64-bit integer or there is an overflow.
+    /// Also fails if the state is not a 64-bit integer (signed or unsigned) or there is an
+    /// overflow.
     #[strict_type(tag = 0x13)]
     Mul(StateSelector, StateSelector),
 
     /// Divide the first state on the second state, expecting them to be integers.
     /// The resulting value is rounded towards zero.
     ///
+    /// If both states are unsigned integers, the result is an unsigned integer; otherwise, both
+    /// states are converted into signed integers and the result is a signed integer.
+    ///
     /// Fails if any of the state is not defined or contains multiple elements.
-    /// Also fails if the state is not an unsigned 64-bit integer, or the second state is zero.
+    /// Also fails if the state is not a 64-bit integer (signed or unsigned), the second state is
+    /// zero, or there is an overflow.
     #[strict_type(tag = 0x14)]
     Div(StateSelector, StateSelector),
 
     /// Modulo-divide the first state on the second state, expecting them to be integers.
     ///
+    /// If both states are unsigned integers, the result is an unsigned integer; otherwise, both
+    /// states are converted into signed integers and the result is a signed integer.
+    ///
     /// Fails if any of the state is not defined or contains multiple elements.
-    /// Also fails if the state is not an unsigned 64-bit integer, or the second state is zero.
+    /// Also fails if the state is not a 64-bit integer (signed or unsigned), the second state is
+    /// zero, or there is an overflow.
     #[strict_type(tag = 0x15)]
     Rem(StateSelector, StateSelector),
 
     /// Exponentiates the first state with the second state, expecting them to be integers.
     /// The resulting value is rounded towards zero.
     ///
+    /// If both states are unsigned integers, the result is an unsigned integer; otherwise, both
+    /// states are converted into signed integers and the result is a signed integer.
+    ///
     /// Fails if any of the state is not defined or contains multiple elements.
-    /// Also fails if the first state is not an unsigned 64-bit integer,
-    /// the second state is not an unsigned 32-bit integer, or there is an overflow.
+    /// Also fails if the first state is not a 64-bit integer (signed or unsigned),
+    /// the second state is not a non-negative 32-bit integer, or there is an overflow.
     #[strict_type(tag = 0x16)]
     Exp(StateSelector, StateSelector),
 
@@ -316,6 +515,9 @@ pub enum SubAggregator {
 
     /// Convert a verified state under the same state type into an ordered set.
     ///
+    /// Newtype-like tuples in the verified state are unwrapped, such that tuple-wrapped and bare
+    /// values are treated as the same set element.
+    ///
     /// Acts only on a global state; doesn't recognize aggregated state.
     ///
     /// If the global state with the name is absent returns an empty set.
@@ -326,6 +528,9 @@ pub enum SubAggregator {
     /// when the field-based element state repeats, it is ignored and only the initial state is
     /// kept.
     ///
+    /// Newtype-like tuples in the field-based element state are unwrapped before being used as
+    /// map keys, such that tuple-wrapped and bare values are treated as the same key.
+    ///
     /// The map is sorted by its values, lexicographically.
     ///
     /// Acts only on a global state; doesn't recognize aggregated state.
@@ -349,6 +554,9 @@ pub enum SubAggregator {
     /// when the field-based element state repeats, the list is extended with the non-verifiable
     /// state.
     ///
+    /// Newtype-like tuples in the field-based element state are unwrapped before being used as
+    /// map keys, such that tuple-wrapped and bare values are treated as the same key.
+    ///
     /// The map is ordered according to the global state element order.
     ///
     /// Acts only on a global state; doesn't recognize aggregated state.
@@ -372,6 +580,9 @@ pub enum SubAggregator {
     /// when the field-based element state repeats, the set is extended with the non-verifiable
     /// state.
     ///
+    /// Newtype-like tuples in the field-based element state are unwrapped before being used as
+    /// map keys, such that tuple-wrapped and bare values are treated as the same key.
+    ///
     /// The map is ordered according to the global state element order.
     ///
     /// Acts only on a global state; doesn't recognize aggregated state.
@@ -391,6 +602,69 @@ pub enum SubAggregator {
     #[strict_type(tag = 0x25)]
     MapV2SetU(StateName),
 
+    /// Map from the position of a global state element to its verifiable state.
+    ///
+    /// Unlike [`Self::SetV`], all elements are preserved, including duplicates; the keys are
+    /// unsigned integers starting from zero and following the global state element order.
+    ///
+    /// Acts only on a global state; doesn't recognize aggregated state.
+    ///
+    /// If the global state with the name is absent returns an empty map.
+    #[strict_type(tag = 0x26)]
+    IndexedListV(StateName),
+
+    /// Count the number of elements of the global state which verifiable part is an enum or a
+    /// union with a specific variant.
+    ///
+    /// The variant is matched either by its name or by its ordinal, depending on how the enum tag
+    /// is represented in the verified state.
+    ///
+    /// Acts only on a global state; doesn't recognize aggregated state.
+    ///
+    /// If the global state with the name is absent returns zero.
+    #[strict_type(tag = 0x27)]
+    CountWhereTag(StateName, VariantName, u8),
+
+    /// Map from each distinct verifiable state of a global state to the number of times it
+    /// appears.
+    ///
+    /// Unlike [`Self::CountUnique`], which produces just the number of distinct elements, provides
+    /// a count for each of them.
+    ///
+    /// Newtype-like tuples in the verified state are unwrapped before being used as map keys, such
+    /// that tuple-wrapped and bare values are treated as the same key.
+    ///
+    /// The map is ordered according to the first appearance of each key in the global state.
+    ///
+    /// Acts only on a global state; doesn't recognize aggregated state.
+    ///
+    /// If the global state with the name is absent returns an empty map.
+    #[strict_type(tag = 0x28)]
+    CountBy(StateName),
+
+    /// Joins verifiable string state of a global state into a single string, putting the provided
+    /// separator between the elements.
+    ///
+    /// The strings are joined according to the global state element order.
+    ///
+    /// Acts only on a global state; doesn't recognize aggregated state.
+    ///
+    /// Fails if any of the elements is not a string. If the global state with the name is absent
+    /// returns an empty string.
+    ///
+    /// # Nota bene
+    ///
+    /// The global state does not have only a partial ordering (it is a lattice).
+    ///
+    /// It is only in the case when one operation depends on outputs of another
+    /// (via global or owned state) there is a guarantee that the global state
+    /// defined by the second operation will always follow the first one.
+    ///
+    /// It is the responsibility of the codex developer
+    /// to ensure non-ambiguity when this aggregator is used.
+    #[strict_type(tag = 0x29)]
+    ConcatV(StateName, TinyString),
+
     /// Sums over verifiable part of a global state.
     ///
     /// Acts only on a global state; doesn't recognize aggregated state.
@@ -428,6 +702,76 @@ pub enum SubAggregator {
     /// If any of the elements of the global state are not an unsigner integer, treats them as one.
     #[strict_type(tag = 0x33)]
     ProdOrDefault(StateName),
+
+    /// Takes the smallest of the elements of a global state, taking their verifiable part.
+    ///
+    /// Acts only on a global state; doesn't recognize aggregated state.
+    ///
+    /// Fails if the global state doesn't have any elements, or the state type is not an unsigned
+    /// integer.
+    #[strict_type(tag = 0x34)]
+    Min(StateName),
+
+    /// Takes the largest of the elements of a global state, taking their verifiable part.
+    ///
+    /// Acts only on a global state; doesn't recognize aggregated state.
+    ///
+    /// Fails if the global state doesn't have any elements, or the state type is not an unsigned
+    /// integer.
+    #[strict_type(tag = 0x35)]
+    Max(StateName),
+
+    /// Takes an arithmetic mean of the elements of a global state, taking their verifiable part.
+    /// The resulting value is rounded towards zero.
+    ///
+    /// Acts only on a global state; doesn't recognize aggregated state.
+    ///
+    /// Fails if the global state doesn't have any elements, or the state type is not an unsigned
+    /// integer.
+    #[strict_type(tag = 0x36)]
+    Avg(StateName),
+
+    /// Checks whether any of the elements of a global state has its verifiable part equal to the
+    /// provided strict-encoded value, producing a boolean.
+    ///
+    /// Acts only on a global state; doesn't recognize aggregated state.
+    ///
+    /// If the global state with the name is absent or has no elements, produces `false`.
+    #[strict_type(tag = 0x40)]
+    Any(StateName, SemId, TinyBlob),
+
+    /// Checks whether all the elements of a global state have their verifiable part equal to the
+    /// provided strict-encoded value, producing a boolean.
+    ///
+    /// Acts only on a global state; doesn't recognize aggregated state.
+    ///
+    /// If the global state with the name is absent or has no elements, produces `true`.
+    #[strict_type(tag = 0x41)]
+    All(StateName, SemId, TinyBlob),
+
+    /// Checks whether any of the elements of a global state has its verifiable part being an enum
+    /// or a union with a specific variant, producing a boolean.
+    ///
+    /// The variant is matched either by its name or by its ordinal, like in
+    /// [`Self::CountWhereTag`].
+    ///
+    /// Acts only on a global state; doesn't recognize aggregated state.
+    ///
+    /// If the global state with the name is absent or has no elements, produces `false`.
+    #[strict_type(tag = 0x42)]
+    AnyTag(StateName, VariantName, u8),
+
+    /// Checks whether all the elements of a global state have their verifiable part being an enum
+    /// or a union with a specific variant, producing a boolean.
+    ///
+    /// The variant is matched either by its name or by its ordinal, like in
+    /// [`Self::CountWhereTag`].
+    ///
+    /// Acts only on a global state; doesn't recognize aggregated state.
+    ///
+    /// If the global state with the name is absent or has no elements, produces `true`.
+    #[strict_type(tag = 0x43)]
+    AllTag(StateName, VariantName, u8),
 }
 
 impl SubAggregator {
@@ -435,7 +779,8 @@ impl SubAggregator {
     /// and which needs to be computed before running this aggregator.
     pub fn depends_on(&self) -> Vec<&StateName> {
         match self {
-            Self::Neg(StateSelector::Aggregated(state))
+            Self::Copy(state)
+            | Self::Neg(StateSelector::Aggregated(state))
             | Self::Add(StateSelector::Global(_, _), StateSelector::Aggregated(state))
             | Self::Sub(StateSelector::Global(_, _), StateSelector::Aggregated(state))
             | Self::Mul(StateSelector::Global(_, _), StateSelector::Aggregated(state))
@@ -460,7 +805,6 @@ impl SubAggregator {
             | Self::TheOnly(_)
             | Self::Count(_)
             | Self::CountUnique(_)
-            | Self::Copy(_)
             | Self::Unwrap(_)
             | Self::First(_)
             | Self::Nth(_, _)
@@ -477,10 +821,21 @@ impl SubAggregator {
             | Self::MapV2U(_)
             | Self::MapV2ListU(_)
             | Self::MapV2SetU(_)
+            | Self::IndexedListV(_)
+            | Self::CountWhereTag(_, _, _)
+            | Self::CountBy(_)
+            | Self::ConcatV(_, _)
             | Self::SumUnwrap(_)
             | Self::SumOrDefault(_)
             | Self::ProdUnwrap(_)
-            | Self::ProdOrDefault(_) => vec![],
+            | Self::ProdOrDefault(_)
+            | Self::Min(_)
+            | Self::Max(_)
+            | Self::Avg(_)
+            | Self::Any(_, _, _)
+            | Self::All(_, _, _)
+            | Self::AnyTag(_, _, _)
+            | Self::AllTag(_, _, _) => vec![],
         }
     }
 
@@ -489,120 +844,153 @@ impl SubAggregator {
     /// # Returns
     ///
     /// Aggregated state value.
-    /// If the computing fails due to any exception, `None`.
+    /// If the computing fails due to any exception, `None`; use [`Self::try_aggregate`] to learn
+    /// the reason of the failure.
     pub fn aggregate(
         &self,
         global: &BTreeMap<StateName, BTreeMap<CellAddr, StateAtom>>,
         aggregated: &BTreeMap<StateName, StrictVal>,
         types: &TypeSystem,
     ) -> Option<StrictVal> {
-        let get_u64 = |sel: &StateSelector| -> Option<u64> {
-            let state = match sel {
+        self.try_aggregate(global, aggregated, types).ok()
+    }
+
+    /// Compute state via applying some aggregator function, reporting the reason of a failure.
+    ///
+    /// # Returns
+    ///
+    /// Aggregated state value, or an error explaining why the state can't be computed.
+    pub fn try_aggregate(
+        &self,
+        global: &BTreeMap<StateName, BTreeMap<CellAddr, StateAtom>>,
+        aggregated: &BTreeMap<StateName, StrictVal>,
+        types: &TypeSystem,
+    ) -> Result<StrictVal, AggregateError> {
+        let get_global = |name: &StateName| {
+            global
+                .get(name)
+                .ok_or_else(|| AggregateError::GlobalAbsent(name.clone()))
+        };
+        let get_the_only = |name: &StateName| {
+            let state = get_global(name)?;
+            match state.len() {
+                0 => Err(AggregateError::NoElements(name.clone())),
+                1 => Ok(state.first_key_value().expect("state has one element").1),
+                _ => Err(AggregateError::MultipleElements(name.clone())),
+            }
+        };
+        let get_num = |sel: &StateSelector| -> Result<StrictNum, AggregateError> {
+            let (name, state) = match sel {
                 StateSelector::Global(name, first) => {
-                    let map = global.get(name)?;
-                    if map.len() != 1 && !*first {
-                        return None;
-                    }
-                    let (_, atom) = map.first_key_value()?;
-                    &atom.verified
+                    let atom = if *first {
+                        let (_, atom) = get_global(name)?
+                            .first_key_value()
+                            .ok_or_else(|| AggregateError::NoElements(name.clone()))?;
+                        atom
+                    } else {
+                        get_the_only(name)?
+                    };
+                    (name, &atom.verified)
                 }
-                StateSelector::Aggregated(name) => aggregated.get(name)?,
+                StateSelector::Aggregated(name) => (
+                    name,
+                    aggregated
+                        .get(name)
+                        .ok_or_else(|| AggregateError::AggregatedAbsent(name.clone()))?,
+                ),
             };
             match state {
-                StrictVal::Number(StrictNum::Uint(val)) => Some(*val),
-                _ => None,
+                StrictVal::Number(num @ (StrictNum::Uint(_) | StrictNum::Int(_))) => Ok(*num),
+                _ => Err(AggregateError::NotInteger(name.clone())),
+            }
+        };
+        let calc = |a: &StateSelector,
+                    b: &StateSelector,
+                    uint: fn(u64, u64) -> Option<u64>,
+                    int: fn(i64, i64) -> Option<i64>|
+         -> Result<StrictVal, AggregateError> {
+            match (get_num(a)?, get_num(b)?) {
+                (StrictNum::Uint(a), StrictNum::Uint(b)) => uint(a, b).map(|val| svnum!(val)),
+                (a, b) => num_to_i64(a)
+                    .zip(num_to_i64(b))
+                    .and_then(|(a, b)| int(a, b))
+                    .map(|val| svnum!(val)),
             }
+            .ok_or(AggregateError::Overflow)
+        };
+        let get_uint = |name: &StateName, atom: &StateAtom| match &atom.verified {
+            StrictVal::Number(StrictNum::Uint(val)) => Ok(*val),
+            _ => Err(AggregateError::NotInteger(name.clone())),
         };
 
-        match self {
-            Self::Const(sem_id, val) => deserialize(*sem_id, val, types),
+        Ok(match self {
+            Self::Const(sem_id, val) => deserialize(*sem_id, val, types)?,
 
-            Self::TheOnly(name) => {
-                let state = global.get(name)?;
-                if state.len() != 1 {
-                    return None;
-                }
-                Some(state.first_key_value()?.1.verified.clone())
-            }
+            Self::TheOnly(name) => get_the_only(name)?.verified.clone(),
 
-            Self::Copy(name) => aggregated.get(name).cloned(),
+            Self::Copy(name) => aggregated
+                .get(name)
+                .cloned()
+                .ok_or_else(|| AggregateError::AggregatedAbsent(name.clone()))?,
 
             Self::Unwrap(name) => {
-                let state = global.get(name)?;
-                if state.len() != 1 {
-                    return None;
-                }
-                let (_, atom) = state.first_key_value()?;
+                let atom = get_the_only(name)?;
                 let StrictVal::Union(tag, sv) = &atom.verified else {
-                    return None;
+                    return Err(AggregateError::NotSome(name.clone()));
                 };
-                Some(match tag {
+                match tag {
                     EnumTag::Name(name) if name.as_str() == "some" => sv.as_ref().clone(),
                     EnumTag::Ord(1) => sv.as_ref().clone(),
-                    _ => return None,
-                })
+                    _ => return Err(AggregateError::NotSome(name.clone())),
+                }
             }
 
             Self::First(name) => {
-                let state = global.get(name)?;
-                Some(state.first_key_value()?.1.verified.clone())
+                let state = get_global(name)?;
+                let (_, atom) = state
+                    .first_key_value()
+                    .ok_or_else(|| AggregateError::NoElements(name.clone()))?;
+                atom.verified.clone()
             }
 
             Self::Nth(name, pos) => {
-                let state = global.get(name)?;
-                Some(state.iter().nth(*pos as usize)?.1.verified.clone())
+                let state = get_global(name)?;
+                let (_, atom) = state
+                    .iter()
+                    .nth(*pos as usize)
+                    .ok_or_else(|| AggregateError::OutOfRange(name.clone(), *pos))?;
+                atom.verified.clone()
             }
 
             Self::Last(name) => {
-                let state = global.get(name)?;
-                Some(state.last_key_value()?.1.verified.clone())
+                let state = get_global(name)?;
+                let (_, atom) = state
+                    .last_key_value()
+                    .ok_or_else(|| AggregateError::NoElements(name.clone()))?;
+                atom.verified.clone()
             }
 
             Self::NthBack(name, pos) => {
-                let state = global.get(name)?;
-                Some(state.iter().nth_back(*pos as usize)?.1.verified.clone())
+                let state = get_global(name)?;
+                let (_, atom) = state
+                    .iter()
+                    .nth_back(*pos as usize)
+                    .ok_or_else(|| AggregateError::OutOfRange(name.clone(), *pos))?;
+                atom.verified.clone()
             }
 
             Self::Neg(name) => {
-                let val = get_u64(name)?;
-                let neg = (val as i64).checked_neg()?;
-                Some(svnum!(neg))
-            }
-            Self::Add(a, b) => {
-                let a = get_u64(a)?;
-                let b = get_u64(b)?;
-                let sum = a.checked_add(b)?;
-                Some(svnum!(sum))
-            }
-            Self::Sub(a, b) => {
-                let a = get_u64(a)?;
-                let b = get_u64(b)?;
-                let sub = a.checked_sub(b)?;
-                Some(svnum!(sub))
-            }
-            Self::Mul(a, b) => {
-                let a = get_u64(a)?;
-                let b = get_u64(b)?;
-                let sub = a.checked_mul(b)?;
-                Some(svnum!(sub))
-            }
-            Self::Div(a, b) => {
-                let a = get_u64(a)?;
-                let b = get_u64(b)?;
-                let sub = a.checked_div(b)?;
-                Some(svnum!(sub))
-            }
-            Self::Rem(a, b) => {
-                let a = get_u64(a)?;
-                let b = get_u64(b)?;
-                let sub = a.checked_rem(b)?;
-                Some(svnum!(sub))
+                let val = num_to_i64(get_num(name)?).ok_or(AggregateError::Overflow)?;
+                let neg = val.checked_neg().ok_or(AggregateError::Overflow)?;
+                svnum!(neg)
             }
+            Self::Add(a, b) => calc(a, b, u64::checked_add, i64::checked_add)?,
+            Self::Sub(a, b) => calc(a, b, u64::checked_sub, i64::checked_sub)?,
+            Self::Mul(a, b) => calc(a, b, u64::checked_mul, i64::checked_mul)?,
+            Self::Div(a, b) => calc(a, b, u64::checked_div, i64::checked_div)?,
+            Self::Rem(a, b) => calc(a, b, u64::checked_rem, i64::checked_rem)?,
             Self::Exp(a, b) => {
-                let a = get_u64(a)?;
-                let b = get_u64(b)?;
-                let sub = a.checked_pow(b.try_into().ok()?)?;
-                Some(svnum!(sub))
+                calc(a, b, |a, b| a.checked_pow(b.try_into().ok()?), |a, b| a.checked_pow(b.try_into().ok()?))?
             }
 
             Self::Count(name) => {
@@ -611,62 +999,66 @@ impl SubAggregator {
                     .into_iter()
                     .flat_map(BTreeMap::values)
                     .count();
-                Some(svnum!(count as u64))
+                svnum!(count as u64)
             }
 
             Self::CountUnique(name) => {
                 let mut unique = Vec::new();
-                for item in global.get(name)?.values() {
+                for item in get_global(name)?.values() {
                     if !unique.contains(&item) {
                         unique.push(item);
                     }
                 }
-                Some(svnum!(unique.len() as u64))
+                svnum!(unique.len() as u64)
             }
 
             Self::SetV(name) => {
                 let mut set = Vec::new();
                 for state in global.get(name).into_iter().flat_map(BTreeMap::values) {
-                    if !set.contains(&state.verified) {
-                        set.push(state.verified.clone());
+                    let item = reduce_tuples(state.verified.clone());
+                    if !set.contains(&item) {
+                        set.push(item);
                     }
                 }
-                Some(StrictVal::Set(set))
+                StrictVal::Set(set)
             }
 
             Self::MapV2U(name) => {
                 let mut map = Vec::new();
-                for atom in global.get(name)?.values() {
+                for atom in get_global(name)?.values() {
                     let Some(val) = &atom.unverified else { continue };
-                    if map.iter().any(|(key, _)| &atom.verified == key) {
+                    let verified = reduce_tuples(atom.verified.clone());
+                    if map.iter().any(|(key, _)| &verified == key) {
                         continue;
                     }
-                    map.push((atom.verified.clone(), val.clone()));
+                    map.push((verified, val.clone()));
                 }
-                Some(StrictVal::Map(map))
+                StrictVal::Map(map)
             }
 
             Self::MapV2ListU(name) => {
                 let mut map = Vec::<(StrictVal, StrictVal)>::new();
-                for atom in global.get(name)?.values() {
+                for atom in get_global(name)?.values() {
                     let Some(val) = &atom.unverified else { continue };
-                    if let Some((_key, list)) = map.iter_mut().find(|(key, _)| &atom.verified == key) {
+                    let verified = reduce_tuples(atom.verified.clone());
+                    if let Some((_key, list)) = map.iter_mut().find(|(key, _)| &verified == key) {
                         let StrictVal::List(list) = list else {
                             unreachable!();
                         };
                         list.push(val.clone());
                     } else {
-                        map.push((atom.verified.clone(), StrictVal::List(vec![val.clone()])));
+                        map.push((verified, StrictVal::List(vec![val.clone()])));
                     }
                 }
-                Some(StrictVal::Map(map))
+                StrictVal::Map(map)
             }
 
             Self::MapV2SetU(name) => {
                 let mut map = Vec::<(StrictVal, StrictVal)>::new();
-                for atom in global.get(name)?.values() {
+                for atom in get_global(name)?.values() {
                     let Some(val) = &atom.unverified else { continue };
-                    if let Some((_key, list)) = map.iter_mut().find(|(key, _)| &atom.verified == key) {
+                    let verified = reduce_tuples(atom.verified.clone());
+                    if let Some((_key, list)) = map.iter_mut().find(|(key, _)| &verified == key) {
                         let StrictVal::Set(list) = list else {
                             unreachable!();
                         };
@@ -674,10 +1066,61 @@ impl SubAggregator {
                             list.push(val.clone());
                         }
                     } else {
-                        map.push((atom.verified.clone(), StrictVal::Set(vec![val.clone()])));
+                        map.push((verified, StrictVal::Set(vec![val.clone()])));
                     }
                 }
-                Some(StrictVal::Map(map))
+                StrictVal::Map(map)
+            }
+
+            Self::IndexedListV(name) => {
+                let map = global
+                    .get(name)
+                    .into_iter()
+                    .flat_map(BTreeMap::values)
+                    .enumerate()
+                    .map(|(index, atom)| (svnum!(index as u64), atom.verified.clone()))
+                    .collect();
+                StrictVal::Map(map)
+            }
+
+            Self::CountWhereTag(name, variant, ord) => {
+                let count = global
+                    .get(name)
+                    .into_iter()
+                    .flat_map(BTreeMap::values)
+                    .filter(|atom| has_tag(&atom.verified, variant, *ord))
+                    .count();
+                svnum!(count as u64)
+            }
+
+            Self::CountBy(name) => {
+                let mut map = Vec::<(StrictVal, u64)>::new();
+                for atom in global.get(name).into_iter().flat_map(BTreeMap::values) {
+                    let verified = reduce_tuples(atom.verified.clone());
+                    if let Some((_, count)) = map.iter_mut().find(|(key, _)| &verified == key) {
+                        *count += 1;
+                    } else {
+                        map.push((verified, 1));
+                    }
+                }
+                let map = map
+                    .into_iter()
+                    .map(|(key, count)| (key, svnum!(count)))
+                    .collect();
+                StrictVal::Map(map)
+            }
+
+            Self::ConcatV(name, separator) => {
+                let strings = global
+                    .get(name)
+                    .into_iter()
+                    .flat_map(BTreeMap::values)
+                    .map(|atom| match &atom.verified {
+                        StrictVal::String(s) => Ok(s.as_str()),
+                        _ => Err(AggregateError::NotString(name.clone())),
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                StrictVal::String(strings.join(separator.as_str()))
             }
 
             Self::SumUnwrap(name) => {
@@ -685,11 +1128,11 @@ impl SubAggregator {
                     .get(name)
                     .into_iter()
                     .flat_map(BTreeMap::values)
-                    .try_fold(0u64, |sum, val| match &val.verified {
-                        StrictVal::Number(StrictNum::Uint(val)) => sum.checked_add(*val),
-                        _ => None,
+                    .try_fold(0u64, |sum, atom| {
+                        sum.checked_add(get_uint(name, atom)?)
+                            .ok_or(AggregateError::Overflow)
                     })?;
-                Some(svnum!(sum))
+                svnum!(sum)
             }
 
             Self::SumOrDefault(name) => {
@@ -697,45 +1140,132 @@ impl SubAggregator {
                     .get(name)
                     .into_iter()
                     .flat_map(BTreeMap::values)
-                    .try_fold(0u64, |sum, val| match &val.verified {
-                        StrictVal::Number(StrictNum::Uint(val)) => sum.checked_add(*val),
-                        _ => Some(sum),
-                    })?;
-                Some(svnum!(sum))
+                    .try_fold(0u64, |sum, atom| sum.checked_add(get_uint(name, atom).unwrap_or_default()))
+                    .ok_or(AggregateError::Overflow)?;
+                svnum!(sum)
             }
 
             Self::ProdUnwrap(name) => {
-                let sum = global
+                let prod = global
                     .get(name)
                     .into_iter()
                     .flat_map(BTreeMap::values)
-                    .try_fold(1u64, |prod, val| match &val.verified {
-                        StrictVal::Number(StrictNum::Uint(val)) => prod.checked_mul(*val),
-                        _ => None,
+                    .try_fold(1u64, |prod, atom| {
+                        prod.checked_mul(get_uint(name, atom)?)
+                            .ok_or(AggregateError::Overflow)
                     })?;
-                Some(svnum!(sum))
+                svnum!(prod)
             }
 
             Self::ProdOrDefault(name) => {
-                let sum = global
+                let prod = global
                     .get(name)
                     .into_iter()
                     .flat_map(BTreeMap::values)
-                    .try_fold(1u64, |prod, val| match &val.verified {
-                        StrictVal::Number(StrictNum::Uint(val)) => prod.checked_mul(*val),
-                        _ => Some(prod),
-                    })?;
-                Some(svnum!(sum))
+                    .try_fold(1u64, |prod, atom| prod.checked_mul(get_uint(name, atom).unwrap_or(1)))
+                    .ok_or(AggregateError::Overflow)?;
+                svnum!(prod)
             }
-        }
+
+            Self::Min(name) => {
+                let min = get_global(name)?
+                    .values()
+                    .map(|atom| get_uint(name, atom))
+                    .try_fold(None, |min: Option<u64>, val| val.map(|val| Some(min.map_or(val, |min| min.min(val)))))?
+                    .ok_or_else(|| AggregateError::NoElements(name.clone()))?;
+                svnum!(min)
+            }
+
+            Self::Max(name) => {
+                let max = get_global(name)?
+                    .values()
+                    .map(|atom| get_uint(name, atom))
+                    .try_fold(None, |max: Option<u64>, val| val.map(|val| Some(max.map_or(val, |max| max.max(val)))))?
+                    .ok_or_else(|| AggregateError::NoElements(name.clone()))?;
+                svnum!(max)
+            }
+
+            Self::Avg(name) => {
+                let state = get_global(name)?;
+                let count = state.len() as u128;
+                if count == 0 {
+                    return Err(AggregateError::NoElements(name.clone()));
+                }
+                // Summing into u128 can't overflow for any realistic number of u64 elements.
+                let sum = state
+                    .values()
+                    .try_fold(0u128, |sum, atom| Ok(sum + get_uint(name, atom)? as u128))?;
+                svnum!((sum / count) as u64)
+            }
+
+            Self::Any(name, sem_id, val) => {
+                // Verified state is stored with newtype tuples reduced, so the reference must be too
+                let reference = reduce_tuples(deserialize(*sem_id, val, types)?);
+                let any = global
+                    .get(name)
+                    .into_iter()
+                    .flat_map(BTreeMap::values)
+                    .any(|atom| atom.verified == reference);
+                StrictVal::bool(any)
+            }
+
+            Self::All(name, sem_id, val) => {
+                // Verified state is stored with newtype tuples reduced, so the reference must be too
+                let reference = reduce_tuples(deserialize(*sem_id, val, types)?);
+                let all = global
+                    .get(name)
+                    .into_iter()
+                    .flat_map(BTreeMap::values)
+                    .all(|atom| atom.verified == reference);
+                StrictVal::bool(all)
+            }
+
+            Self::AnyTag(name, variant, ord) => {
+                let any = global
+                    .get(name)
+                    .into_iter()
+                    .flat_map(BTreeMap::values)
+                    .any(|atom| has_tag(&atom.verified, variant, *ord));
+                StrictVal::bool(any)
+            }
+
+            Self::AllTag(name, variant, ord) => {
+                let all = global
+                    .get(name)
+                    .into_iter()
+                    .flat_map(BTreeMap::values)
+                    .all(|atom| has_tag(&atom.verified, variant, *ord));
+                StrictVal::bool(all)
+            }
+        })
     }
 }
 
-fn deserialize(sem_id: SemId, val: &TinyBlob, types: &TypeSystem) -> Option<StrictVal> {
+fn num_to_i64(num: StrictNum) -> Option<i64> {
+    match num {
+        StrictNum::Uint(val) => val.try_into().ok(),
+        StrictNum::Int(val) => Some(val),
+        _ => None,
+    }
+}
+
+/// Checks whether a value is an enum or a union with the variant matching either the provided name
+/// or the ordinal, depending on how the enum tag is represented in the value.
+fn has_tag(val: &StrictVal, variant: &VariantName, ord: u8) -> bool {
+    let (StrictVal::Enum(tag) | StrictVal::Union(tag, _)) = val else {
+        return false;
+    };
+    match tag {
+        EnumTag::Name(name) => name == variant,
+        EnumTag::Ord(no) => *no == ord,
+    }
+}
+
+fn deserialize(sem_id: SemId, val: &TinyBlob, types: &TypeSystem) -> Result<StrictVal, AggregateError> {
     let ty = types
         .strict_deserialize_type(sem_id, val.as_slice())
-        .unwrap();
-    Some(ty.unbox())
+        .map_err(|_| AggregateError::InvalidReference(sem_id))?;
+    Ok(ty.unbox())
 }
 
 #[cfg(test)]
@@ -743,11 +1273,37 @@ mod test {
     #![cfg_attr(coverage_nightly, coverage(off))]
 
     use aluvm::aluasm;
+    use strict_encoding::StrictDumb;
     use strict_types::stl::std_stl;
-    use strict_types::SystemBuilder;
+    use strict_types::{LibBuilder, SymbolicSys, SystemBuilder, TypeLib};
+    use ultrasonic::uasm;
 
     use super::*;
 
+    const LIB_NAME_TEST: &str = "Test";
+
+    #[derive(Copy, Clone, Eq, PartialEq, Debug)]
+    #[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+    #[strict_type(lib = LIB_NAME_TEST)]
+    struct Supply(u64);
+
+    fn stl() -> TypeLib {
+        LibBuilder::with(libname!(LIB_NAME_TEST), [std_stl().to_dependency_types()])
+            .transpile::<Supply>()
+            .compile()
+            .expect("invalid Test type library")
+    }
+    fn sys() -> SymbolicSys {
+        SystemBuilder::new()
+            .import(std_stl())
+            .unwrap()
+            .import(stl())
+            .unwrap()
+            .finalize()
+            .unwrap()
+    }
+    fn supply() -> SemId { *sys().resolve("Test.Supply").unwrap() }
+
     fn addr(no: u16) -> CellAddr { CellAddr::new(strict_dumb!(), no) }
     fn state() -> BTreeMap<StateName, BTreeMap<CellAddr, StateAtom>> {
         bmap! {
@@ -778,16 +1334,12 @@ mod test {
         }
     }
     fn types() -> TypeSystem {
-        let sys = SystemBuilder::new()
-            .import(std_stl())
-            .unwrap()
-            .finalize()
-            .unwrap();
         let types = std_stl()
             .types
             .into_iter()
+            .chain(stl().types)
             .map(|(tn, ty)| ty.sem_id_named(&tn));
-        sys.as_types().extract(types).unwrap()
+        sys().as_types().extract(types).unwrap()
     }
     fn success_lib() -> Lib {
         let code = aluasm! { ret; };
@@ -798,15 +1350,21 @@ mod test {
             .aggregate(&state(), &none!(), &[success_lib()], &types())
             .unwrap()
     }
-    fn call2(aggregator: &Aggregator) -> StrictVal {
-        let aggregated = bmap! {
+    fn aggregated() -> BTreeMap<StateName, StrictVal> {
+        bmap! {
             vname!("zero") => svnum!(0u64),
             vname!("two") => svnum!(2u64),
             vname!("three") => svnum!(3u64),
             vname!("str") => svstr!("Hi"),
-        };
+            vname!("minusTwo") => StrictVal::Number(StrictNum::Int(-2)),
+            vname!("minusFive") => StrictVal::Number(StrictNum::Int(-5)),
+            vname!("minInt") => StrictVal::Number(StrictNum::Int(i64::MIN)),
+            vname!("maxUint") => svnum!(u64::MAX),
+        }
+    }
+    fn call2(aggregator: &Aggregator) -> StrictVal {
         aggregator
-            .aggregate(&state(), &aggregated, &[success_lib()], &types())
+            .aggregate(&state(), &aggregated(), &[success_lib()], &types())
             .unwrap()
     }
 
@@ -868,6 +1426,58 @@ mod test {
         assert_eq!(agg.depends_on().count(), 0);
     }
 
+    #[test]
+    fn min_max() {
+        independent(Aggregator::Take(SubAggregator::Min(vname!("verified"))), svnum!(1u64));
+        independent(Aggregator::Take(SubAggregator::Max(vname!("verified"))), svnum!(5u64));
+        independent(Aggregator::Take(SubAggregator::Min(vname!("pairs"))), svnum!(1u64));
+        independent(Aggregator::Take(SubAggregator::Max(vname!("pairs"))), svnum!(5u64));
+    }
+
+    #[test]
+    fn min_max_fail() {
+        for agg in [
+            SubAggregator::Min(vname!("unverified")),
+            SubAggregator::Max(vname!("unverified")),
+            SubAggregator::Min(vname!("nonExisting")),
+            SubAggregator::Max(vname!("nonExisting")),
+        ] {
+            let agg = Aggregator::Take(agg);
+            assert_eq!(agg.aggregate(&state(), &none!(), &[success_lib()], &types()), None);
+        }
+
+        let empty = bmap! { vname!("empty") => bmap! {} };
+        let agg = Aggregator::Take(SubAggregator::Max(vname!("empty")));
+        assert_eq!(agg.aggregate(&empty, &none!(), &[success_lib()], &types()), None);
+    }
+
+    #[test]
+    fn avg() {
+        independent(Aggregator::Take(SubAggregator::Avg(vname!("verified"))), svnum!(20u64 / 6));
+        independent(Aggregator::Take(SubAggregator::Avg(vname!("pairs"))), svnum!(20u64 / 6));
+
+        let state = bmap! {
+            vname!("single") => bmap! { addr(0) => StateAtom::new_verified(7u64) },
+            vname!("rounded") => bmap! {
+                addr(0) => StateAtom::new_verified(1u64),
+                addr(1) => StateAtom::new_verified(2u64),
+                addr(2) => StateAtom::new_verified(2u64),
+            },
+            vname!("empty") => bmap! {},
+        };
+        let call = |name: &'static str| {
+            Aggregator::Take(SubAggregator::Avg(vname!(name))).aggregate(&state, &none!(), &[], &types())
+        };
+        assert_eq!(call("single"), Some(svnum!(7u64)));
+        assert_eq!(call("rounded"), Some(svnum!(1u64)));
+        assert_eq!(call("empty"), None);
+        assert_eq!(call("nonExisting"), None);
+    }
+
+    #[test]
+    #[should_panic]
+    fn avg_fail() { call(&Aggregator::Take(SubAggregator::Avg(vname!("unverified")))); }
+
     #[test]
     fn add() {
         let agg = Aggregator::Take(SubAggregator::Add(
@@ -955,6 +1565,41 @@ mod test {
         assert_eq!(call2(&agg), svnum!(8u64));
     }
 
+    #[test]
+    fn signed() {
+        let neg = |name: &'static str| Aggregator::Take(SubAggregator::Neg(StateSelector::Aggregated(vname!(name))));
+        let op = |f: fn(StateSelector, StateSelector) -> SubAggregator, a: &'static str, b: &'static str| {
+            Aggregator::Take(f(StateSelector::Aggregated(vname!(a)), StateSelector::Aggregated(vname!(b))))
+        };
+        let int = |val: i64| StrictVal::Number(StrictNum::Int(val));
+
+        assert_eq!(call2(&neg("minusTwo")), int(2));
+        assert_eq!(call2(&op(SubAggregator::Add, "minusTwo", "three")), int(1));
+        assert_eq!(call2(&op(SubAggregator::Sub, "two", "minusFive")), int(7));
+        assert_eq!(call2(&op(SubAggregator::Sub, "minusFive", "two")), int(-7));
+        assert_eq!(call2(&op(SubAggregator::Mul, "minusTwo", "minusFive")), int(10));
+        assert_eq!(call2(&op(SubAggregator::Div, "minusFive", "two")), int(-2));
+        assert_eq!(call2(&op(SubAggregator::Rem, "minusFive", "two")), int(-1));
+        assert_eq!(call2(&op(SubAggregator::Exp, "minusTwo", "three")), int(-8));
+
+        // Unsigned operands still produce unsigned results
+        assert_eq!(call2(&op(SubAggregator::Add, "two", "three")), svnum!(5u64));
+        assert_eq!(call2(&op(SubAggregator::Sub, "three", "two")), svnum!(1u64));
+
+        let fails = [
+            neg("minInt"),
+            neg("maxUint"),
+            op(SubAggregator::Sub, "minInt", "two"),
+            op(SubAggregator::Mul, "minInt", "minusTwo"),
+            op(SubAggregator::Add, "minusTwo", "maxUint"),
+            op(SubAggregator::Div, "minusFive", "zero"),
+            op(SubAggregator::Exp, "two", "minusTwo"),
+        ];
+        for agg in fails {
+            assert_eq!(agg.aggregate(&state(), &aggregated(), &[success_lib()], &types()), None);
+        }
+    }
+
     #[test]
     #[should_panic]
     fn math_sum_fail() { call(&Aggregator::Take(SubAggregator::SumUnwrap(vname!("unverified")))); }
@@ -984,6 +1629,116 @@ mod test {
         independent(Aggregator::Take(SubAggregator::MapV2SetU(vname!("verified"))), StrictVal::Map(none!()));
     }
 
+    #[test]
+    fn indexed_list() {
+        independent(
+            Aggregator::Take(SubAggregator::IndexedListV(vname!("verified"))),
+            StrictVal::Map(vec![
+                (svnum!(0u64), svnum!(5u64)),
+                (svnum!(1u64), svnum!(1u64)),
+                (svnum!(2u64), svnum!(2u64)),
+                (svnum!(3u64), svnum!(3u64)),
+                (svnum!(4u64), svnum!(4u64)),
+                (svnum!(5u64), svnum!(5u64)),
+            ]),
+        );
+        independent(Aggregator::Take(SubAggregator::IndexedListV(vname!("nonExisting"))), StrictVal::Map(none!()));
+    }
+
+    #[test]
+    fn concat() {
+        let log = bmap! {
+            vname!("log") => bmap! {
+                addr(0) => StateAtom::new_verified("first"),
+                addr(1) => StateAtom::new_verified("second"),
+                addr(2) => StateAtom::new_verified("third"),
+            },
+        };
+        let call = |name: &'static str| {
+            let agg = Aggregator::Take(SubAggregator::ConcatV(vname!(name), tiny_s!(",")));
+            agg.try_aggregate(&log, &none!(), &[], &types())
+        };
+        assert_eq!(call("log"), Ok(svstr!("first,second,third")));
+        assert_eq!(call("nonExisting"), Ok(svstr!("")));
+
+        let agg = Aggregator::Take(SubAggregator::ConcatV(vname!("verified"), tiny_s!(",")));
+        assert_eq!(
+            agg.try_aggregate(&state(), &none!(), &[], &types()),
+            Err(AggregateError::NotString(vname!("verified")))
+        );
+    }
+
+    #[test]
+    fn any_all_tag() {
+        let votes = bmap! {
+            vname!("approved") => bmap! {
+                addr(0) => StateAtom::new_verified(StrictVal::enumer("yes")),
+                addr(1) => StateAtom::new_verified(StrictVal::enumer(1u8)),
+            },
+            vname!("mixed") => bmap! {
+                addr(0) => StateAtom::new_verified(StrictVal::enumer("yes")),
+                addr(1) => StateAtom::new_verified(StrictVal::enumer("no")),
+                addr(2) => StateAtom::new_verified(5u64),
+            },
+        };
+        let call = |agg: SubAggregator| Aggregator::Take(agg).aggregate(&votes, &none!(), &[], &types());
+        let any = |name: &'static str| call(SubAggregator::AnyTag(vname!(name), vname!("yes"), 1));
+        let all = |name: &'static str| call(SubAggregator::AllTag(vname!(name), vname!("yes"), 1));
+
+        assert_eq!(any("nonExisting"), Some(StrictVal::bool(false)));
+        assert_eq!(all("nonExisting"), Some(StrictVal::bool(true)));
+        assert_eq!(any("approved"), Some(StrictVal::bool(true)));
+        assert_eq!(all("approved"), Some(StrictVal::bool(true)));
+        assert_eq!(any("mixed"), Some(StrictVal::bool(true)));
+        assert_eq!(all("mixed"), Some(StrictVal::bool(false)));
+        assert_eq!(call(SubAggregator::AnyTag(vname!("mixed"), vname!("abstain"), 2)), Some(StrictVal::bool(false)));
+    }
+
+    #[test]
+    fn count_by() {
+        independent(
+            Aggregator::Take(SubAggregator::CountBy(vname!("verified"))),
+            StrictVal::Map(vec![
+                (svnum!(5u64), svnum!(2u64)),
+                (svnum!(1u64), svnum!(1u64)),
+                (svnum!(2u64), svnum!(1u64)),
+                (svnum!(3u64), svnum!(1u64)),
+                (svnum!(4u64), svnum!(1u64)),
+            ]),
+        );
+        independent(Aggregator::Take(SubAggregator::CountBy(vname!("nonExisting"))), StrictVal::Map(none!()));
+
+        let state = bmap! {
+            vname!("tally") => bmap! {
+                addr(0) => StateAtom::new_verified("b"),
+                addr(1) => StateAtom::new_verified("a"),
+                addr(2) => StateAtom::new_verified("b"),
+                addr(3) => StateAtom::new_verified(StrictVal::tuple([svstr!("b")])),
+                addr(4) => StateAtom::new_verified("a"),
+                addr(5) => StateAtom::new_verified("c"),
+            },
+        };
+        let agg = Aggregator::Take(SubAggregator::CountBy(vname!("tally")));
+        assert_eq!(
+            agg.aggregate(&state, &none!(), &[], &types()),
+            Some(StrictVal::Map(vec![
+                (svstr!("b"), svnum!(3u64)),
+                (svstr!("a"), svnum!(2u64)),
+                (svstr!("c"), svnum!(1u64)),
+            ]))
+        );
+    }
+
+    #[test]
+    fn nth_out_of_range() {
+        let agg = Aggregator::Take(SubAggregator::Nth(vname!("verified"), 9999));
+        assert_eq!(agg.aggregate(&state(), &none!(), &[success_lib()], &types()), None);
+        let agg = Aggregator::Take(SubAggregator::NthBack(vname!("verified"), 9999));
+        assert_eq!(agg.aggregate(&state(), &none!(), &[success_lib()], &types()), None);
+        let agg = Aggregator::Take(SubAggregator::Nth(vname!("verified"), 6));
+        assert_eq!(agg.aggregate(&state(), &none!(), &[success_lib()], &types()), None);
+    }
+
     #[test]
     fn unverified_readers() {
         independent(Aggregator::Take(SubAggregator::Count(vname!("verified"))), svnum!(6u64));
@@ -1053,7 +1808,230 @@ mod test {
     }
 
     #[test]
-    #[should_panic]
-    // For now, the fail here indicates forward compatibility with when we allow AluVM
-    fn aluvm() { call(&Aggregator::AluVM(LibSite::new(success_lib().lib_id(), 0))); }
+    fn wrapped_keys() {
+        let atom = |verified: StrictVal, unverified: &str| StateAtom { verified, unverified: Some(svstr!(unverified)) };
+        let state = bmap! {
+            vname!("wrapped") => bmap! {
+                addr(0) => atom(StrictVal::newtype(5u64), "state 1"),
+                addr(1) => atom(svnum!(5u64), "state 2"),
+                addr(2) => atom(StrictVal::newtype(StrictVal::newtype(1u64)), "state 3"),
+                addr(3) => atom(svnum!(1u64), "state 3"),
+            },
+        };
+        let call = |aggregator: SubAggregator| {
+            Aggregator::Take(aggregator)
+                .aggregate(&state, &none!(), &[], &types())
+                .unwrap()
+        };
+
+        assert_eq!(call(SubAggregator::SetV(vname!("wrapped"))), svset!([5u64, 1u64]));
+        assert_eq!(
+            call(SubAggregator::MapV2U(vname!("wrapped"))),
+            StrictVal::Map(vec![(svnum!(5u64), svstr!("state 1")), (svnum!(1u64), svstr!("state 3"))])
+        );
+        assert_eq!(
+            call(SubAggregator::MapV2ListU(vname!("wrapped"))),
+            StrictVal::Map(vec![
+                (svnum!(5u64), svlist![[svstr!("state 1"), svstr!("state 2")]]),
+                (svnum!(1u64), svlist![[svstr!("state 3"), svstr!("state 3")]]),
+            ])
+        );
+        assert_eq!(
+            call(SubAggregator::MapV2SetU(vname!("wrapped"))),
+            StrictVal::Map(vec![
+                (svnum!(5u64), svset!([svstr!("state 1"), svstr!("state 2")])),
+                (svnum!(1u64), svset!([svstr!("state 3")])),
+            ])
+        );
+    }
+
+    #[test]
+    fn aluvm() {
+        let lib = Lib::assemble(&uasm! {
+            ldi     immutable;
+            mov     EE, EB;
+            ldi     immutable;
+            add     EE, EB;
+            ldo     immutable;
+            add     EE, EB;
+            mov     EA, EE;
+            clr     EB;
+            stop;
+        })
+        .unwrap();
+        let global = bmap! {
+            vname!("supply") => bmap! {
+                addr(0) => StateAtom::new_verified(5u64),
+                addr(1) => StateAtom::new_unverified("ignored"),
+                addr(2) => StateAtom::new_verified(7u64),
+            },
+        };
+        let aggregated = bmap! {
+            vname!("bonus") => svnum!(3u64),
+            vname!("str") => svstr!("ignored"),
+        };
+        let agg = Aggregator::AluVM(LibSite::new(lib.lib_id(), 0), supply());
+        assert_eq!(agg.depends_on().count(), 0);
+        assert_eq!(agg.aggregate(&global, &aggregated, [&lib], &types()), Some(svnum!(15u64)));
+        // There is no aggregated state to load
+        assert_eq!(agg.aggregate(&global, &none!(), [&lib], &types()), None);
+        // The library is not known
+        assert_eq!(agg.aggregate(&global, &aggregated, [], &types()), None);
+    }
+
+    #[test]
+    fn aluvm_order() {
+        let lib = Lib::assemble(&uasm! {
+            ldi     immutable;
+            mov     EA, EB;
+            clr     EB;
+            stop;
+        })
+        .unwrap();
+        let global = bmap! {
+            vname!("first") => bmap! { addr(0) => StateAtom::new_verified(1u64) },
+            vname!("second") => bmap! { addr(1) => StateAtom::new_verified(2u64) },
+        };
+        let expected = if Aggregator::aluvm_state_id(&vname!("first")).to_u256()
+            < Aggregator::aluvm_state_id(&vname!("second")).to_u256()
+        {
+            1u64
+        } else {
+            2u64
+        };
+        let agg = Aggregator::AluVM(LibSite::new(lib.lib_id(), 0), supply());
+        assert_eq!(agg.aggregate(&global, &none!(), [&lib], &types()), Some(svnum!(expected)));
+    }
+
+    #[test]
+    fn aluvm_fail() {
+        let no_result = Lib::assemble(&uasm! { stop; }).unwrap();
+        let agg = Aggregator::AluVM(LibSite::new(no_result.lib_id(), 0), supply());
+        assert_eq!(agg.aggregate(&state(), &none!(), [&no_result], &types()), None);
+
+        let failure = Lib::assemble(&uasm! {
+            put     EA, 1;
+            fail    CK;
+            stop;
+        })
+        .unwrap();
+        let agg = Aggregator::AluVM(LibSite::new(failure.lib_id(), 0), supply());
+        assert_eq!(agg.aggregate(&state(), &none!(), [&failure], &types()), None);
+    }
+
+    #[test]
+    fn any_all_newtype() {
+        let encode = |val: u64| TinyBlob::from_checked(val.to_le_bytes().to_vec());
+        let call = |sub: SubAggregator, global: &BTreeMap<StateName, BTreeMap<CellAddr, StateAtom>>| {
+            Aggregator::Take(sub)
+                .aggregate(global, &none!(), &[success_lib()], &types())
+                .unwrap()
+        };
+        let any = |val: u64| SubAggregator::Any(vname!("verified"), supply(), encode(val));
+        let all = |val: u64| SubAggregator::All(vname!("verified"), supply(), encode(val));
+
+        assert_eq!(call(any(5), &state()), StrictVal::bool(true));
+        assert_eq!(call(any(7), &state()), StrictVal::bool(false));
+        assert_eq!(call(all(5), &state()), StrictVal::bool(false));
+
+        let same = bmap! {
+            vname!("verified") => bmap! {
+                addr(0) => StateAtom::new_verified(5u64),
+                addr(1) => StateAtom::new_verified(5u64),
+            },
+        };
+        assert_eq!(call(all(5), &same), StrictVal::bool(true));
+        assert_eq!(call(all(1), &same), StrictVal::bool(false));
+    }
+
+    #[test]
+    fn aggregate_errors() {
+        let err = |agg: SubAggregator| {
+            Aggregator::Take(agg)
+                .try_aggregate(&state(), &aggregated(), &[success_lib()], &types())
+                .unwrap_err()
+        };
+        let global = |name: &'static str| StateSelector::Global(vname!(name), false);
+        let aggr = |name: &'static str| StateSelector::Aggregated(vname!(name));
+
+        assert_eq!(
+            err(SubAggregator::First(vname!("nonExisting"))),
+            AggregateError::GlobalAbsent(vname!("nonExisting"))
+        );
+        assert_eq!(
+            err(SubAggregator::Copy(vname!("nonExisting"))),
+            AggregateError::AggregatedAbsent(vname!("nonExisting"))
+        );
+        assert_eq!(
+            err(SubAggregator::TheOnly(vname!("verified"))),
+            AggregateError::MultipleElements(vname!("verified"))
+        );
+        assert_eq!(err(SubAggregator::Nth(vname!("verified"), 6)), AggregateError::OutOfRange(vname!("verified"), 6));
+        assert_eq!(err(SubAggregator::Neg(global("verified"))), AggregateError::MultipleElements(vname!("verified")));
+        assert_eq!(err(SubAggregator::Neg(aggr("str"))), AggregateError::NotInteger(vname!("str")));
+        assert_eq!(
+            err(SubAggregator::SumUnwrap(vname!("unverified"))),
+            AggregateError::NotInteger(vname!("unverified"))
+        );
+        assert_eq!(err(SubAggregator::Add(aggr("maxUint"), aggr("two"))), AggregateError::Overflow);
+        assert_eq!(err(SubAggregator::Div(aggr("two"), aggr("zero"))), AggregateError::Overflow);
+
+        let unknown = SemId::from([0xCA; 32]);
+        assert_eq!(
+            err(SubAggregator::Any(vname!("verified"), unknown, none!())),
+            AggregateError::InvalidReference(unknown)
+        );
+        assert_eq!(
+            err(SubAggregator::All(vname!("verified"), unknown, none!())),
+            AggregateError::InvalidReference(unknown)
+        );
+        assert_eq!(err(SubAggregator::Const(unknown, none!())), AggregateError::InvalidReference(unknown));
+        let bool = std_stl()
+            .types
+            .iter()
+            .find(|(tn, _)| tn.as_str() == "Bool")
+            .map(|(tn, ty)| ty.sem_id_named(tn))
+            .unwrap();
+        assert_eq!(
+            err(SubAggregator::Any(vname!("verified"), bool, tiny_blob!(5))),
+            AggregateError::InvalidReference(bool)
+        );
+
+        let empty = bmap! { vname!("empty") => bmap! {} };
+        let agg = Aggregator::Take(SubAggregator::Avg(vname!("empty")));
+        assert_eq!(
+            agg.try_aggregate(&empty, &none!(), &[], &types()),
+            Err(AggregateError::NoElements(vname!("empty")))
+        );
+
+        // `Some` and `Or` recover from failures
+        let agg = Aggregator::Some(SubAggregator::Copy(vname!("nonExisting")));
+        assert_eq!(agg.try_aggregate(&state(), &none!(), &[], &types()), Ok(svnone!()));
+        let agg = Aggregator::Or(SubAggregator::Copy(vname!("nonExisting")), SubAggregator::Copy(vname!("other")));
+        assert_eq!(
+            agg.try_aggregate(&state(), &none!(), &[], &types()),
+            Err(AggregateError::AggregatedAbsent(vname!("other")))
+        );
+
+        let no_result = Lib::assemble(&uasm! { stop; }).unwrap();
+        let agg = Aggregator::AluVM(LibSite::new(no_result.lib_id(), 0), supply());
+        assert_eq!(agg.try_aggregate(&state(), &none!(), [&no_result], &types()), Err(AggregateError::VmNoResult));
+        let failure = Lib::assemble(&uasm! { fail CK; stop; }).unwrap();
+        let agg = Aggregator::AluVM(LibSite::new(failure.lib_id(), 0), supply());
+        assert_eq!(agg.try_aggregate(&state(), &none!(), [&failure], &types()), Err(AggregateError::VmFailure));
+        // Result doesn't fit the type
+        let too_long = Lib::assemble(&uasm! { put EA, 1; put EB, 1; stop; }).unwrap();
+        let agg = Aggregator::AluVM(LibSite::new(too_long.lib_id(), 0), supply());
+        assert_eq!(
+            agg.try_aggregate(&state(), &none!(), [&too_long], &types()),
+            Err(AggregateError::VmInvalidResult(supply()))
+        );
+        let unknown = SemId::from([0xCA; 32]);
+        let lib = Lib::assemble(&uasm! { put EA, 1; stop; }).unwrap();
+        let agg = Aggregator::AluVM(LibSite::new(lib.lib_id(), 0), unknown);
+        assert_eq!(
+            agg.try_aggregate(&state(), &none!(), [&lib], &types()),
+            Err(AggregateError::VmInvalidResult(unknown))
+        );
+    }
 }
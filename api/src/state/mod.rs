@@ -27,8 +27,8 @@ mod arithmetics;
 mod data;
 mod raw;
 
-pub use adaptors::{StateBuildError, StateBuilder, StateConvertError, StateConvertor};
-pub use aggregators::{Aggregator, StateSelector, SubAggregator};
+pub use adaptors::{debug_hex, encode_state, StateBuildError, StateBuilder, StateConvertError, StateConvertor};
+pub use aggregators::{AggregateError, Aggregator, StateSelector, SubAggregator};
 pub use arithmetics::{StateArithm, StateCalc, StateCalcError};
 pub use data::{DataCell, StateAtom, StateTy};
-pub use raw::{RawBuilder, RawConvertor, TOTAL_RAW_BYTES};
+pub use raw::{RawBuilder, RawConvertor, MAX_RAW_BYTES, TOTAL_RAW_BYTES};
@@ -23,13 +23,15 @@
 
 use aluvm::LibSite;
 use amplify::confinement::{SmallBlob, U24 as U24MAX};
-use strict_encoding::StreamReader;
+use strict_encoding::{SerializeError, StreamReader};
 use strict_types::{SemId, StrictVal, TypeSystem};
 use ultrasonic::RawData;
 
 use crate::{StateBuildError, StateConvertError, LIB_NAME_SONIC};
 
 pub const TOTAL_RAW_BYTES: usize = U24MAX;
+/// Maximal size of a single raw state value.
+pub const MAX_RAW_BYTES: usize = u16::MAX as usize;
 
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 #[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
@@ -113,10 +115,12 @@ fn strict_convert(sem_id: SemId, raw: &RawData, sys: &TypeSystem) -> Result<Stri
 
 #[allow(clippy::result_large_err)]
 fn strict_build(sem_id: SemId, val: StrictVal, sys: &TypeSystem) -> Result<RawData, StateBuildError> {
-    let mut data = SmallBlob::new();
+    let mut data = Vec::new();
 
     let typed_val = sys.typify(val, sem_id)?;
-    sys.strict_write_value(&typed_val, &mut data)?;
+    sys.strict_write_value(&typed_val, &mut data).map_err(SerializeError::from)?;
 
+    let len = data.len();
+    let data = SmallBlob::try_from(data).map_err(|_| StateBuildError::RawTooLarge(len))?;
     Ok(RawData::from(data))
 }
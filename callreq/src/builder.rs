@@ -21,12 +21,19 @@
 // or implied. See the License for the specific language governing permissions and limitations under
 // the License.
 
-use amplify::confinement;
+#[cfg(feature = "uri")]
+use core::str::FromStr;
+
+use amplify::confinement::{self, ConfinedVec, TinyBlob};
 use chrono::{DateTime, Utc};
+#[cfg(feature = "uri")]
+use strict_types::value::StrictNum;
 use strict_types::{StrictVal, TypeName};
-use ultrasonic::Consensus;
+use ultrasonic::{AuthToken, Consensus};
 
-use crate::{CallRequest, CallState, Endpoint, Layer1, MethodName, StateName};
+#[cfg(feature = "uri")]
+use crate::uri::is_valid_endpoint;
+use crate::{CallRequest, CallScope, CallState, Endpoint, Layer1, MethodName, StateName};
 
 impl<T, A> CallRequest<T, A> {
     pub fn bitcoin_mainnet(scope: T, auth: A, data: Option<StrictVal>) -> Self {
@@ -52,7 +59,7 @@ impl<T, A> CallRequest<T, A> {
             api: None,
             call: None,
             auth,
-            data,
+            data: data.into_iter().collect(),
             lock: None,
             expiry: None,
             endpoints: Default::default(),
@@ -93,3 +100,327 @@ impl<T, A> CallRequest<T, A> {
         Ok(self)
     }
 }
+
+/// Fluent builder for [`CallRequest`], allowing to construct requests without setting all the
+/// fields by hand or formatting URIs.
+///
+/// The builder checks the same invariants as the URI parser when [`Self::build`] is called, such
+/// that a successfully built request round-trips through its URI form. Requests with
+/// [`Endpoint::Custom`] endpoints round-trip only if parsed with
+/// [`CallRequest::parse_with_schemes`] using a registry which knows their schemes; otherwise these
+/// endpoints are parsed back as [`Endpoint::UnspecifiedMeans`].
+#[derive(Clone, Debug)]
+pub struct CallRequestBuilder<T = CallScope, A = AuthToken> {
+    scope: Option<T>,
+    layer1: Option<Layer1>,
+    api: Option<TypeName>,
+    method: Option<MethodName>,
+    owned: Option<StateName>,
+    data: Vec<StrictVal>,
+    auth: Option<A>,
+    lock: Option<Vec<u8>>,
+    expiry: Option<DateTime<Utc>>,
+    endpoints: Vec<Endpoint>,
+}
+
+impl<T, A> Default for CallRequestBuilder<T, A> {
+    fn default() -> Self {
+        Self {
+            scope: None,
+            layer1: None,
+            api: None,
+            method: None,
+            owned: None,
+            data: vec![],
+            auth: None,
+            lock: None,
+            expiry: None,
+            endpoints: vec![],
+        }
+    }
+}
+
+impl<T, A> CallRequestBuilder<T, A> {
+    pub fn new() -> Self { Self::default() }
+
+    pub fn scope(mut self, scope: T) -> Self {
+        self.scope = Some(scope);
+        self
+    }
+
+    pub fn layer1(mut self, layer1: Layer1) -> Self {
+        self.layer1 = Some(layer1);
+        self
+    }
+
+    pub fn api(mut self, api: impl Into<TypeName>) -> Self {
+        self.api = Some(api.into());
+        self
+    }
+
+    pub fn method(mut self, method: impl Into<MethodName>) -> Self {
+        self.method = Some(method.into());
+        self
+    }
+
+    /// Sets the name of the owned state for the call; requires a [`Self::method`] to be set as
+    /// well before the request is built.
+    pub fn owned_state(mut self, state: impl Into<StateName>) -> Self {
+        self.owned = Some(state.into());
+        self
+    }
+
+    /// Adds a data value to the call. May be called multiple times, for instance, to request
+    /// several amounts for the same beneficiary in a batch transfer.
+    pub fn data(mut self, data: impl Into<StrictVal>) -> Self {
+        self.data.push(data.into());
+        self
+    }
+
+    pub fn auth(mut self, auth: A) -> Self {
+        self.auth = Some(auth);
+        self
+    }
+
+    pub fn lock(mut self, lock: impl Into<Vec<u8>>) -> Self {
+        self.lock = Some(lock.into());
+        self
+    }
+
+    pub fn expiry(mut self, expiry: DateTime<Utc>) -> Self {
+        self.expiry = Some(expiry);
+        self
+    }
+
+    pub fn add_endpoint(mut self, endpoint: Endpoint) -> Self {
+        self.endpoints.push(endpoint);
+        self
+    }
+
+    /// Builds the call request, checking that it satisfies the same conditions which are required
+    /// from a parsed call request URI.
+    ///
+    /// With the `uri` feature, also checks that endpoints with recognized schemes have a valid host
+    /// and port, and that the data values are either unsigned integers or strings which can't be
+    /// read as an unsigned integer, since other values are not preserved by the URI form.
+    #[allow(clippy::result_large_err)]
+    pub fn build(self) -> Result<CallRequest<T, A>, CallRequestBuildError> {
+        let scope = self.scope.ok_or(CallRequestBuildError::ScopeMissed)?;
+        let layer1 = self.layer1.ok_or(CallRequestBuildError::NoLayer1)?;
+        let auth = self.auth.ok_or(CallRequestBuildError::NoAuth)?;
+        let call = match (self.method, self.owned) {
+            (Some(method), owned) => Some(CallState { method, owned }),
+            (None, Some(state)) => return Err(CallRequestBuildError::StateWithoutMethod(state)),
+            (None, None) => None,
+        };
+        let lock = self
+            .lock
+            .map(|lock| TinyBlob::try_from(lock).map_err(|_| CallRequestBuildError::LockTooLong))
+            .transpose()?;
+        #[cfg(feature = "uri")]
+        if let Some(data) = self.data.iter().find(|data| !is_uri_data(data)) {
+            return Err(CallRequestBuildError::DataUnsupported(data.clone()));
+        }
+        #[cfg(feature = "uri")]
+        if let Some(endpoint) = self
+            .endpoints
+            .iter()
+            .find(|endpoint| !is_uri_endpoint(endpoint))
+        {
+            return Err(CallRequestBuildError::EndpointInvalid(endpoint.to_string()));
+        }
+        let count = self.endpoints.len();
+        let endpoints =
+            ConfinedVec::try_from(self.endpoints).map_err(|_| CallRequestBuildError::TooManyEndpoints(count))?;
+        Ok(CallRequest {
+            scope,
+            layer1,
+            api: self.api,
+            call,
+            auth,
+            data: self.data,
+            lock,
+            expiry: self.expiry,
+            endpoints,
+            unknown_query: none!(),
+        })
+    }
+}
+
+/// Checks that the data value is parsed back from the URI form as the same value: the parser
+/// reads values as unsigned integers if they can be read as such, and as strings otherwise.
+#[cfg(feature = "uri")]
+fn is_uri_data(data: &StrictVal) -> bool {
+    match data {
+        StrictVal::Number(StrictNum::Uint(_)) => true,
+        StrictVal::String(s) => u64::from_str(s).is_err(),
+        _ => false,
+    }
+}
+
+/// Checks that the endpoint passes the same validation as the URI parser applies to it.
+#[cfg(feature = "uri")]
+fn is_uri_endpoint(endpoint: &Endpoint) -> bool {
+    match endpoint {
+        Endpoint::UnspecifiedMeans(_) | Endpoint::Custom { .. } => true,
+        _ => is_valid_endpoint(&endpoint.to_string()),
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum CallRequestBuildError {
+    /// contract call request scope is not provided.
+    ScopeMissed,
+
+    /// absent information about layer 1.
+    NoLayer1,
+
+    /// contract call request misses the beneficiary authority token.
+    NoAuth,
+
+    /// owned state {0} is provided for a call request without a method name.
+    StateWithoutMethod(StateName),
+
+    /// lock data conditions are too long (they must not exceed 255 bytes).
+    LockTooLong,
+
+    /// contract call request contains {0} endpoints, while at most 10 are allowed.
+    TooManyEndpoints(usize),
+
+    /// endpoint '{0}' must have a valid host and port.
+    EndpointInvalid(String),
+
+    /// call data value {0:?} can't be represented in a call request URI; only unsigned integers
+    /// and strings which are not numbers are supported.
+    DataUnsupported(StrictVal),
+}
+
+#[cfg(test)]
+mod test {
+    #![cfg_attr(coverage_nightly, coverage(off))]
+
+    use core::str::FromStr;
+
+    use ultrasonic::ContractId;
+
+    use super::*;
+
+    fn builder() -> CallRequestBuilder<ContractId, AuthToken> {
+        CallRequestBuilder::new()
+            .scope(ContractId::from_str("contract:qKpMlzOe-Imn6ysZ-a8JjG2p-WHWvaFm-BWMiPi3-_LvnfRw").unwrap())
+            .layer1(Layer1::new(Consensus::Bitcoin, true))
+            .auth(AuthToken::from_str("at:5WIb5EMY-RCLbO3Wq-hGdddRP4-IeCQzP1y-S5H_UKzd-ViYmlA").unwrap())
+    }
+
+    #[test]
+    #[cfg(feature = "uri")]
+    fn build_short() {
+        let req = builder().data(StrictVal::num(10u64)).build().unwrap();
+        assert_eq!(
+            req.to_string(),
+            "contract:tb@qKpMlzOe-Imn6ysZ-a8JjG2p-WHWvaFm-BWMiPi3-_LvnfRw/10@at:\
+             5WIb5EMY-RCLbO3Wq-hGdddRP4-IeCQzP1y-S5H_UKzd-ViYmlA/"
+        );
+        assert_eq!(CallRequest::from_str(&req.to_string()).unwrap(), req);
+    }
+
+    #[test]
+    #[cfg(feature = "uri")]
+    fn build_lock() {
+        let req = builder()
+            .api("RGB20")
+            .method("transfer")
+            .owned_state("amount")
+            .data(StrictVal::num(10u64))
+            .lock(vec![3, 174, 2, 14, 183, 230, 27, 143, 55])
+            .build()
+            .unwrap();
+        assert_eq!(
+            req.to_string(),
+            "contract:tb@qKpMlzOe-Imn6ysZ-a8JjG2p-WHWvaFm-BWMiPi3-_LvnfRw/RGB20/transfer/amount/10@at:\
+             5WIb5EMY-RCLbO3Wq-hGdddRP4-IeCQzP1y-S5H_UKzd-ViYmlA/?lock=A64CDrfmG483"
+        );
+        assert_eq!(CallRequest::from_str(&req.to_string()).unwrap(), req);
+    }
+
+    #[test]
+    #[cfg(feature = "uri")]
+    fn build_endpoints() {
+        let req = builder()
+            .api("RGB20")
+            .method("transfer")
+            .add_endpoint(Endpoint::RestHttp(s!("http://127.0.0.1:8080")))
+            .add_endpoint(Endpoint::Storm(s!("storm://127.0.0.1:8082")))
+            .build()
+            .unwrap();
+        assert_eq!(
+            req.to_string(),
+            "contract:tb@qKpMlzOe-Imn6ysZ-a8JjG2p-WHWvaFm-BWMiPi3-_LvnfRw/RGB20/transfer/at:\
+             5WIb5EMY-RCLbO3Wq-hGdddRP4-IeCQzP1y-S5H_UKzd-ViYmlA/?\
+             endpoints=http://127.0.0.1:8080,storm://127.0.0.1:8082"
+        );
+        assert_eq!(CallRequest::from_str(&req.to_string()).unwrap(), req);
+    }
+
+    #[test]
+    #[cfg(feature = "uri")]
+    fn build_roundtrip() {
+        use crate::{CustomScheme, EndpointSchemes};
+
+        let req = builder()
+            .data(StrictVal::num(10u64))
+            .data(StrictVal::str("ten"))
+            .add_endpoint(Endpoint::Storm(s!("storm://127.0.0.1:8082")))
+            .add_endpoint(Endpoint::Custom { scheme: s!("tor"), url: s!("tor://example.onion:8080") })
+            .build()
+            .unwrap();
+        let mut schemes = EndpointSchemes::default();
+        schemes.register(CustomScheme::new("tor"));
+        assert_eq!(CallRequest::parse_with_schemes(&req.to_string(), &schemes).unwrap(), req);
+
+        // Custom endpoints are not known to the default registry
+        let parsed = CallRequest::<ContractId, AuthToken>::from_str(&req.to_string()).unwrap();
+        assert_eq!(parsed.endpoints[1], Endpoint::UnspecifiedMeans(s!("tor://example.onion:8080")));
+    }
+
+    #[test]
+    #[cfg(feature = "uri")]
+    fn build_not_uri() {
+        assert_eq!(
+            builder().data(StrictVal::str("10")).build(),
+            Err(CallRequestBuildError::DataUnsupported(StrictVal::str("10")))
+        );
+        assert_eq!(
+            builder().data(StrictVal::bool(true)).build(),
+            Err(CallRequestBuildError::DataUnsupported(StrictVal::bool(true)))
+        );
+        assert_eq!(
+            builder()
+                .add_endpoint(Endpoint::Storm(s!("storm://:8082")))
+                .build(),
+            Err(CallRequestBuildError::EndpointInvalid(s!("storm://:8082")))
+        );
+        assert_eq!(
+            builder()
+                .add_endpoint(Endpoint::RestHttp(s!("http://127.0.0.1:port")))
+                .build(),
+            Err(CallRequestBuildError::EndpointInvalid(s!("http://127.0.0.1:port")))
+        );
+    }
+
+    #[test]
+    fn build_invalid() {
+        assert_eq!(CallRequestBuilder::<ContractId, AuthToken>::new().build(), Err(CallRequestBuildError::ScopeMissed));
+        assert_eq!(
+            builder().owned_state("amount").build(),
+            Err(CallRequestBuildError::StateWithoutMethod(vname!("amount")))
+        );
+        assert!(builder().lock(vec![0u8; 255]).build().is_ok());
+        assert_eq!(builder().lock(vec![0u8; 256]).build(), Err(CallRequestBuildError::LockTooLong));
+        let builder = (0..11).fold(builder(), |builder, no| {
+            builder.add_endpoint(Endpoint::Storm(format!("storm://127.0.0.1:{}", 8000 + no)))
+        });
+        assert_eq!(builder.build(), Err(CallRequestBuildError::TooManyEndpoints(11)));
+    }
+}
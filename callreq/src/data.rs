@@ -86,6 +86,10 @@ impl CallState {
 ///   default method used from the contract default API;
 /// - 5-component path - all parameters except API name are given.
 ///
+/// The last path component may prefix the auth token with the data values, separated from the
+/// token with `@`. Multiple data values are separated with commas (`10,20,30@AUTH`); commas in the
+/// string values are percent-encoded. A component without `@` means no data values.
+///
 /// ## Query
 ///
 /// Supported URI query parameters are:
@@ -113,7 +117,8 @@ pub struct CallRequest<T = CallScope, A = AuthToken> {
     pub api: Option<TypeName>,
     pub call: Option<CallState>,
     pub auth: A,
-    pub data: Option<StrictVal>,
+    /// Ordered list of the data values for the call, which may be empty if no data are requested.
+    pub data: Vec<StrictVal>,
     pub lock: Option<TinyBlob>,
     pub expiry: Option<DateTime<Utc>>,
     pub endpoints: ConfinedVec<Endpoint, 0, 10>,
@@ -121,6 +126,9 @@ pub struct CallRequest<T = CallScope, A = AuthToken> {
 }
 
 impl<Q: Display + FromStr, A> CallRequest<CallScope<Q>, A> {
+    /// Returns the contract id of the request scope, if the scope doesn't require resolution.
+    pub fn contract_id(&self) -> Option<ContractId> { self.scope.as_contract_id() }
+
     pub fn unwrap_contract_with<E>(
         self,
         f: impl FnOnce(Q) -> Result<ContractId, E>,
@@ -144,6 +152,14 @@ impl<Q: Display + FromStr, A> CallRequest<CallScope<Q>, A> {
     }
 }
 
+impl<T, A> CallRequest<T, A> {
+    /// Checks the beneficiary auth token of the request against an external predicate.
+    ///
+    /// Allows wallets to detect, using their own key material, whether the request beneficiary
+    /// belongs to them - for instance, to avoid paying to itself.
+    pub fn auth_matches(&self, predicate: impl Fn(&A) -> bool) -> bool { predicate(&self.auth) }
+}
+
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Layer1 {
@@ -212,6 +228,17 @@ pub enum CallScope<Q: Display + FromStr = String> {
     ContractQuery(Q),
 }
 
+impl<Q: Display + FromStr> CallScope<Q> {
+    /// Returns the contract id if the scope already refers to a concrete contract, or `None` if it
+    /// is a query which has to be resolved first.
+    pub fn as_contract_id(&self) -> Option<ContractId> {
+        match self {
+            CallScope::ContractId(id) => Some(*id),
+            CallScope::ContractQuery(_) => None,
+        }
+    }
+}
+
 impl<Q: Display + FromStr> FromStr for CallScope<Q> {
     type Err = Baid64ParseError;
 
@@ -242,35 +269,141 @@ impl<Q: Display + FromStr + Clone> From<CallScope<Q>> for String {
 #[display(inner)]
 #[non_exhaustive]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-#[cfg_attr(feature = "serde", serde(try_from = "String", into = "String"))]
+#[cfg_attr(feature = "serde", serde(from = "EndpointRepr", into = "EndpointRepr"))]
 pub enum Endpoint {
     JsonRpc(String),
     RestHttp(String),
     WebSockets(String),
     Storm(String),
+    /// gRPC endpoint, using `grpc://` or `grpc+tls://` scheme.
+    Grpc(String),
+    /// Endpoint using a scheme which is not known to this library, but recognized by a custom
+    /// [`EndpointScheme`] handler registered in [`EndpointSchemes`].
+    ///
+    /// Unlike other endpoints, which are serialized as URL strings, it is serialized as a
+    /// structure with `scheme` and `url` fields, such that it can be deserialized without the
+    /// registry.
+    #[display("{url}")]
+    Custom {
+        scheme: String,
+        url: String,
+    },
     UnspecifiedMeans(String),
 }
 
 impl FromStr for Endpoint {
     type Err = Infallible;
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
+    fn from_str(s: &str) -> Result<Self, Self::Err> { Ok(EndpointSchemes::default().parse(s)) }
+}
+
+/// Handler for a URL scheme used by [`Endpoint`]s.
+pub trait EndpointScheme {
+    /// Detects whether a lowercase URL uses the scheme supported by the handler.
+    fn matches(&self, url: &str) -> bool;
+
+    /// Constructs an endpoint from a lowercase URL matching the scheme.
+    fn endpoint(&self, url: String) -> Endpoint;
+}
+
+/// Endpoint schemes known to this library.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum StdScheme {
+    RestHttp,
+    JsonRpc,
+    WebSockets,
+    Storm,
+    Grpc,
+}
+
+impl StdScheme {
+    pub const ALL: [Self; 5] = [Self::RestHttp, Self::JsonRpc, Self::WebSockets, Self::Storm, Self::Grpc];
+
+    /// URL prefixes used by the scheme.
+    pub fn prefixes(self) -> &'static [&'static str] {
+        match self {
+            Self::RestHttp => &["http://", "https://"],
+            Self::JsonRpc => &["http+json-rpc://", "https+json-rpc://"],
+            Self::WebSockets => &["ws://", "wss://"],
+            Self::Storm => &["storm://"],
+            Self::Grpc => &["grpc://", "grpc+tls://"],
+        }
+    }
+}
+
+impl EndpointScheme for StdScheme {
+    fn matches(&self, url: &str) -> bool { self.prefixes().iter().any(|prefix| url.starts_with(prefix)) }
+
+    fn endpoint(&self, url: String) -> Endpoint {
+        match self {
+            Self::RestHttp => Endpoint::RestHttp(url),
+            Self::JsonRpc => Endpoint::JsonRpc(url),
+            Self::WebSockets => Endpoint::WebSockets(url),
+            Self::Storm => Endpoint::Storm(url),
+            Self::Grpc => Endpoint::Grpc(url),
+        }
+    }
+}
+
+/// Handler for a custom URL scheme, producing [`Endpoint::Custom`].
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct CustomScheme(String);
+
+impl CustomScheme {
+    /// Constructs the handler for a scheme name, like `tor` for `tor://` URLs.
+    pub fn new(scheme: impl Into<String>) -> Self { Self(scheme.into().to_lowercase()) }
+
+    /// Returns the scheme name.
+    pub fn scheme(&self) -> &str { &self.0 }
+}
+
+impl EndpointScheme for CustomScheme {
+    fn matches(&self, url: &str) -> bool {
+        url.strip_prefix(self.0.as_str())
+            .is_some_and(|rest| rest.starts_with("://"))
+    }
+
+    fn endpoint(&self, url: String) -> Endpoint { Endpoint::Custom { scheme: self.0.clone(), url } }
+}
+
+/// Registry of URL scheme handlers used for parsing [`Endpoint`]s.
+///
+/// The default registry contains handlers for all [`StdScheme`]s.
+pub struct EndpointSchemes(Vec<Box<dyn EndpointScheme>>);
+
+impl Default for EndpointSchemes {
+    fn default() -> Self {
+        Self(
+            StdScheme::ALL
+                .into_iter()
+                .map(|scheme| Box::new(scheme) as Box<dyn EndpointScheme>)
+                .collect(),
+        )
+    }
+}
+
+impl EndpointSchemes {
+    /// Constructs a registry without any scheme handlers, such that all endpoints are parsed as
+    /// [`Endpoint::UnspecifiedMeans`].
+    pub fn empty() -> Self { Self(vec![]) }
+
+    /// Registers an additional scheme handler.
+    ///
+    /// Handlers registered later take precedence over the previously registered ones.
+    pub fn register(&mut self, scheme: impl EndpointScheme + 'static) { self.0.push(Box::new(scheme)); }
+
+    /// Parses an endpoint URL using the first matching handler, falling back to
+    /// [`Endpoint::UnspecifiedMeans`] if no handler matches.
+    pub fn parse(&self, s: &str) -> Endpoint {
         let s = s.to_lowercase();
-        #[allow(clippy::if_same_then_else)] // Some wierd clippy bug
-        if s.starts_with("http://") || s.starts_with("https://") {
-            Ok(Endpoint::RestHttp(s))
-        } else if s.starts_with("http+json-rpc://") || s.starts_with("https+json-rpc://") {
-            Ok(Endpoint::JsonRpc(s))
-        } else if s.starts_with("ws://") || s.starts_with("wss://") {
-            Ok(Endpoint::WebSockets(s))
-        } else if s.starts_with("storm://") {
-            Ok(Endpoint::Storm(s))
-        } else {
-            Ok(Endpoint::UnspecifiedMeans(s.to_string()))
+        match self.0.iter().rev().find(|scheme| scheme.matches(&s)) {
+            Some(scheme) => scheme.endpoint(s),
+            None => Endpoint::UnspecifiedMeans(s),
         }
     }
 }
 
+#[allow(clippy::infallible_try_from)]
 impl TryFrom<String> for Endpoint {
     type Error = Infallible;
 
@@ -280,3 +413,83 @@ impl TryFrom<String> for Endpoint {
 impl From<Endpoint> for String {
     fn from(value: Endpoint) -> Self { value.to_string() }
 }
+
+/// Serialized form of an [`Endpoint`], preserving the scheme of [`Endpoint::Custom`] endpoints.
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum EndpointRepr {
+    Url(String),
+    Custom { scheme: String, url: String },
+}
+
+#[cfg(feature = "serde")]
+impl From<EndpointRepr> for Endpoint {
+    fn from(value: EndpointRepr) -> Self {
+        match value {
+            EndpointRepr::Url(url) => EndpointSchemes::default().parse(&url),
+            EndpointRepr::Custom { scheme, url } => Endpoint::Custom { scheme, url },
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<Endpoint> for EndpointRepr {
+    fn from(value: Endpoint) -> Self {
+        match value {
+            Endpoint::Custom { scheme, url } => EndpointRepr::Custom { scheme, url },
+            endpoint => EndpointRepr::Url(endpoint.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #![cfg_attr(coverage_nightly, coverage(off))]
+
+    use super::*;
+
+    #[test]
+    fn auth_matches() {
+        let own = AuthToken::from([0xAA; 30]);
+        let other = AuthToken::from([0xBB; 30]);
+        let wallet = [own];
+
+        let req = CallRequest::<CallScope, _>::bitcoin_testnet(CallScope::ContractQuery(s!("test")), own, None);
+        assert!(req.auth_matches(|auth| wallet.contains(auth)));
+
+        let req = CallRequest::<CallScope, _>::bitcoin_testnet(CallScope::ContractQuery(s!("test")), other, None);
+        assert!(!req.auth_matches(|auth| wallet.contains(auth)));
+    }
+
+    #[test]
+    fn contract_id() {
+        let auth = AuthToken::from([0xAA; 30]);
+        let id = ContractId::from_str("contract:qKpMlzOe-Imn6ysZ-a8JjG2p-WHWvaFm-BWMiPi3-_LvnfRw").unwrap();
+
+        let req = CallRequest::<CallScope, _>::bitcoin_testnet(CallScope::ContractId(id), auth, None);
+        assert_eq!(req.scope.as_contract_id(), Some(id));
+        assert_eq!(req.contract_id(), Some(id));
+
+        let req = CallRequest::<CallScope, _>::bitcoin_testnet(CallScope::ContractQuery(s!("test")), auth, None);
+        assert_eq!(req.scope.as_contract_id(), None);
+        assert_eq!(req.contract_id(), None);
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn endpoint_serde() {
+        let storm = Endpoint::Storm(s!("storm://127.0.0.1:8082"));
+        let json = serde_json::to_string(&storm).unwrap();
+        assert_eq!(json, r#""storm://127.0.0.1:8082""#);
+        assert_eq!(serde_json::from_str::<Endpoint>(&json).unwrap(), storm);
+
+        let custom = Endpoint::Custom { scheme: s!("tor"), url: s!("tor://example.onion:8080") };
+        let json = serde_json::to_string(&custom).unwrap();
+        assert_eq!(json, r#"{"scheme":"tor","url":"tor://example.onion:8080"}"#);
+        assert_eq!(serde_json::from_str::<Endpoint>(&json).unwrap(), custom);
+
+        let unspecified = serde_json::from_str::<Endpoint>(r#""tor://example.onion:8080""#).unwrap();
+        assert_eq!(unspecified, Endpoint::UnspecifiedMeans(s!("tor://example.onion:8080")));
+    }
+}
@@ -76,6 +76,10 @@ mod data;
 pub mod uri;
 mod builder;
 
-pub use data::{CallRequest, CallScope, CallState, Endpoint, Layer1, MethodName, ParseLayer1Error, StateName};
+pub use builder::{CallRequestBuildError, CallRequestBuilder};
+pub use data::{
+    CallRequest, CallScope, CallState, CustomScheme, Endpoint, EndpointScheme, EndpointSchemes, Layer1, MethodName,
+    ParseLayer1Error, StateName, StdScheme,
+};
 
 pub const LIB_NAME_SONIC: &str = "SONIC";
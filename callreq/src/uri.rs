@@ -38,7 +38,7 @@ use indexmap::IndexMap;
 use percent_encoding::{percent_decode, utf8_percent_encode, AsciiSet, CONTROLS};
 use strict_types::{InvalidRString, StrictVal};
 
-use crate::{CallRequest, CallState, Endpoint};
+use crate::{CallRequest, CallState, Endpoint, EndpointSchemes};
 
 const URI_SCHEME: &str = "contract";
 const LOCK: &str = "lock";
@@ -55,6 +55,18 @@ const QUERY_ENCODE: &AsciiSet = &CONTROLS
     .add(b']')
     .add(b'&')
     .add(b'=');
+// Endpoint URLs may contain their own query strings, so the separator and the escape character
+// itself must be encoded for the endpoint list to round-trip.
+const ENDPOINT_ENCODE: &AsciiSet = &QUERY_ENCODE.add(ENDPOINT_SEP as u8).add(b'%');
+const DATA_SEP: char = ',';
+// The same applies to the data values, since string values may contain the value separator; they
+// also must not contain characters delimiting the path segment and the authority token.
+const DATA_ENCODE: &AsciiSet = &QUERY_ENCODE
+    .add(DATA_SEP as u8)
+    .add(b'%')
+    .add(b'/')
+    .add(b'?')
+    .add(b'@');
 
 impl<T, A> CallRequest<T, A> {
     pub fn has_query(&self) -> bool {
@@ -62,12 +74,26 @@ impl<T, A> CallRequest<T, A> {
     }
 }
 
-impl<T, A> Display for CallRequest<T, A>
+impl<T, A> CallRequest<T, A>
 where
     T: Display,
     A: Display,
 {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    /// Produces a canonical URI string for the call request, which is the same for all logically
+    /// identical requests.
+    ///
+    /// Unlike the [`Display`] implementation, which preserves the order of the unknown query
+    /// parameters as they were parsed or inserted, the canonical string puts them after the
+    /// `lock`, `expiry` and `endpoints` parameters sorted lexicographically by key. This makes it
+    /// suitable for the deduplication and comparison of the requests.
+    pub fn canonical_string(&self) -> String {
+        let mut s = String::new();
+        self.write_uri(&mut s, true)
+            .expect("writing to a string never fails");
+        s
+    }
+
+    fn write_uri(&self, f: &mut impl fmt::Write, canonical: bool) -> fmt::Result {
         write!(f, "contract:{}@{:-}/", self.layer1, self.scope)?;
         if let Some(api) = &self.api {
             write!(f, "{api}/")?;
@@ -79,45 +105,65 @@ where
             }
         }
 
-        if let Some(data) = &self.data {
-            write!(f, "{}@", utf8_percent_encode(&data.to_string(), QUERY_ENCODE))?;
+        if !self.data.is_empty() {
+            let mut iter = self.data.iter().peekable();
+            while let Some(data) = iter.next() {
+                // String values are written without quotes, matching how they are parsed
+                match data {
+                    StrictVal::String(s) => write!(f, "{}", utf8_percent_encode(s, DATA_ENCODE))?,
+                    _ => write!(f, "{}", utf8_percent_encode(&data.to_string(), DATA_ENCODE))?,
+                }
+                if iter.peek().is_some() {
+                    write!(f, "{DATA_SEP}")?;
+                }
+            }
+            f.write_str("@")?;
         }
         write!(f, "{}/", self.auth)?;
 
-        if self.has_query() {
-            f.write_str("?")?;
-        }
-
+        let mut sep = '?';
         if let Some(lock) = &self.lock {
             let alphabet = Alphabet::new(BAID64_ALPHABET).expect("invalid Baid64 alphabet");
             let engine = GeneralPurpose::new(&alphabet, GeneralPurposeConfig::new().with_encode_padding(false));
-            write!(f, "{LOCK}={}", engine.encode(lock))?;
+            write!(f, "{sep}{LOCK}={}", engine.encode(lock))?;
+            sep = '&';
         }
         if let Some(expiry) = &self.expiry {
-            write!(f, "{EXPIRY}={}", expiry.to_rfc3339())?;
+            write!(f, "{sep}{EXPIRY}={}", expiry.to_rfc3339())?;
+            sep = '&';
         }
         if !self.endpoints.is_empty() {
-            write!(f, "{ENDPOINTS}=")?;
+            write!(f, "{sep}{ENDPOINTS}=")?;
+            sep = '&';
             let mut iter = self.endpoints.iter().peekable();
             while let Some(endpoint) = iter.next() {
-                write!(f, "{}", utf8_percent_encode(&endpoint.to_string(), QUERY_ENCODE))?;
+                write!(f, "{}", utf8_percent_encode(&endpoint.to_string(), ENDPOINT_ENCODE))?;
                 if iter.peek().is_some() {
                     write!(f, "{ENDPOINT_SEP}")?;
                 }
             }
         }
 
-        let mut iter = self.unknown_query.iter().peekable();
-        while let Some((key, value)) = iter.next() {
-            write!(f, "{}={}", utf8_percent_encode(key, QUERY_ENCODE), utf8_percent_encode(value, QUERY_ENCODE))?;
-            if iter.peek().is_some() {
-                f.write_str("&")?;
-            }
+        let mut query = self.unknown_query.iter().collect::<Vec<_>>();
+        if canonical {
+            query.sort_by_key(|(key, _)| *key);
+        }
+        for (key, value) in query {
+            write!(f, "{sep}{}={}", utf8_percent_encode(key, QUERY_ENCODE), utf8_percent_encode(value, QUERY_ENCODE))?;
+            sep = '&';
         }
         Ok(())
     }
 }
 
+impl<T, A> Display for CallRequest<T, A>
+where
+    T: Display,
+    A: Display,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result { self.write_uri(f, false) }
+}
+
 impl<T, A> FromStr for CallRequest<T, A>
 where
     T: FromStr,
@@ -129,8 +175,36 @@ where
 
     /// # Special conditions
     ///
-    /// If a URI contains more than 10 endpoints, endpoints from number 10 are ignored.
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
+    /// If a URI contains more than 10 endpoints, the parsing fails with
+    /// [`ParseError::TooManyEndpoints`].
+    ///
+    /// Call data values are percent-decoded; if the decoded value is not a valid UTF-8 string, the
+    /// parsing fails with [`ParseError::DataInvalid`].
+    ///
+    /// Empty endpoint entries (resulting from leading, trailing or repeated separators) are
+    /// ignored.
+    ///
+    /// Endpoints using one of the recognized schemes must have a non-empty host and, if present, a
+    /// valid port; otherwise the parsing fails with [`ParseError::EndpointInvalid`]. Endpoints with
+    /// unknown schemes are not validated and are parsed as [`Endpoint::UnspecifiedMeans`].
+    ///
+    /// Endpoints are parsed with the default [`EndpointSchemes`]; use
+    /// [`CallRequest::parse_with_schemes`] to recognize custom endpoint schemes.
+    fn from_str(s: &str) -> Result<Self, Self::Err> { Self::parse_with_schemes(s, &EndpointSchemes::default()) }
+}
+
+impl<T, A> CallRequest<T, A>
+where
+    T: FromStr,
+    A: FromStr,
+    T::Err: Error,
+    A::Err: Error,
+{
+    /// Parses a call request URI, using the provided registry of scheme handlers to parse the
+    /// endpoints.
+    ///
+    /// See [`FromStr`] implementation for the details.
+    pub fn parse_with_schemes(s: &str, schemes: &EndpointSchemes) -> Result<Self, ParseError<T::Err, A::Err>> {
         let uri = Uri::parse(s)?;
 
         let scheme = uri.scheme();
@@ -158,11 +232,18 @@ where
         let value_auth = path.pop_back().ok_or(ParseError::PathNoAuth)?.as_str();
         let (data, auth) =
             if let Some((data, auth)) = value_auth.split_once('@') { (Some(data), auth) } else { (None, value_auth) };
-        let data = data.map(|data| {
-            u64::from_str(data)
-                .map(StrictVal::num)
-                .unwrap_or_else(|_| StrictVal::str(data))
-        });
+        let data = data
+            .into_iter()
+            .flat_map(|data| data.split(DATA_SEP))
+            .map(|data| {
+                let data = percent_decode(data.as_bytes())
+                    .decode_utf8()
+                    .map_err(|_| ParseError::DataInvalid(data.to_owned()))?;
+                Ok(u64::from_str(&data)
+                    .map(StrictVal::num)
+                    .unwrap_or_else(|_| StrictVal::str(data)))
+            })
+            .collect::<Result<_, ParseError<T::Err, A::Err>>>()?;
         let auth = auth.parse().map_err(ParseError::AuthInvalid)?;
 
         let api = path
@@ -191,9 +272,15 @@ where
                     let key = percent_decode(k.as_str().as_bytes())
                         .decode_utf8_lossy()
                         .to_string();
-                    let value = percent_decode(v.as_str().as_bytes())
-                        .decode_utf8_lossy()
-                        .to_string();
+                    // Endpoints are decoded only after splitting on the separator, since the
+                    // endpoint URLs themselves may contain encoded separators.
+                    let value = if key == ENDPOINTS {
+                        v.as_str().to_owned()
+                    } else {
+                        percent_decode(v.as_str().as_bytes())
+                            .decode_utf8_lossy()
+                            .to_string()
+                    };
                     match query_params.entry(key) {
                         Entry::Occupied(mut prev) => {
                             prev.insert(format!("{},{value}", prev.get()));
@@ -232,12 +319,20 @@ where
             .shift_remove(ENDPOINTS)
             .unwrap_or_default()
             .split(ENDPOINT_SEP)
-            .map(Endpoint::from_str)
-            .map(Result::unwrap)
-            .filter(|endpoint| endpoint != &Endpoint::UnspecifiedMeans(s!("")))
-            .take(10)
-            .collect::<Vec<_>>();
-        let endpoints = ConfinedVec::from_checked(endpoints);
+            // Leading, trailing and repeated separators produce empty segments, which do not
+            // represent any endpoint and must be skipped before parsing.
+            .filter(|segment| !segment.is_empty())
+            .map(|segment| {
+                let endpoint = schemes.parse(&percent_decode(segment.as_bytes()).decode_utf8_lossy());
+                match endpoint {
+                    Endpoint::UnspecifiedMeans(_) | Endpoint::Custom { .. } => Ok(endpoint),
+                    _ if is_valid_endpoint(&endpoint.to_string()) => Ok(endpoint),
+                    _ => Err(ParseError::EndpointInvalid(endpoint.to_string())),
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let count = endpoints.len();
+        let endpoints = ConfinedVec::try_from(endpoints).map_err(|_| ParseError::TooManyEndpoints(count))?;
 
         Ok(Self {
             scope,
@@ -254,6 +349,27 @@ where
     }
 }
 
+/// Checks that an endpoint URL has an authority with a non-empty host and, if the port is present,
+/// the port is a valid number.
+///
+/// Only the scheme and the authority are validated, since the rest of the URL is specific to the
+/// endpoint API.
+pub(crate) fn is_valid_endpoint(url: &str) -> bool {
+    let Some((scheme, rest)) = url.split_once("://") else {
+        return false;
+    };
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    let url = format!("{scheme}://{authority}");
+    let Ok(uri) = Uri::parse(url.as_str()) else {
+        return false;
+    };
+    let Some(authority) = uri.authority() else {
+        return false;
+    };
+    !authority.host().is_empty()
+        && matches!(authority.port_to_u16(), Ok(port) if port.is_some() || !authority.has_port())
+}
+
 #[derive(Debug, Display, Error, From)]
 #[display(doc_comments)]
 pub enum ParseError<E1: Error, E2: Error> {
@@ -285,6 +401,9 @@ pub enum ParseError<E1: Error, E2: Error> {
     /// contract call request URI misses the beneficiary authority token.
     PathNoAuth,
 
+    /// call data value '{0}' is not a valid percent-encoded UTF-8 string.
+    DataInvalid(String),
+
     /// invalid beneficiary authentication token - {0}.
     AuthInvalid(E2),
 
@@ -300,7 +419,7 @@ pub enum ParseError<E1: Error, E2: Error> {
     /// invalid lock data encoding - {0}.
     LockInvalidEncoding(DecodeError),
 
-    /// Lock data conditions are too long (they must not exceed 256 bytes).
+    /// Lock data conditions are too long (they must not exceed 255 bytes).
     LockTooLong,
 
     #[from]
@@ -309,6 +428,12 @@ pub enum ParseError<E1: Error, E2: Error> {
 
     /// invalid query parameter {0}.
     QueryParamInvalid(String),
+
+    /// contract call request contains {0} endpoints, while at most 10 are allowed.
+    TooManyEndpoints(usize),
+
+    /// endpoint '{0}' must have a valid host and port.
+    EndpointInvalid(String),
 }
 
 #[cfg(test)]
@@ -321,6 +446,7 @@ mod test {
     use ultrasonic::{AuthToken, ContractId};
 
     use super::*;
+    use crate::{CustomScheme, Endpoint};
 
     #[test]
     fn short() {
@@ -333,7 +459,7 @@ mod test {
             req.scope,
             ContractId::from_str("contract:qKpMlzOe-Imn6ysZ-a8JjG2p-WHWvaFm-BWMiPi3-_LvnfRw").unwrap()
         );
-        assert_eq!(req.data, Some(StrictVal::num(10u64)));
+        assert_eq!(req.data, vec![StrictVal::num(10u64)]);
         assert_eq!(req.auth, AuthToken::from_str("at:5WIb5EMY-RCLbO3Wq-hGdddRP4-IeCQzP1y-S5H_UKzd-ViYmlA").unwrap());
         assert_eq!(req.api, None);
         assert_eq!(req.call, None);
@@ -343,6 +469,36 @@ mod test {
         assert!(req.unknown_query.is_empty());
     }
 
+    #[test]
+    fn no_data() {
+        let s = "contract:tb@qKpMlzOe-Imn6ysZ-a8JjG2p-WHWvaFm-BWMiPi3-_LvnfRw/at:\
+                 5WIb5EMY-RCLbO3Wq-hGdddRP4-IeCQzP1y-S5H_UKzd-ViYmlA/";
+        let req = CallRequest::<ContractId, AuthToken>::from_str(s).unwrap();
+        assert_eq!(s, req.to_string());
+        assert_eq!(req.data, vec![]);
+    }
+
+    #[test]
+    fn two_values() {
+        let s = "contract:tb@qKpMlzOe-Imn6ysZ-a8JjG2p-WHWvaFm-BWMiPi3-_LvnfRw/RGB20/transfer/amount/10,20@at:\
+                 5WIb5EMY-RCLbO3Wq-hGdddRP4-IeCQzP1y-S5H_UKzd-ViYmlA/";
+        let req = CallRequest::<ContractId, AuthToken>::from_str(s).unwrap();
+        assert_eq!(s, req.to_string());
+        assert_eq!(req.data, vec![StrictVal::num(10u64), StrictVal::num(20u64)]);
+        assert_eq!(req.call, Some(CallState::with("transfer", "amount")));
+    }
+
+    #[test]
+    fn three_values() {
+        let s = "contract:tb@qKpMlzOe-Imn6ysZ-a8JjG2p-WHWvaFm-BWMiPi3-_LvnfRw/RGB20/transfer/amount/10,20,30@at:\
+                 5WIb5EMY-RCLbO3Wq-hGdddRP4-IeCQzP1y-S5H_UKzd-ViYmlA/?expiry=2021-05-20T08:32:48+00:00";
+        let req = CallRequest::<ContractId, AuthToken>::from_str(s).unwrap();
+        assert_eq!(s, req.to_string());
+        assert_eq!(req.data, vec![StrictVal::num(10u64), StrictVal::num(20u64), StrictVal::num(30u64)]);
+        assert_eq!(req.expiry, Some(Utc.with_ymd_and_hms(2021, 5, 20, 8, 32, 48).unwrap()));
+        assert_eq!(CallRequest::<ContractId, AuthToken>::from_str(&req.to_string()).unwrap(), req);
+    }
+
     #[test]
     fn api() {
         let s = "contract:tb@qKpMlzOe-Imn6ysZ-a8JjG2p-WHWvaFm-BWMiPi3-_LvnfRw/RGB20/10@at:\
@@ -354,7 +510,7 @@ mod test {
             req.scope,
             ContractId::from_str("contract:qKpMlzOe-Imn6ysZ-a8JjG2p-WHWvaFm-BWMiPi3-_LvnfRw").unwrap()
         );
-        assert_eq!(req.data, Some(StrictVal::num(10u64)));
+        assert_eq!(req.data, vec![StrictVal::num(10u64)]);
         assert_eq!(req.auth, AuthToken::from_str("at:5WIb5EMY-RCLbO3Wq-hGdddRP4-IeCQzP1y-S5H_UKzd-ViYmlA").unwrap());
         assert_eq!(req.api, Some(tn!("RGB20")));
         assert_eq!(req.call, None);
@@ -375,7 +531,7 @@ mod test {
             req.scope,
             ContractId::from_str("contract:qKpMlzOe-Imn6ysZ-a8JjG2p-WHWvaFm-BWMiPi3-_LvnfRw").unwrap()
         );
-        assert_eq!(req.data, Some(StrictVal::num(10u64)));
+        assert_eq!(req.data, vec![StrictVal::num(10u64)]);
         assert_eq!(req.auth, AuthToken::from_str("at:5WIb5EMY-RCLbO3Wq-hGdddRP4-IeCQzP1y-S5H_UKzd-ViYmlA").unwrap());
         assert_eq!(req.api, Some(tn!("RGB20")));
         assert_eq!(req.call, Some(CallState::new("transfer")));
@@ -396,7 +552,7 @@ mod test {
             req.scope,
             ContractId::from_str("contract:qKpMlzOe-Imn6ysZ-a8JjG2p-WHWvaFm-BWMiPi3-_LvnfRw").unwrap()
         );
-        assert_eq!(req.data, Some(StrictVal::num(10u64)));
+        assert_eq!(req.data, vec![StrictVal::num(10u64)]);
         assert_eq!(req.auth, AuthToken::from_str("at:5WIb5EMY-RCLbO3Wq-hGdddRP4-IeCQzP1y-S5H_UKzd-ViYmlA").unwrap());
         assert_eq!(req.api, Some(tn!("RGB20")));
         assert_eq!(req.call, Some(CallState::with("transfer", "amount")));
@@ -417,7 +573,7 @@ mod test {
             req.scope,
             ContractId::from_str("contract:qKpMlzOe-Imn6ysZ-a8JjG2p-WHWvaFm-BWMiPi3-_LvnfRw").unwrap()
         );
-        assert_eq!(req.data, Some(StrictVal::num(10u64)));
+        assert_eq!(req.data, vec![StrictVal::num(10u64)]);
         assert_eq!(req.auth, AuthToken::from_str("at:5WIb5EMY-RCLbO3Wq-hGdddRP4-IeCQzP1y-S5H_UKzd-ViYmlA").unwrap());
         assert_eq!(req.api, Some(tn!("RGB20")));
         assert_eq!(req.call, Some(CallState::with("transfer", "amount")));
@@ -438,7 +594,7 @@ mod test {
             req.scope,
             ContractId::from_str("contract:qKpMlzOe-Imn6ysZ-a8JjG2p-WHWvaFm-BWMiPi3-_LvnfRw").unwrap()
         );
-        assert_eq!(req.data, Some(StrictVal::num(10u64)));
+        assert_eq!(req.data, vec![StrictVal::num(10u64)]);
         assert_eq!(req.auth, AuthToken::from_str("at:5WIb5EMY-RCLbO3Wq-hGdddRP4-IeCQzP1y-S5H_UKzd-ViYmlA").unwrap());
         assert_eq!(req.api, Some(tn!("RGB20")));
         assert_eq!(req.call, Some(CallState::with("transfer", "amount")));
@@ -463,7 +619,7 @@ mod test {
             req.scope,
             ContractId::from_str("contract:qKpMlzOe-Imn6ysZ-a8JjG2p-WHWvaFm-BWMiPi3-_LvnfRw").unwrap()
         );
-        assert_eq!(req.data, Some(StrictVal::num(10u64)));
+        assert_eq!(req.data, vec![StrictVal::num(10u64)]);
         assert_eq!(req.auth, AuthToken::from_str("at:5WIb5EMY-RCLbO3Wq-hGdddRP4-IeCQzP1y-S5H_UKzd-ViYmlA").unwrap());
         assert_eq!(req.api, Some(tn!("RGB20")));
         assert_eq!(req.call, Some(CallState::with("transfer", "amount")));
@@ -496,7 +652,7 @@ mod test {
             req.scope,
             ContractId::from_str("contract:qKpMlzOe-Imn6ysZ-a8JjG2p-WHWvaFm-BWMiPi3-_LvnfRw").unwrap()
         );
-        assert_eq!(req.data, Some(StrictVal::num(10u64)));
+        assert_eq!(req.data, vec![StrictVal::num(10u64)]);
         assert_eq!(req.auth, AuthToken::from_str("at:5WIb5EMY-RCLbO3Wq-hGdddRP4-IeCQzP1y-S5H_UKzd-ViYmlA").unwrap());
         assert_eq!(req.api, Some(tn!("RGB20")));
         assert_eq!(req.call, Some(CallState::with("transfer", "amount")));
@@ -515,6 +671,130 @@ mod test {
         assert!(req.unknown_query.is_empty());
     }
 
+    #[test]
+    fn endpoints_too_many() {
+        let uri = |count: u16| {
+            let endpoints = (0..count)
+                .map(|no| format!("http://127.0.0.1:{}", 8080 + no))
+                .collect::<Vec<_>>()
+                .join(",");
+            format!(
+                "contract:tb@qKpMlzOe-Imn6ysZ-a8JjG2p-WHWvaFm-BWMiPi3-_LvnfRw/RGB20/transfer/amount/10@at:\
+                 5WIb5EMY-RCLbO3Wq-hGdddRP4-IeCQzP1y-S5H_UKzd-ViYmlA/?endpoints={endpoints}"
+            )
+        };
+
+        let req = CallRequest::<ContractId, AuthToken>::from_str(&uri(10)).unwrap();
+        assert_eq!(req.endpoints.len(), 10);
+        assert_eq!(req.endpoints[9], Endpoint::RestHttp("http://127.0.0.1:8089".to_owned()));
+
+        let err = CallRequest::<ContractId, AuthToken>::from_str(&uri(11)).unwrap_err();
+        assert!(matches!(err, ParseError::TooManyEndpoints(11)));
+    }
+
+    #[test]
+    fn endpoints_custom_scheme() {
+        let s = "contract:tb@qKpMlzOe-Imn6ysZ-a8JjG2p-WHWvaFm-BWMiPi3-_LvnfRw/RGB20/transfer/amount/10@at:\
+                 5WIb5EMY-RCLbO3Wq-hGdddRP4-IeCQzP1y-S5H_UKzd-ViYmlA/?\
+                 endpoints=tor://example.onion:8080,http://127.0.0.1:8080,i2p://example.i2p";
+
+        let req = CallRequest::<ContractId, AuthToken>::from_str(s).unwrap();
+        assert_eq!(req.endpoints[0], Endpoint::UnspecifiedMeans("tor://example.onion:8080".to_owned()));
+
+        let mut schemes = EndpointSchemes::default();
+        schemes.register(CustomScheme::new("tor"));
+        let req = CallRequest::<ContractId, AuthToken>::parse_with_schemes(s, &schemes).unwrap();
+        assert_eq!(s, req.to_string());
+        assert_eq!(
+            req.endpoints,
+            Confined::from_iter_checked([
+                Endpoint::Custom { scheme: s!("tor"), url: s!("tor://example.onion:8080") },
+                Endpoint::RestHttp("http://127.0.0.1:8080".to_owned()),
+                Endpoint::UnspecifiedMeans("i2p://example.i2p".to_owned()),
+            ])
+        );
+    }
+
+    #[test]
+    fn endpoints_empty_segments() {
+        let req = CallRequest::<ContractId, AuthToken>::from_str(
+            "contract:tb@qKpMlzOe-Imn6ysZ-a8JjG2p-WHWvaFm-BWMiPi3-_LvnfRw/RGB20/transfer/amount/10@at:\
+             5WIb5EMY-RCLbO3Wq-hGdddRP4-IeCQzP1y-S5H_UKzd-ViYmlA/?endpoints=,,http://x,",
+        )
+        .unwrap();
+        assert_eq!(req.endpoints, Confined::from_iter_checked([Endpoint::RestHttp("http://x".to_owned())]));
+        assert!(req.unknown_query.is_empty());
+
+        let req = CallRequest::<ContractId, AuthToken>::from_str(
+            "contract:tb@qKpMlzOe-Imn6ysZ-a8JjG2p-WHWvaFm-BWMiPi3-_LvnfRw/RGB20/transfer/amount/10@at:\
+             5WIb5EMY-RCLbO3Wq-hGdddRP4-IeCQzP1y-S5H_UKzd-ViYmlA/?endpoints=,",
+        )
+        .unwrap();
+        assert_eq!(req.endpoints, none!());
+    }
+
+    #[test]
+    fn endpoints_grpc() {
+        let s = "contract:tb@qKpMlzOe-Imn6ysZ-a8JjG2p-WHWvaFm-BWMiPi3-_LvnfRw/RGB20/transfer/amount/10@at:\
+                 5WIb5EMY-RCLbO3Wq-hGdddRP4-IeCQzP1y-S5H_UKzd-ViYmlA/?\
+                 endpoints=http://127.0.0.1:8080,grpc://127.0.0.1:50051,grpc+tls://node.example.com:443,\
+                 storm://127.0.0.1:8082";
+        let req = CallRequest::<ContractId, AuthToken>::from_str(s).unwrap();
+        assert_eq!(
+            req.endpoints,
+            Confined::from_iter_checked([
+                Endpoint::RestHttp("http://127.0.0.1:8080".to_owned()),
+                Endpoint::Grpc("grpc://127.0.0.1:50051".to_owned()),
+                Endpoint::Grpc("grpc+tls://node.example.com:443".to_owned()),
+                Endpoint::Storm("storm://127.0.0.1:8082".to_owned()),
+            ])
+        );
+        assert_eq!(s, req.to_string());
+        assert_eq!(CallRequest::<ContractId, AuthToken>::from_str(&req.to_string()).unwrap(), req);
+
+        let err = CallRequest::<ContractId, AuthToken>::from_str(&s.replace("127.0.0.1:50051", ":50051")).unwrap_err();
+        assert!(matches!(err, ParseError::EndpointInvalid(url) if url == "grpc://:50051"));
+    }
+
+    #[test]
+    fn endpoints_invalid() {
+        let prefix = "contract:tb@qKpMlzOe-Imn6ysZ-a8JjG2p-WHWvaFm-BWMiPi3-_LvnfRw/RGB20/transfer/amount/10@at:\
+                      5WIb5EMY-RCLbO3Wq-hGdddRP4-IeCQzP1y-S5H_UKzd-ViYmlA/?endpoints=";
+
+        let req = CallRequest::<ContractId, AuthToken>::from_str(&format!("{prefix}wss://relay.example.com:8081/ws"))
+            .unwrap();
+        assert_eq!(
+            req.endpoints,
+            Confined::from_iter_checked([Endpoint::WebSockets("wss://relay.example.com:8081/ws".to_owned())])
+        );
+
+        for endpoint in ["wss://relay.example.com:80x", "wss://:8081", "wss://relay.example.com:", "wss://"] {
+            let err = CallRequest::<ContractId, AuthToken>::from_str(&format!("{prefix}{endpoint}")).unwrap_err();
+            assert!(matches!(err, ParseError::EndpointInvalid(url) if url == endpoint));
+        }
+
+        // Unknown schemes are not validated
+        let req = CallRequest::<ContractId, AuthToken>::from_str(&format!("{prefix}htps://bad")).unwrap();
+        assert_eq!(req.endpoints, Confined::from_iter_checked([Endpoint::UnspecifiedMeans("htps://bad".to_owned())]));
+    }
+
+    #[test]
+    fn endpoints_with_query() {
+        let s = "contract:tb@qKpMlzOe-Imn6ysZ-a8JjG2p-WHWvaFm-BWMiPi3-_LvnfRw/RGB20/transfer/amount/10@at:\
+                 5WIb5EMY-RCLbO3Wq-hGdddRP4-IeCQzP1y-S5H_UKzd-ViYmlA/?\
+                 endpoints=http://127.0.0.1:8080/api?a%3Db%2Cc%25d,wss://127.0.0.1:8081";
+        let req = CallRequest::<ContractId, AuthToken>::from_str(s).unwrap();
+        assert_eq!(
+            req.endpoints,
+            Confined::from_iter_checked([
+                Endpoint::RestHttp("http://127.0.0.1:8080/api?a=b,c%d".to_owned()),
+                Endpoint::WebSockets("wss://127.0.0.1:8081".to_owned()),
+            ])
+        );
+        assert_eq!(s, req.to_string());
+        assert_eq!(CallRequest::<ContractId, AuthToken>::from_str(&req.to_string()).unwrap(), req);
+    }
+
     #[test]
     fn unknown_query() {
         let s = "contract:tb@qKpMlzOe-Imn6ysZ-a8JjG2p-WHWvaFm-BWMiPi3-_LvnfRw/RGB20/transfer/amount/10@at:\
@@ -526,7 +806,7 @@ mod test {
             req.scope,
             ContractId::from_str("contract:qKpMlzOe-Imn6ysZ-a8JjG2p-WHWvaFm-BWMiPi3-_LvnfRw").unwrap()
         );
-        assert_eq!(req.data, Some(StrictVal::num(10u64)));
+        assert_eq!(req.data, vec![StrictVal::num(10u64)]);
         assert_eq!(req.auth, AuthToken::from_str("at:5WIb5EMY-RCLbO3Wq-hGdddRP4-IeCQzP1y-S5H_UKzd-ViYmlA").unwrap());
         assert_eq!(req.api, Some(tn!("RGB20")));
         assert_eq!(req.call, Some(CallState::with("transfer", "amount")));
@@ -538,4 +818,44 @@ mod test {
             indexmap! { s!("sats") => s!("40"), s!("bull") => s!("shit"), s!("other") => s!("x") }
         );
     }
+
+    #[test]
+    fn canonical_string() {
+        let prefix = "contract:tb@qKpMlzOe-Imn6ysZ-a8JjG2p-WHWvaFm-BWMiPi3-_LvnfRw/RGB20/transfer/amount/10@at:\
+                      5WIb5EMY-RCLbO3Wq-hGdddRP4-IeCQzP1y-S5H_UKzd-ViYmlA/";
+        let s1 = format!("{prefix}?expiry=2021-05-20T08:32:48+00:00&sats=40&bull=shit&other=x");
+        let s2 = format!("{prefix}?other=x&expiry=2021-05-20T08:32:48+00:00&sats=40&bull=shit");
+        let req1 = CallRequest::<ContractId, AuthToken>::from_str(&s1).unwrap();
+        let req2 = CallRequest::<ContractId, AuthToken>::from_str(&s2).unwrap();
+        assert_ne!(req1.to_string(), req2.to_string());
+
+        let canonical = format!("{prefix}?expiry=2021-05-20T08:32:48+00:00&bull=shit&other=x&sats=40");
+        assert_eq!(req1.canonical_string(), canonical);
+        assert_eq!(req2.canonical_string(), canonical);
+        assert_eq!(req1.to_string(), s1);
+        assert_eq!(
+            CallRequest::<ContractId, AuthToken>::from_str(&canonical)
+                .unwrap()
+                .canonical_string(),
+            canonical
+        );
+    }
+
+    #[test]
+    fn string_values() {
+        let s = "contract:tb@qKpMlzOe-Imn6ysZ-a8JjG2p-WHWvaFm-BWMiPi3-_LvnfRw/RGB20/transfer/amount/hello%20world%\
+                 23one,a%2Cb%40c%2Fd,10@at:5WIb5EMY-RCLbO3Wq-hGdddRP4-IeCQzP1y-S5H_UKzd-ViYmlA/";
+        let req = CallRequest::<ContractId, AuthToken>::from_str(s).unwrap();
+        assert_eq!(req.data, vec![StrictVal::str("hello world#one"), StrictVal::str("a,b@c/d"), StrictVal::num(10u64)]);
+        assert_eq!(s, req.to_string());
+        assert_eq!(CallRequest::<ContractId, AuthToken>::from_str(&req.to_string()).unwrap(), req);
+    }
+
+    #[test]
+    fn string_values_invalid() {
+        let s = "contract:tb@qKpMlzOe-Imn6ysZ-a8JjG2p-WHWvaFm-BWMiPi3-_LvnfRw/RGB20/transfer/amount/ok,%FF%FE@at:\
+                 5WIb5EMY-RCLbO3Wq-hGdddRP4-IeCQzP1y-S5H_UKzd-ViYmlA/";
+        let err = CallRequest::<ContractId, AuthToken>::from_str(s).unwrap_err();
+        assert!(matches!(err, ParseError::DataInvalid(data) if data == "%FF%FE"));
+    }
 }
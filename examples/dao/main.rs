@@ -79,6 +79,7 @@ fn api() -> Api {
                 published: true,
                 sem_id: types.get("DAO.PartyId"),
                 convertor: StateConvertor::TypedEncoder(u256::ZERO),
+                fallback_convertors: none!(),
                 builder: StateBuilder::TypedEncoder(u256::ZERO),
                 raw_convertor: RawConvertor::StrictDecode(types.get("DAO.Party")),
                 raw_builder: RawBuilder::StrictEncode(types.get("DAO.Party")),
@@ -87,6 +88,7 @@ fn api() -> Api {
                 published: true,
                 sem_id: types.get("DAO.VoteId"),
                 convertor: StateConvertor::TypedEncoder(u256::ONE),
+                fallback_convertors: none!(),
                 builder: StateBuilder::TypedEncoder(u256::ONE),
                 raw_convertor: RawConvertor::StrictDecode(types.get("DAO.Voting")),
                 raw_builder: RawBuilder::StrictEncode(types.get("DAO.Voting")),
@@ -95,6 +97,7 @@ fn api() -> Api {
                 published: true,
                 sem_id: types.get("DAO.CastVote"),
                 convertor: StateConvertor::TypedEncoder(u256::from(2u8)),
+                fallback_convertors: none!(),
                 builder: StateBuilder::TypedEncoder(u256::from(2u8)),
                 raw_convertor: RawConvertor::StrictDecode(SemId::unit()),
                 raw_builder: RawBuilder::StrictEncode(SemId::unit()),
@@ -105,6 +108,7 @@ fn api() -> Api {
                 sem_id: types.get("DAO.PartyId"),
                 arithmetics: StateArithm::NonFungible,
                 convertor: StateConvertor::TypedEncoder(u256::ZERO),
+                fallback_convertors: none!(),
                 builder: StateBuilder::TypedEncoder(u256::ZERO),
                 witness_sem_id: SemId::unit(),
                 witness_builder: StateBuilder::TypedEncoder(u256::ZERO),
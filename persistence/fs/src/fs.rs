@@ -27,10 +27,12 @@ use std::io::Write;
 use std::path::{Path, PathBuf};
 use std::{fs, io};
 
+use amplify::confinement::TinyString;
 use amplify::MultiError;
 use aora::file::{FileAoraIndex, FileAoraMap, FileAuraMap};
 use aora::{AoraIndex, AoraMap, AuraMap, TransactionalMap};
 use binfile::BinFile;
+use chrono::{DateTime, Utc};
 use hypersonic::{
     Articles, CellAddr, EffectiveState, Genesis, Issue, IssueError, Ledger, Operation, Opid, RawState, SemanticError,
     Semantics, SigBlob, Stock, Transition,
@@ -47,6 +49,8 @@ const TRACE_MAGIC: u64 = u64::from_be_bytes(*b"CONTRACE");
 const SPENT_MAGIC: u64 = u64::from_be_bytes(*b"OPSPENT ");
 const READ_MAGIC: u64 = u64::from_be_bytes(*b"OPREADBY");
 const VALID_MAGIC: u64 = u64::from_be_bytes(*b"OPVALID ");
+const RECEIVED_MAGIC: u64 = u64::from_be_bytes(*b"OPRECVAT");
+const REASON_MAGIC: u64 = u64::from_be_bytes(*b"OPREASON");
 
 const SEMANTICS_MAGIC: u64 = u64::from_be_bytes(*b"SEMANTIC");
 const STATE_MAGIC: u64 = u64::from_be_bytes(*b"CONSTATE");
@@ -87,12 +91,47 @@ impl From<OpValidity> for bool {
     }
 }
 
+/// Local receive-time of an operation, in milliseconds since the UNIX epoch.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+struct ReceivedAt(i64);
+
+impl From<[u8; 8]> for ReceivedAt {
+    fn from(bytes: [u8; 8]) -> Self { Self(i64::from_le_bytes(bytes)) }
+}
+
+impl From<ReceivedAt> for [u8; 8] {
+    fn from(v: ReceivedAt) -> Self { v.0.to_le_bytes() }
+}
+
+/// Reason for an operation invalidation; an empty string stands for no reason.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+struct InvalidationReason(TinyString);
+
+impl From<[u8; 256]> for InvalidationReason {
+    fn from(bytes: [u8; 256]) -> Self {
+        let len = bytes[0] as usize;
+        let s = String::from_utf8_lossy(&bytes[1..=len]);
+        Self(TinyString::from_checked(s.into_owned()))
+    }
+}
+
+impl From<InvalidationReason> for [u8; 256] {
+    fn from(v: InvalidationReason) -> Self {
+        let mut bytes = [0u8; 256];
+        bytes[0] = v.0.len() as u8;
+        bytes[1..=v.0.len()].copy_from_slice(v.0.as_bytes());
+        bytes
+    }
+}
+
 #[derive(Debug)]
 pub struct StockFs {
     path: PathBuf,
     stash: FileAoraMap<Opid, Operation, STASH_MAGIC, 1>,
     trace: FileAoraMap<Opid, Transition, TRACE_MAGIC, 1>,
     valid: FileAuraMap<Opid, OpValidity, VALID_MAGIC, 1, 32, 1>,
+    received: FileAuraMap<Opid, ReceivedAt, RECEIVED_MAGIC, 1, 32, 8>,
+    reasons: FileAuraMap<Opid, InvalidationReason, REASON_MAGIC, 1, 32, 256>,
     spent: FileAuraMap<CellAddr, Opid, SPENT_MAGIC, 1, 34>,
     read: FileAoraIndex<CellAddr, Opid, READ_MAGIC, 1, 34>,
     articles: Articles,
@@ -105,6 +144,13 @@ impl StockFs {
     const FILENAME_GENESIS: &'static str = "genesis.dat";
     const FILENAME_SEMANTICS: &'static str = "semantics.dat";
     const FILENAME_STATE_RAW: &'static str = "state.dat";
+
+    fn clear_reason(&mut self, opid: Opid) {
+        if self.reasons.contains_key(opid) {
+            self.reasons
+                .insert_or_update(opid, InvalidationReason(none!()));
+        }
+    }
 }
 
 impl Stock for StockFs {
@@ -117,6 +163,8 @@ impl Stock for StockFs {
         let spent = FileAuraMap::create_new(&path, "spent")?;
         let read = FileAoraIndex::create_new(&path, "read")?;
         let valid = FileAuraMap::create_new(&path, "valid")?;
+        let received = FileAuraMap::create_new(&path, "received")?;
+        let reasons = FileAuraMap::create_new(&path, "reasons")?;
 
         let meta = toml::to_string(&articles.issue().meta)?;
         let mut file = File::create_new(path.join(Self::FILENAME_META))?;
@@ -138,7 +186,18 @@ impl Stock for StockFs {
         let writer = StreamWriter::new::<{ usize::MAX }>(file);
         state.raw.strict_write(writer)?;
 
-        Ok(Self { path, stash, trace, spent, read, articles, state, valid })
+        Ok(Self {
+            path,
+            stash,
+            trace,
+            spent,
+            read,
+            articles,
+            state,
+            valid,
+            received,
+            reasons,
+        })
     }
 
     fn load(path: PathBuf) -> Result<Self, FsError> {
@@ -149,6 +208,16 @@ impl Stock for StockFs {
         let spent = FileAuraMap::open(&path, "spent")?;
         let read = FileAoraIndex::open(&path, "read")?;
         let valid = FileAuraMap::open(&path, "valid")?;
+        // Contracts persisted by older versions do not have receive-time information
+        let received = match FileAuraMap::open(&path, "received") {
+            Err(err) if err.kind() == io::ErrorKind::NotFound => FileAuraMap::create_new(&path, "received")?,
+            res => res?,
+        };
+        // Nor do they have invalidation reasons
+        let reasons = match FileAuraMap::open(&path, "reasons") {
+            Err(err) if err.kind() == io::ErrorKind::NotFound => FileAuraMap::create_new(&path, "reasons")?,
+            res => res?,
+        };
 
         let meta = fs::read_to_string(path.join(Self::FILENAME_META))?;
         let meta = toml::from_str(&meta)?;
@@ -176,7 +245,18 @@ impl Stock for StockFs {
 
         let state = EffectiveState::with_raw_state(raw, &articles);
 
-        Ok(Self { path, stash, trace, spent, read, articles, state, valid })
+        Ok(Self {
+            path,
+            stash,
+            trace,
+            spent,
+            read,
+            articles,
+            state,
+            valid,
+            received,
+            reasons,
+        })
     }
 
     fn config(&self) -> Self::Conf { self.path.clone() }
@@ -188,10 +268,38 @@ impl Stock for StockFs {
 
     #[inline]
     fn is_valid(&self, opid: Opid) -> bool { self.valid.get(opid).map(bool::from).unwrap_or_default() }
+    fn mark_valid(&mut self, opid: Opid) {
+        self.valid.insert_or_update(opid, OpValidity::Valid);
+        self.clear_reason(opid);
+    }
+    fn mark_invalid(&mut self, opid: Opid) {
+        self.valid.insert_or_update(opid, OpValidity::Invalid);
+        self.clear_reason(opid);
+    }
+    fn mark_invalid_with_reason(&mut self, opid: Opid, reason: TinyString) {
+        self.valid.insert_or_update(opid, OpValidity::Invalid);
+        self.reasons
+            .insert_or_update(opid, InvalidationReason(reason));
+    }
+    fn invalidation_reason(&self, opid: Opid) -> Option<TinyString> {
+        self.reasons
+            .get(opid)
+            .map(|reason| reason.0)
+            .filter(|reason| !reason.is_empty())
+    }
+
     #[inline]
-    fn mark_valid(&mut self, opid: Opid) { self.valid.insert_or_update(opid, OpValidity::Valid) }
+    fn received_at(&self, opid: Opid) -> Option<DateTime<Utc>> {
+        self.received
+            .get(opid)
+            .and_then(|time| DateTime::from_timestamp_millis(time.0))
+    }
+
     #[inline]
-    fn mark_invalid(&mut self, opid: Opid) { self.valid.insert_or_update(opid, OpValidity::Invalid) }
+    fn set_received_at(&mut self, opid: Opid, time: DateTime<Utc>) {
+        self.received
+            .insert_or_update(opid, ReceivedAt(time.timestamp_millis()))
+    }
 
     #[inline]
     fn has_operation(&self, opid: Opid) -> bool { self.stash.contains_key(opid) }
@@ -255,6 +363,8 @@ impl Stock for StockFs {
     fn commit_transaction(&mut self) {
         self.spent.commit_transaction();
         self.valid.commit_transaction();
+        self.received.commit_transaction();
+        self.reasons.commit_transaction();
     }
 }
 
@@ -23,13 +23,15 @@
 
 use std::collections::BTreeMap;
 
+use aluvm::LibSite;
+use amplify::num::u256;
 use amplify::MultiError;
 use sonic_callreq::StateName;
 use sonicapi::{CoreParams, OpBuilder};
 use strict_types::StrictVal;
-use ultrasonic::{AuthToken, CellAddr, CellLock, Opid};
+use ultrasonic::{fe256, AuthToken, CellAddr, CellLock, Operation, Opid, StateValue};
 
-use crate::{AcceptError, Ledger, Stock};
+use crate::{verify_operation, AcceptError, Ledger, MethodName, Stock};
 
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -38,6 +40,30 @@ pub struct Satisfaction {
     pub witness: StrictVal,
 }
 
+impl Satisfaction {
+    /// Constructs satisfaction for a hash-locked cell (see [`Satisfaction::hash_lock`]),
+    /// providing a preimage of the hash as the input witness.
+    ///
+    /// The `name` must be the name of the owned state of the spent cell, which defines how the
+    /// preimage is encoded into the witness.
+    pub fn preimage(name: StateName, preimage: impl Into<StrictVal>) -> Self {
+        Self { name, witness: preimage.into() }
+    }
+
+    /// Constructs lock conditions for a hash-locked cell, which can be spent only by providing a
+    /// preimage of the `digest` (see [`Satisfaction::preimage`]).
+    ///
+    /// The digest is truncated to its first 30 bytes, such that it fits a field element, and put
+    /// into the auxiliary lock data. The `script` must point to a lock script checking that it
+    /// matches the hash of the input witness, truncated in the same way.
+    pub fn hash_lock(digest: [u8; 32], script: LibSite) -> CellLock {
+        let mut buf = [0u8; 32];
+        buf[..30].copy_from_slice(&digest[..30]);
+        let digest = fe256::from(u256::from_le_bytes(buf));
+        CellLock { aux: StateValue::Single { first: digest }, script: Some(script) }
+    }
+}
+
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct CallParams {
@@ -47,12 +73,26 @@ pub struct CallParams {
     pub reading: Vec<CellAddr>,
 }
 
+/// Builder constructing a new operation ("deed") under the contract and adding it to the ledger.
+///
+/// The builder doesn't modify the ledger state or stash until [`DeedBuilder::commit`] is called;
+/// thus, a deed can be abandoned at any moment by dropping the builder or calling
+/// [`DeedBuilder::cancel`].
 pub struct DeedBuilder<'c, S: Stock> {
     pub(super) builder: OpBuilder,
     pub(super) ledger: &'c mut Ledger<S>,
 }
 
 impl<S: Stock> DeedBuilder<'_, S> {
+    /// Sets the deed operation nonce, which defaults to zero.
+    ///
+    /// The nonce is committed to in the operation id; thus it allows producing deeds with distinct
+    /// ids despite them having identical content.
+    pub fn nonce(mut self, nonce: fe256) -> Self {
+        self.builder = self.builder.nonce(nonce);
+        self
+    }
+
     pub fn reading(mut self, addr: CellAddr) -> Self {
         self.builder = self.builder.access(addr);
         self
@@ -65,17 +105,19 @@ impl<S: Stock> DeedBuilder<'_, S> {
 
     pub fn satisfying(mut self, addr: CellAddr, name: impl Into<StateName>, witness: StrictVal) -> Self {
         let api = &self.ledger.articles().default_api();
+        let libs = &self.ledger.articles().semantics().api_libs;
         let types = &self.ledger.articles().types();
         self.builder = self
             .builder
-            .destroy_satisfy(addr, name, witness, api, types);
+            .destroy_satisfy(addr, name, witness, api, libs, types);
         self
     }
 
     pub fn append(mut self, name: impl Into<StateName>, data: StrictVal, raw: Option<StrictVal>) -> Self {
         let api = &self.ledger.articles().default_api();
+        let libs = &self.ledger.articles().semantics().api_libs;
         let types = &self.ledger.articles().types();
-        self.builder = self.builder.add_global(name, data, raw, api, types);
+        self.builder = self.builder.add_global(name, data, raw, api, libs, types);
         self
     }
 
@@ -87,11 +129,19 @@ impl<S: Stock> DeedBuilder<'_, S> {
         lock: Option<CellLock>,
     ) -> Self {
         let api = &self.ledger.articles().default_api();
+        let libs = &self.ledger.articles().semantics().api_libs;
         let types = &self.ledger.articles().types();
-        self.builder = self.builder.add_owned(name, auth, data, lock, api, types);
+        self.builder = self
+            .builder
+            .add_owned(name, auth, data, lock, api, libs, types);
         self
     }
 
+    /// Abandons the deed, releasing the ledger without any changes to its state or stash.
+    ///
+    /// This is equivalent to dropping the builder, and is provided for code clarity.
+    pub fn cancel(self) {}
+
     pub fn commit<'a>(self) -> Result<Opid, MultiError<AcceptError, S::Error>>
     where Self: 'a {
         let deed = self.builder.finalize();
@@ -101,3 +151,82 @@ impl<S: Stock> DeedBuilder<'_, S> {
         Ok(opid)
     }
 }
+
+/// Builder constructing several operations ("deeds") under the contract and adding them to the
+/// ledger together: the deeds are added only if all of them pass the verification (see
+/// [`BatchBuilder::commit_all`] for the details on persistence failures).
+///
+/// Deeds added to the batch may spend or read outputs of the deeds added before them.
+///
+/// Like [`DeedBuilder`], the builder doesn't modify the ledger state or stash until
+/// [`BatchBuilder::commit_all`] is called.
+pub struct BatchBuilder<'c, S: Stock> {
+    pub(super) deeds: Vec<Operation>,
+    pub(super) ledger: &'c mut Ledger<S>,
+}
+
+impl<S: Stock> BatchBuilder<'_, S> {
+    /// Adds a deed calling the `method` to the batch, constructing it with the `build` closure.
+    ///
+    /// # Returns
+    ///
+    /// Id of the deed operation, which can be used to refer to its outputs from the deeds added to
+    /// the batch later.
+    pub fn deed(
+        &mut self,
+        method: impl Into<MethodName>,
+        build: impl FnOnce(DeedBuilder<'_, S>) -> DeedBuilder<'_, S>,
+    ) -> Opid {
+        let deed = build(self.ledger.start_deed(method)).builder.finalize();
+        let opid = deed.opid();
+        self.deeds.push(deed);
+        opid
+    }
+
+    /// Abandons the batch, releasing the ledger without any changes to its state or stash.
+    ///
+    /// This is equivalent to dropping the builder, and is provided for code clarity.
+    pub fn cancel(self) {}
+
+    /// Verifies all the deeds of the batch, each against the state resulting from the deeds
+    /// preceding it, and, if all of them are valid, adds them to the ledger.
+    ///
+    /// # Returns
+    ///
+    /// Ids of the deed operations in the order they were added to the batch.
+    ///
+    /// # Errors
+    ///
+    /// If any of the deeds fails the verification, returns the error without modifying the ledger.
+    ///
+    /// If the persistence fails while adding the deeds, the deeds which were already added are
+    /// rolled back: they are kept in the stash, but are marked as invalid, and their state is
+    /// removed. The deed which has caused the failure may remain partially applied, since its
+    /// changes are not undone.
+    pub fn commit_all(self) -> Result<Vec<Opid>, MultiError<AcceptError, S::Error>> {
+        let articles = self.ledger.articles();
+        let mut raw = self.ledger.state().raw.clone();
+        let mut verified = Vec::with_capacity(self.deeds.len());
+        for deed in self.deeds {
+            let op = verify_operation(articles, &raw, &deed)
+                .map_err(AcceptError::from)
+                .map_err(MultiError::A)?;
+            // We do not need state transitions, since the state is not persisted.
+            let _ = raw.apply(op.clone());
+            verified.push(op);
+        }
+
+        let mut applied = Vec::with_capacity(verified.len());
+        for op in verified {
+            let opid = op.opid();
+            if let Err(err) = self.ledger.apply(op) {
+                self.ledger.commit_transaction();
+                self.ledger.rollback(applied).map_err(MultiError::B)?;
+                return Err(MultiError::B(err));
+            }
+            applied.push(opid);
+        }
+        self.ledger.commit_transaction();
+        Ok(applied)
+    }
+}
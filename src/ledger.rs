@@ -21,22 +21,35 @@
 // or implied. See the License for the specific language governing permissions and limitations under
 // the License.
 
-use alloc::collections::BTreeSet;
+use alloc::collections::{BTreeMap, BTreeSet};
 use core::borrow::Borrow;
+use core::iter;
 use std::io;
 
+use aluvm::{Lib, LibSite};
+use amplify::confinement::{SmallOrdMap, TinyOrdMap, TinyString};
 use amplify::MultiError;
-use commit_verify::{ReservedBytes, StrictHash};
+use chrono::{DateTime, Utc};
+use commit_verify::{CommitmentId, DigestExt, ReservedBytes, Sha256, StrictHash};
 use indexmap::IndexSet;
 use sonic_callreq::MethodName;
-use sonicapi::{Api, NamedState, OpBuilder, SemanticError, Semantics, SigBlob};
+use sonicapi::{
+    Api, DataCell, IssueBuilder, Issuer, NamedState, OpBuilder, SemanticError, Semantics, SigBlob, StateArithm,
+    StateAtom, StateCalc, StateName,
+};
 use strict_encoding::{
-    DecodeError, ReadRaw, SerializeError, StrictDecode, StrictEncode, StrictReader, StrictWriter, TypedRead, WriteRaw,
+    DecodeError, ReadRaw, SerializeError, StreamWriter, StrictDecode, StrictEncode, StrictReader, StrictWriter, TypedRead,
+    WriteRaw,
+};
+use strict_types::value::StrictNum;
+use strict_types::StrictVal;
+use ultrasonic::{
+    AuthToken, CallError, CallId, CellAddr, Codex, Consensus, ContractId, Identity, Issue, Operation, Opid,
+    StateCell, StateValue, VerifiedOperation,
 };
-use ultrasonic::{AuthToken, CallError, CellAddr, ContractId, Identity, Issue, Operation, Opid, VerifiedOperation};
 
-use crate::deed::{CallParams, DeedBuilder};
-use crate::{Articles, EffectiveState, IssueError, ProcessedState, Stock, Transition};
+use crate::deed::{BatchBuilder, CallParams, DeedBuilder};
+use crate::{Articles, EffectiveState, IssueError, ProcessedState, RawState, Stock, Transition};
 
 pub const DEEDS_VERSION: u16 = 0;
 
@@ -47,6 +60,71 @@ pub const DEEDS_VERSION: u16 = 0;
 #[derive(Clone, Debug)]
 pub struct Ledger<S: Stock>(S, /** Cached value */ ContractId);
 
+/// Decoded outputs of an operation located at some [`CellAddr`], as returned by
+/// [`Ledger::output_at`].
+///
+/// Owned and global outputs of an operation are indexed independently, thus the same address may
+/// refer to both of them.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct OutputView {
+    pub addr: CellAddr,
+    /// Owned (destructible) output at the address, if it is present and can be decoded by the
+    /// default contract API.
+    pub owned: Option<(StateName, DataCell)>,
+    /// Global (immutable) output at the address, if it is present and can be decoded by the
+    /// default contract API.
+    pub global: Option<(StateName, StateAtom)>,
+}
+
+/// Operations consuming an output of some operation, as returned by [`Ledger::output_consumers`].
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct OutputConsumers {
+    /// Operations reading the global (immutable) output, if it is present.
+    pub readers: BTreeSet<Opid>,
+    /// Operation spending the owned (destructible) output, if it is present and was spent.
+    pub spender: Option<Opid>,
+}
+
+/// Operation from the contract history with its state decoded by the default contract API, as
+/// returned by [`Ledger::activity_log`].
+///
+/// State which can't be decoded by the default API is not included.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ActivityEntry {
+    pub opid: Opid,
+    /// Name of the method called by the operation, if it is known to the contract APIs (see
+    /// [`Ledger::operation_method`]).
+    pub method: Option<MethodName>,
+    /// Global (immutable) state created by the operation.
+    pub global: Vec<(CellAddr, StateName, StateAtom)>,
+    /// Owned (destructible) state created by the operation.
+    pub owned: Vec<(CellAddr, StateName, DataCell)>,
+    /// Owned (destructible) state consumed by the operation inputs.
+    pub consumed: Vec<(CellAddr, StateName, DataCell)>,
+}
+
+/// Progress of accepting a contract history transferred in chunks (see [`Ledger::accept_chunk`]).
+///
+/// Keeps the indexes of already accepted chunks and the operations which can't be applied until
+/// the chunks with their ancestors arrive.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct ChunkBuffer {
+    chunks: BTreeSet<u32>,
+    orphans: Vec<Operation>,
+}
+
+impl ChunkBuffer {
+    /// Indexes of the accepted chunks.
+    pub fn accepted_chunks(&self) -> &BTreeSet<u32> { &self.chunks }
+
+    /// Number of operations waiting for their ancestors to arrive.
+    pub fn orphan_count(&self) -> usize { self.orphans.len() }
+
+    /// Detects whether all accepted operations were applied to the ledger, i.e. there are no
+    /// operations waiting for their ancestors.
+    pub fn is_complete(&self) -> bool { self.orphans.is_empty() }
+}
+
 impl<S: Stock> Ledger<S> {
     /// Instantiates a new contract from the provided articles, creating its persistence with the
     /// provided configuration.
@@ -70,6 +148,35 @@ impl<S: Stock> Ledger<S> {
         Ok(Self(stock, contract_id))
     }
 
+    /// Issues a new testnet contract and instantiates it, creating its persistence with the
+    /// provided configuration.
+    ///
+    /// This combines construction of an [`Issuer`] from the `codex` and `semantics`, starting a
+    /// testnet issue using the genesis `method`, and instantiating the ledger from the articles
+    /// produced by the `build` closure, which must add the genesis state and finish the issue.
+    ///
+    /// # Panics
+    ///
+    /// This call must not panic, and instead must return an error.
+    ///
+    /// # Blocking I/O
+    ///
+    /// This call MAY perform any I/O operations.
+    pub fn issue_testnet(
+        codex: Codex,
+        semantics: Semantics,
+        method: impl Into<MethodName>,
+        consensus: Consensus,
+        conf: S::Conf,
+        build: impl FnOnce(IssueBuilder) -> Articles,
+    ) -> Result<Self, MultiError<IssueError, S::Error>> {
+        let issuer = Issuer::new(codex, semantics)
+            .map_err(IssueError::Semantics)
+            .map_err(MultiError::A)?;
+        let articles = build(issuer.start_issue_testnet(method, consensus));
+        Self::new(articles, conf)
+    }
+
     /// Loads a contract using the provided configuration for persistence.
     ///
     /// # Panics
@@ -86,6 +193,24 @@ impl<S: Stock> Ledger<S> {
         })
     }
 
+    /// Restores a contract from a snapshot created with [`Self::snapshot`].
+    ///
+    /// See [`Stock::restore`] for the details.
+    pub fn restore(
+        reader: &mut StrictReader<impl ReadRaw>,
+        conf: S::Conf,
+    ) -> Result<Self, MultiError<AcceptError, S::Error>> {
+        S::restore(reader, conf).map(|stock| {
+            let contract_id = stock.articles().contract_id();
+            Self(stock, contract_id)
+        })
+    }
+
+    /// Writes a complete snapshot of the contract, which can be used for creating a backup.
+    ///
+    /// See [`Stock::snapshot`] for the details.
+    pub fn snapshot(&self, writer: StrictWriter<impl WriteRaw>) -> io::Result<()> { self.0.snapshot(writer) }
+
     pub fn config(&self) -> S::Conf { self.0.config() }
 
     pub fn stock(&self) -> &S { &self.0 }
@@ -108,6 +233,54 @@ impl<S: Stock> Ledger<S> {
     #[inline]
     pub fn articles(&self) -> &Articles { self.0.articles() }
 
+    /// Provides AluVM libraries used by the contract codex verifiers.
+    ///
+    /// # Blocking I/O
+    ///
+    /// This call MUST NOT perform any I/O operations and MUST BE a non-blocking.
+    #[inline]
+    pub fn codex_libs(&self) -> impl Iterator<Item = &Lib> { self.0.articles().codex_libs() }
+
+    /// Provides the contract codex verifiers, mapping call ids to the AluVM entry points of the
+    /// verification scripts.
+    ///
+    /// # Blocking I/O
+    ///
+    /// This call MUST NOT perform any I/O operations and MUST BE a non-blocking.
+    #[inline]
+    pub fn codex_verifiers(&self) -> &TinyOrdMap<CallId, LibSite> { &self.0.articles().codex().verifiers }
+
+    /// Detects whether the contract is immutable, i.e., its codex doesn't define any verifiers
+    /// except the one used by the genesis. Such contracts (like data registries) can't have any
+    /// operations after the genesis, and their state is fully defined by the issue.
+    ///
+    /// # Blocking I/O
+    ///
+    /// This call MUST NOT perform any I/O operations and MUST BE a non-blocking.
+    pub fn is_immutable(&self) -> bool {
+        let genesis_call = self.articles().genesis().call_id;
+        self.codex_verifiers()
+            .keys()
+            .all(|call_id| *call_id == genesis_call)
+    }
+
+    /// Resolves the name of the contract method which operation calls, i.e. the method whose
+    /// verifier will be invoked for the operation.
+    ///
+    /// The method is looked up in the default API first, followed by the custom APIs. Returns
+    /// `None` if none of the APIs defines a method for the operation call id.
+    ///
+    /// # Blocking I/O
+    ///
+    /// This call MUST NOT perform any I/O operations and MUST BE a non-blocking.
+    pub fn operation_method(&self, op: &Operation) -> Option<MethodName> {
+        self.0
+            .articles()
+            .apis()
+            .find_map(|api| api.method(op.call_id))
+            .cloned()
+    }
+
     /// Provides contract [`EffectiveState`].
     ///
     /// # Blocking I/O
@@ -116,9 +289,138 @@ impl<S: Stock> Ledger<S> {
     #[inline]
     pub fn state(&self) -> &EffectiveState { self.0.state() }
 
+    /// Computes a hypothetical contract [`EffectiveState`] which would result from applying the
+    /// provided pending operations on top of the current state.
+    ///
+    /// Operations are applied in the order they are given; neither the ledger state nor the stash
+    /// are modified.
+    ///
+    /// # Nota bene
+    ///
+    /// If some of the pending operations depend on each other, each of them must be verified
+    /// against the state previewed with all its preceding operations.
+    ///
+    /// # Blocking I/O
+    ///
+    /// This call MUST NOT perform any I/O operations and MUST BE a non-blocking.
+    pub fn preview_state(&self, ops: &[VerifiedOperation]) -> EffectiveState {
+        let semantics = self.0.articles().semantics();
+        let mut state = self.0.state().clone();
+        for op in ops {
+            // We do not need state transitions, since the state is not persisted.
+            let _ = state.apply(op.clone(), semantics);
+        }
+        state.recompute(semantics);
+        state
+    }
+
+    /// Reconstructs the contract state at some height of the contract history.
+    ///
+    /// The height is the number of valid operations applied on top of genesis, taken in the
+    /// canonical order (topological, with ties broken by operation ids); height zero corresponds
+    /// to the state defined by genesis. The state is reconstructed by rolling back the operations
+    /// following the height from the current state in the reverse canonical order, which ensures
+    /// that no operation is rolled back before its descendants.
+    ///
+    /// Returns `None` if the height exceeds the number of valid operations.
+    ///
+    /// # Blocking I/O
+    ///
+    /// This call MAY BE blocking.
+    pub fn state_at(&self, height: usize) -> Option<EffectiveState> {
+        let genesis_opid = self.articles().genesis_opid();
+        let opids = self
+            .0
+            .trace()
+            .map(|(opid, _)| opid)
+            .filter(|opid| *opid != genesis_opid && self.0.is_valid(*opid))
+            .collect::<BTreeSet<_>>();
+        if height > opids.len() {
+            return None;
+        }
+        let order = self.canonical_order(opids);
+        let semantics = self.articles().semantics();
+        let mut state = self.state().clone();
+        for opid in order[height..].iter().rev() {
+            state.rollback(self.0.transition(*opid), semantics);
+        }
+        state.recompute(semantics);
+        Some(state)
+    }
+
+    /// Computes the change of a numeric aggregated state of the default API between two heights of
+    /// the contract history (see [`Self::state_at`]).
+    ///
+    /// The difference is positive if the state has grown from `from_height` to `to_height`, and
+    /// negative otherwise (in which case it is returned as a signed integer).
+    ///
+    /// Returns `None` if any of the heights exceeds the number of valid operations, or if the
+    /// aggregated state is unknown or not an integer at any of the heights.
+    ///
+    /// # Blocking I/O
+    ///
+    /// This call MAY BE blocking.
+    pub fn aggregated_delta(
+        &self,
+        name: impl Into<StateName>,
+        from_height: usize,
+        to_height: usize,
+    ) -> Option<StrictVal> {
+        let name = name.into();
+        let read = |height: usize| -> Option<i128> {
+            match self.state_at(height)?.main.aggregated.get(&name)? {
+                StrictVal::Number(StrictNum::Uint(val)) => Some(*val as i128),
+                StrictVal::Number(StrictNum::Int(val)) => Some(*val as i128),
+                _ => None,
+            }
+        };
+        let delta = read(to_height)? - read(from_height)?;
+        match u64::try_from(delta) {
+            Ok(delta) => Some(StrictVal::num(delta)),
+            Err(_) => i64::try_from(delta).ok().map(StrictVal::num),
+        }
+    }
+
+    /// Returns a human-friendly ticker (symbol) of the contract, if the contract defines one.
+    ///
+    /// This is a best-effort convenience relying on a convention: the ticker is taken from the
+    /// string value of a computed state, or (if there is no such computed state) of the first
+    /// global state cell of the default API named `ticker` or `symbol`. Contracts not following
+    /// this convention return `None`.
+    ///
+    /// # Blocking I/O
+    ///
+    /// This call MUST NOT perform any I/O operations and MUST BE a non-blocking.
+    pub fn ticker(&self) -> Option<String> {
+        let state = &self.0.state().main;
+        ["ticker", "symbol"].into_iter().find_map(|name| {
+            let name = StateName::from(name);
+            let val = match state.aggregated.get(&name) {
+                Some(val) => val,
+                None => &state.global.get(&name)?.values().next()?.verified,
+            };
+            match val.skip_wrapper() {
+                StrictVal::String(s) => Some(s.clone()),
+                _ => None,
+            }
+        })
+    }
+
     /// Detects whether an operation with a given `opid` participates in the current state.
     pub fn is_valid(&self, opid: Opid) -> bool { self.0.is_valid(opid) }
 
+    /// Returns the reason recorded when an operation with a given `opid` was rolled back with
+    /// [`Self::rollback_with_reason`].
+    ///
+    /// Returns `None` if the operation is valid, was rolled back without a reason, or the stock
+    /// doesn't keep track of invalidation reasons.
+    pub fn invalidation_reason(&self, opid: Opid) -> Option<TinyString> {
+        if self.0.is_valid(opid) {
+            return None;
+        }
+        self.0.invalidation_reason(opid)
+    }
+
     /// Detects whether an operation with a given `opid` is known to the contract.
     ///
     /// # Nota bene
@@ -190,6 +492,29 @@ impl<S: Stock> Ledger<S> {
     #[inline]
     pub fn operations(&self) -> impl Iterator<Item = (Opid, Operation)> + use<'_, S> { self.0.operations() }
 
+    /// Returns an iterator over all operations known to the contract which do not participate in
+    /// the current contract state (i.e., were rolled back, but are kept in the contract stash).
+    ///
+    /// # Nota bene
+    ///
+    /// Does not include genesis operation, which is always valid.
+    ///
+    /// Operations are excluded from the history due to rollbacks (see [`Ledger::rollback`]) and
+    /// may be re-included later with forwards (see [`Ledger::forward`]).
+    ///
+    /// # Panics
+    ///
+    /// The method MUST NOT panic
+    ///
+    /// # Blocking I/O
+    ///
+    /// The iterator provided in return may be a blocking iterator.
+    pub fn invalid_operations(&self) -> impl Iterator<Item = (Opid, Operation)> + use<'_, S> {
+        self.0
+            .operations()
+            .filter(|(opid, _)| !self.0.is_valid(*opid))
+    }
+
     /// Returns an iterator over all state transitions known to the contract (i.e., the complete
     /// contract trace).
     ///
@@ -214,6 +539,259 @@ impl<S: Stock> Ledger<S> {
     #[inline]
     pub fn trace(&self) -> impl Iterator<Item = (Opid, Transition)> + use<'_, S> { self.0.trace() }
 
+    /// Resolves outputs of an operation located at the given cell address, decoding them using the
+    /// default contract API.
+    ///
+    /// Returns `None` if the operation is not known to the contract, or if the address position is
+    /// out of range for both its owned and global outputs.
+    ///
+    /// # Nota bene
+    ///
+    /// Works for genesis outputs as well.
+    ///
+    /// See [`OutputView`] for the details on the outputs which can't be decoded.
+    ///
+    /// # Blocking I/O
+    ///
+    /// This call MAY BE blocking.
+    pub fn output_at(&self, addr: CellAddr) -> Option<OutputView> {
+        let op = if addr.opid == self.articles().genesis_opid() {
+            self.articles().genesis().to_operation(self.1)
+        } else if self.0.has_operation(addr.opid) {
+            self.0.operation(addr.opid)
+        } else {
+            return None;
+        };
+
+        let pos = addr.pos as usize;
+        if pos >= op.destructible_out.len() && pos >= op.immutable_out.len() {
+            return None;
+        }
+
+        let api = self.articles().default_api();
+        let libs = &self.articles().semantics().api_libs;
+        let types = self.articles().types();
+        let owned = op.destructible_out.get(pos).and_then(|cell| {
+            let (name, data) = api.convert_owned(cell.data, libs, types).ok().flatten()?;
+            Some((name, DataCell { data, auth: cell.auth, lock: cell.lock }))
+        });
+        let global = op
+            .immutable_out
+            .get(pos)
+            .and_then(|data| api.convert_global(data, libs, types).ok().flatten());
+
+        Some(OutputView { addr, owned, global })
+    }
+
+    /// Lists operations consuming each of the outputs of an operation: reading its global outputs
+    /// and spending its owned outputs.
+    ///
+    /// Outputs are listed in the order of their positions; since owned and global outputs are
+    /// indexed independently, the same address may refer to both of them.
+    ///
+    /// # Nota bene
+    ///
+    /// Works for genesis as well. The consuming operations are not required to be valid.
+    ///
+    /// # Panics
+    ///
+    /// If the operation is not known to the ledger.
+    ///
+    /// # Blocking I/O
+    ///
+    /// This call MAY BE blocking.
+    pub fn output_consumers(&self, opid: Opid) -> Vec<(CellAddr, OutputConsumers)> {
+        let (destructible, immutable) = if opid == self.articles().genesis_opid() {
+            let genesis = self.articles().genesis();
+            (genesis.destructible_out.len(), genesis.immutable_out.len())
+        } else {
+            let op = self.0.operation(opid);
+            (op.destructible_out.len(), op.immutable_out.len())
+        };
+
+        (0..destructible.max(immutable) as u16)
+            .map(|pos| {
+                let addr = CellAddr::new(opid, pos);
+                let readers = if (pos as usize) < immutable { self.0.read_by(addr).collect() } else { none!() };
+                let spender = if (pos as usize) < destructible { self.0.spent_by(addr) } else { None };
+                (addr, OutputConsumers { readers, spender })
+            })
+            .collect()
+    }
+
+    /// Iterates over the contract history, starting with genesis and followed by all valid
+    /// operations in the order they were applied to the contract state (see [`Self::trace`]),
+    /// providing for each of them the called method and the decoded state it has created and
+    /// consumed.
+    ///
+    /// # Blocking I/O
+    ///
+    /// The iterator provided in return may be a blocking iterator.
+    pub fn activity_log(&self) -> impl Iterator<Item = ActivityEntry> + use<'_, S> {
+        let genesis_opid = self.articles().genesis_opid();
+        let genesis = self.articles().genesis().to_operation(self.1);
+        let ops = self
+            .0
+            .trace()
+            .filter(move |(opid, _)| *opid != genesis_opid && self.0.is_valid(*opid))
+            .map(|(opid, transition)| (self.0.operation(opid), transition.destroyed));
+        iter::once((genesis, none!()))
+            .chain(ops)
+            .map(|(op, destroyed)| self.activity_entry(op, destroyed))
+    }
+
+    fn activity_entry(&self, op: Operation, destroyed: SmallOrdMap<CellAddr, StateCell>) -> ActivityEntry {
+        let opid = op.opid();
+        let api = self.articles().default_api();
+        let libs = &self.articles().semantics().api_libs;
+        let types = self.articles().types();
+        let decode_owned = |addr: CellAddr, cell: &StateCell| {
+            let (name, data) = api.convert_owned(cell.data, libs, types).ok().flatten()?;
+            Some((addr, name, DataCell { data, auth: cell.auth, lock: cell.lock }))
+        };
+
+        let global = op
+            .immutable_out
+            .iter()
+            .enumerate()
+            .filter_map(|(pos, data)| {
+                let (name, atom) = api.convert_global(data, libs, types).ok().flatten()?;
+                Some((CellAddr::new(opid, pos as u16), name, atom))
+            })
+            .collect();
+        let owned = op
+            .destructible_out
+            .iter()
+            .enumerate()
+            .filter_map(|(pos, cell)| decode_owned(CellAddr::new(opid, pos as u16), cell))
+            .collect();
+        let consumed = destroyed
+            .iter()
+            .filter_map(|(addr, cell)| decode_owned(*addr, cell))
+            .collect();
+
+        let method = self.operation_method(&op);
+        ActivityEntry { opid, method, global, owned, consumed }
+    }
+
+    /// Detects whether an operation is stateless, i.e., doesn't produce any new global or owned
+    /// state; such operations may only read global state or consume owned state.
+    ///
+    /// Used by indexers to filter out query-only calls.
+    ///
+    /// # Panics
+    ///
+    /// If the operation is not known to the ledger.
+    ///
+    /// # Blocking I/O
+    ///
+    /// This call MAY BE blocking.
+    pub fn is_stateless(&self, opid: Opid) -> bool {
+        if opid == self.articles().genesis_opid() {
+            let genesis = self.articles().genesis();
+            return genesis.destructible_out.is_empty() && genesis.immutable_out.is_empty();
+        }
+        let op = self.0.operation(opid);
+        op.destructible_out.is_empty() && op.immutable_out.is_empty()
+    }
+
+    /// Decodes a witness provided by an operation for one of its inputs, using the witness type of
+    /// the default API for the state consumed by the input.
+    ///
+    /// Returns `None` if the operation is not known, has no input with the given index, the input
+    /// has no witness, or the witness can't be decoded.
+    ///
+    /// # Blocking I/O
+    ///
+    /// This call MAY BE blocking.
+    pub fn input_witness(&self, opid: Opid, input_index: u16) -> Option<StrictVal> {
+        // Genesis has no inputs
+        if !self.0.has_operation(opid) {
+            return None;
+        }
+        let op = self.0.operation(opid);
+        let input = op.destructible_in.get(input_index as usize)?;
+        let (name, _) = self.output_at(input.addr)?.owned?;
+        let articles = self.articles();
+        articles
+            .default_api()
+            .convert_witness(name, input.witness, &articles.semantics().api_libs, articles.types())
+            .ok()
+            .flatten()
+    }
+
+    /// Computes the total amount of a fungible owned state with a given name burned by the contract
+    /// operations, i.e., the sum of the differences between the inputs and the outputs of the
+    /// state over all valid operations where the inputs exceed the outputs.
+    ///
+    /// Returns `None` if the state is not known to the default contract API, is not fungible, or
+    /// can't be counted by its state calculator.
+    ///
+    /// # Blocking I/O
+    ///
+    /// This call MAY BE blocking.
+    pub fn total_burned(&self, name: impl Into<StateName>) -> Option<u64> {
+        let name = name.into();
+        let articles = self.articles();
+        let api = articles.default_api();
+        let libs = &articles.semantics().api_libs;
+        let owned = api.owned.get(&name)?;
+        if owned.arithmetics != StateArithm::Fungible {
+            return None;
+        }
+
+        let sum = |values: &mut dyn Iterator<Item = StateValue>| -> Option<u64> {
+            let mut calc = owned.arithmetics.calculator();
+            for value in values {
+                // State which can't be converted is not a part of the contract state
+                if let Ok(Some((state_name, val))) = api.convert_owned(value, libs, articles.types()) {
+                    if state_name == name {
+                        calc.accumulate(&val).ok()?;
+                    }
+                }
+            }
+            match calc {
+                StateCalc::Fungible(StrictVal::Number(StrictNum::Uint(val))) => Some(val),
+                _ => None,
+            }
+        };
+
+        let mut total = 0u64;
+        for (opid, op) in self.0.operations() {
+            if !self.0.is_valid(opid) {
+                continue;
+            }
+            let transition = self.0.transition(opid);
+            let inputs = sum(&mut transition.destroyed.values().map(|cell| cell.data))?;
+            let outputs = sum(&mut op.destructible_out.iter().map(|cell| cell.data))?;
+            total = total.checked_add(inputs.saturating_sub(outputs))?;
+        }
+        Some(total)
+    }
+
+    /// Detects global state memory cells which are read by both of the operations.
+    ///
+    /// Reading global state doesn't consume it, so such operations do not conflict with each
+    /// other; however, invalidation of the shared cells during a reorg affects both of them.
+    ///
+    /// # Panics
+    ///
+    /// If any of the operations is not known to the ledger.
+    ///
+    /// # Blocking I/O
+    ///
+    /// This call MAY BE blocking.
+    pub fn read_conflicts(&self, a: Opid, b: Opid) -> BTreeSet<CellAddr> {
+        let reads = |opid: Opid| -> BTreeSet<CellAddr> {
+            // Genesis can't read any state
+            if opid == self.articles().genesis_opid() {
+                return none!();
+            }
+            self.0.operation(opid).immutable_in.into_iter().collect()
+        };
+        reads(a).intersection(&reads(b)).copied().collect()
+    }
+
     #[inline]
     pub fn read_by(&self, addr: CellAddr) -> impl Iterator<Item = Opid> + use<'_, S> { self.0.read_by(addr) }
     #[inline]
@@ -277,6 +855,127 @@ impl<S: Stock> Ledger<S> {
         chain.into_iter()
     }
 
+    /// Computes the "confirmed depth" of an operation: the number of valid operations which were
+    /// applied on top of it, i.e. its valid descendants, not counting the operation itself.
+    ///
+    /// Tips of the operation DAG (see [`Self::dag_tips`]) have zero depth. For genesis, returns the
+    /// number of all valid operations.
+    ///
+    /// # Panics
+    ///
+    /// If the operation with the `opid` is not known to the contract.
+    ///
+    /// # Blocking I/O
+    ///
+    /// This call MAY BE blocking.
+    pub fn depth(&self, opid: Opid) -> usize {
+        if opid == self.articles().genesis_opid() {
+            return self
+                .0
+                .operations()
+                .filter(|(opid, _)| self.0.is_valid(*opid))
+                .count();
+        }
+        self.descendants([opid])
+            .filter(|id| *id != opid && self.0.is_valid(*id))
+            .count()
+    }
+
+    /// Computes the tips of the operation DAG: valid operations (including genesis) none of whose
+    /// outputs are spent or read by any valid operation.
+    ///
+    /// The tips form a minimal set of terminal operations, whose ancestors cover the whole valid
+    /// contract history; thus, they can be used for a full export or sync.
+    ///
+    /// # Blocking I/O
+    ///
+    /// This call MAY BE blocking.
+    pub fn dag_tips(&self) -> BTreeSet<Opid> {
+        let genesis_opid = self.articles().genesis_opid();
+        let genesis = self.articles().genesis().to_operation(self.1);
+        let ops = self
+            .0
+            .operations()
+            .filter(|(opid, _)| self.0.is_valid(*opid));
+        let mut tips = BTreeSet::new();
+        for (opid, op) in [(genesis_opid, genesis)].into_iter().chain(ops) {
+            let read = (0..op.immutable_out.len_u16())
+                .any(|no| self.0.read_by(CellAddr::new(opid, no)).any(|id| self.0.is_valid(id)));
+            let spent = (0..op.destructible_out.len_u16()).any(|no| {
+                self.0
+                    .spent_by(CellAddr::new(opid, no))
+                    .is_some_and(|id| self.0.is_valid(id))
+            });
+            if !read && !spent {
+                tips.insert(opid);
+            }
+        }
+        tips
+    }
+
+    /// Computes a set of operations (including genesis) which define the current contract state,
+    /// i.e. have at least one unspent owned output or a global state output.
+    ///
+    /// This is the minimal set of operations a new peer needs to know the current contract state.
+    /// Unlike [`Self::dag_tips`], the set includes operations whose global state is read by
+    /// other operations, or whose outputs are only partially spent.
+    pub fn active_operations(&self) -> BTreeSet<Opid> {
+        let raw = &self.state().raw;
+        raw.global
+            .keys()
+            .chain(raw.owned.keys())
+            .map(|addr| addr.opid)
+            .collect()
+    }
+
+    /// Computes a set of authority tokens for owned state which is affected by the provided
+    /// operations and all their descendants, i.e. created or consumed by them.
+    ///
+    /// Used by wallets to detect which of their balances must be refreshed after a rollback or a
+    /// forward of the operations.
+    ///
+    /// # Nota bene
+    ///
+    /// The genesis operation must not be provided in `opids`.
+    ///
+    /// # Blocking I/O
+    ///
+    /// This call MAY BE blocking.
+    pub fn affected_auths(&self, opids: impl IntoIterator<Item = Opid>) -> BTreeSet<AuthToken> {
+        let mut auths = BTreeSet::new();
+        for opid in self.descendants(opids) {
+            let op = self.0.operation(opid);
+            auths.extend(op.destructible_out.iter().map(|cell| cell.auth));
+            let st = self.0.transition(opid);
+            auths.extend(st.destroyed.values().map(|cell| cell.auth));
+        }
+        auths
+    }
+
+    /// Collects authority tokens of all owned state ever defined by the valid contract operations
+    /// (including genesis), whether the state is already spent or not.
+    ///
+    /// Used by wallets for recovery, when all historically used authority tokens must be scanned;
+    /// unlike [`EffectiveState::addr`], the set is not limited to the current contract state.
+    ///
+    /// # Blocking I/O
+    ///
+    /// This call MAY BE blocking.
+    pub fn all_auths(&self) -> BTreeSet<AuthToken> {
+        let genesis = self.articles().genesis();
+        let mut auths = genesis
+            .destructible_out
+            .iter()
+            .map(|cell| cell.auth)
+            .collect::<BTreeSet<_>>();
+        for (opid, op) in self.0.operations() {
+            if self.0.is_valid(opid) {
+                auths.extend(op.destructible_out.iter().map(|cell| cell.auth));
+            }
+        }
+        auths
+    }
+
     /// Exports contract with all known operations.
     pub fn export_all(&self, writer: StrictWriter<impl WriteRaw>) -> io::Result<()> {
         self.export_internal(self.0.operation_count() as u32, writer, |_| true, |_, _, w| Ok(w))
@@ -301,6 +1000,22 @@ impl<S: Stock> Ledger<S> {
         self.export_aux(terminals, writer, |_, _, w| Ok(w))
     }
 
+    /// Export a part of a contract history, like [`Self::export`] does, computing simultaneously
+    /// a hash of the exported data.
+    ///
+    /// The returned hash is a [`StrictHash`]-tagged SHA256 digest of the exact bytes written to
+    /// the `writer`, which allows a distributor to publish a verifiable hash of the export without
+    /// buffering it.
+    pub fn export_hashed(
+        &self,
+        terminals: impl IntoIterator<Item = impl Borrow<AuthToken>>,
+        writer: impl io::Write,
+    ) -> io::Result<StrictHash> {
+        let mut writer = HashingWriter { inner: writer, hasher: Sha256::from_tag(StrictHash::TAG) };
+        self.export(terminals, StrictWriter::with(StreamWriter::new::<{ usize::MAX }>(&mut writer)))?;
+        Ok(StrictHash::from(writer.hasher))
+    }
+
     /// Exports contract and operations to a stream, extending operation data with some auxiliary
     /// information returned by `aux`.
     pub fn export_aux<W: WriteRaw>(
@@ -309,6 +1024,117 @@ impl<S: Stock> Ledger<S> {
         writer: StrictWriter<W>,
         aux: impl FnMut(Opid, &Operation, StrictWriter<W>) -> io::Result<StrictWriter<W>>,
     ) -> io::Result<()> {
+        self.export_graph(terminals, true, writer, aux)
+    }
+
+    /// Export a minimal part of a contract history required to validate the terminals: the
+    /// graph between the terminals and genesis, including operations defining the global state
+    /// read by the operations in the graph.
+    ///
+    /// Unlike [`Self::export`], does not include all operations defining published global state.
+    ///
+    /// # Nota bene
+    ///
+    /// The recipient of a minimal export will lack the published global state which is not a part
+    /// of the terminals' history.
+    pub fn export_minimal(
+        &self,
+        terminals: impl IntoIterator<Item = impl Borrow<AuthToken>>,
+        writer: StrictWriter<impl WriteRaw>,
+    ) -> io::Result<()> {
+        self.export_graph(terminals, false, writer, |_, _, w| Ok(w))
+    }
+
+    /// Export a part of a contract history, like [`Self::export`] does, streaming the operations in
+    /// a canonical topological order instead of the order in which they were added to the stash.
+    ///
+    /// In the canonical order each operation follows all the operations it depends on; operations
+    /// which don't depend on each other are ordered by their ids. Thus, the export depends only on
+    /// the exported history and not on the way it was assembled, and ledgers with identical valid
+    /// histories produce byte-identical exports, which suits content-addressed distribution.
+    pub fn export_canonical(
+        &self,
+        terminals: impl IntoIterator<Item = impl Borrow<AuthToken>>,
+        writer: StrictWriter<impl WriteRaw>,
+    ) -> io::Result<()> {
+        let opids = self.canonical_order(self.export_opids(terminals, true));
+        let mut writer = self.export_header(opids.len() as u32, writer, |_, _, w| Ok(w))?;
+        for opid in opids {
+            writer = self.0.operation(opid).strict_encode(writer)?;
+        }
+        Ok(())
+    }
+
+    /// Orders operations topologically, breaking ties by the operation ids.
+    fn canonical_order(&self, opids: BTreeSet<Opid>) -> Vec<Opid> {
+        let mut parents = BTreeMap::<Opid, usize>::new();
+        let mut children = BTreeMap::<Opid, BTreeSet<Opid>>::new();
+        for opid in &opids {
+            let op = self.0.operation(*opid);
+            let deps = op
+                .destructible_in
+                .iter()
+                .map(|input| input.addr.opid)
+                .chain(op.immutable_in.iter().map(|addr| addr.opid))
+                .filter(|dep| opids.contains(dep))
+                .collect::<BTreeSet<_>>();
+            parents.insert(*opid, deps.len());
+            for dep in deps {
+                children.entry(dep).or_default().insert(*opid);
+            }
+        }
+
+        let mut ready = parents
+            .iter()
+            .filter(|(_, count)| **count == 0)
+            .map(|(opid, _)| *opid)
+            .collect::<BTreeSet<_>>();
+        let mut order = Vec::with_capacity(opids.len());
+        while let Some(opid) = ready.pop_first() {
+            order.push(opid);
+            for child in children.remove(&opid).unwrap_or_default() {
+                let count = parents.get_mut(&child).expect("child is known");
+                *count -= 1;
+                if *count == 0 {
+                    ready.insert(child);
+                }
+            }
+        }
+        debug_assert_eq!(order.len(), opids.len(), "cycle in the operation graph");
+        order
+    }
+
+    fn export_graph<W: WriteRaw>(
+        &self,
+        terminals: impl IntoIterator<Item = impl Borrow<AuthToken>>,
+        include_published: bool,
+        writer: StrictWriter<W>,
+        aux: impl FnMut(Opid, &Operation, StrictWriter<W>) -> io::Result<StrictWriter<W>>,
+    ) -> io::Result<()> {
+        let mut opids = self.export_opids(terminals, include_published);
+
+        self.export_internal(opids.len() as u32, writer, |opid| opids.remove(opid), aux)?;
+
+        debug_assert!(
+            opids.is_empty(),
+            "Missing operations: {}",
+            opids
+                .into_iter()
+                .map(|opid| opid.to_string())
+                .collect::<Vec<_>>()
+                .join("\n -")
+        );
+
+        Ok(())
+    }
+
+    /// Collects the ids of operations (not including genesis) which are exported for the given
+    /// terminals.
+    fn export_opids(
+        &self,
+        terminals: impl IntoIterator<Item = impl Borrow<AuthToken>>,
+        include_published: bool,
+    ) -> BTreeSet<Opid> {
         let mut queue = terminals
             .into_iter()
             .map(|terminal| self.0.state().addr(*terminal.borrow()).opid)
@@ -319,7 +1145,12 @@ impl<S: Stock> Ledger<S> {
         let mut opids = queue.clone();
         while let Some(opid) = queue.pop_first() {
             let st = self.0.transition(opid);
-            for prev in st.destroyed.into_keys().map(|a| a.opid) {
+            let mut parents = st.destroyed.into_keys().map(|a| a.opid).collect::<Vec<_>>();
+            // Without published state we have to include the operations defining the read state
+            if !include_published {
+                parents.extend(self.0.operation(opid).immutable_in.iter().map(|a| a.opid));
+            }
+            for prev in parents {
                 if !opids.contains(&prev) && prev != genesis_opid {
                     opids.insert(prev);
                     queue.insert(prev);
@@ -339,28 +1170,38 @@ impl<S: Stock> Ledger<S> {
                 }
             }
         };
-        collect(&articles.semantics().default, &state.main);
-        for (api_name, api) in &articles.semantics().custom {
-            let Some(state) = state.aux.get(api_name) else {
-                continue;
-            };
-            collect(api, state);
+        if include_published {
+            collect(&articles.semantics().default, &state.main);
+            for (api_name, api) in &articles.semantics().custom {
+                let Some(state) = state.aux.get(api_name) else {
+                    continue;
+                };
+                collect(api, state);
+            }
         }
         opids.remove(&genesis_opid);
+        opids
+    }
 
-        self.export_internal(opids.len() as u32, writer, |opid| opids.remove(opid), aux)?;
-
-        debug_assert!(
-            opids.is_empty(),
-            "Missing operations: {}",
-            opids
-                .into_iter()
-                .map(|opid| opid.to_string())
-                .collect::<Vec<_>>()
-                .join("\n -")
-        );
-
-        Ok(())
+    /// Exports valid operations received by the local stock after `since`.
+    ///
+    /// # Nota bene
+    ///
+    /// Receive-time is a local, non-consensus information kept by the stock (see
+    /// [`Stock::received_at`]), and it differs between peers. Operations with an unknown
+    /// receive-time are not exported.
+    ///
+    /// The export does not include the history preceding the exported operations, thus it can be
+    /// accepted only by a party which already knows that history.
+    pub fn export_since(&self, since: DateTime<Utc>, writer: StrictWriter<impl WriteRaw>) -> io::Result<()> {
+        let mut opids = self
+            .0
+            .operations()
+            .map(|(opid, _)| opid)
+            .filter(|opid| self.0.is_valid(*opid))
+            .filter(|opid| self.0.received_at(*opid).is_some_and(|time| time > since))
+            .collect::<BTreeSet<_>>();
+        self.export_internal(opids.len() as u32, writer, |opid| opids.remove(opid), |_, _, w| Ok(w))
     }
 
     /// Exports only operations for which `should_include` returns `true`.
@@ -375,6 +1216,26 @@ impl<S: Stock> Ledger<S> {
         mut should_include: impl FnMut(&Opid) -> bool,
         mut aux: impl FnMut(Opid, &Operation, StrictWriter<W>) -> io::Result<StrictWriter<W>>,
     ) -> io::Result<()> {
+        writer = self.export_header(count, writer, &mut aux)?;
+        // Stream operations
+        for (opid, op) in self.0.operations() {
+            if !should_include(&opid) {
+                continue;
+            }
+            writer = op.strict_encode(writer)?;
+            writer = aux(opid, &op, writer)?;
+        }
+        Ok(())
+    }
+
+    /// Writes export data preceding the operations: version, contract id, articles, genesis
+    /// auxiliary data and the number of operations.
+    fn export_header<W: WriteRaw>(
+        &self,
+        count: u32,
+        mut writer: StrictWriter<W>,
+        mut aux: impl FnMut(Opid, &Operation, StrictWriter<W>) -> io::Result<StrictWriter<W>>,
+    ) -> io::Result<StrictWriter<W>> {
         let articles = self.articles();
         let genesis_opid = articles.genesis_opid();
 
@@ -389,27 +1250,116 @@ impl<S: Stock> Ledger<S> {
         writer = articles.strict_encode(writer)?;
         writer = aux(genesis_opid, &articles.genesis().to_operation(contract_id), writer)?;
         // Write no of operations
-        writer = count.strict_encode(writer)?;
-        // Stream operations
-        for (opid, op) in self.0.operations() {
-            if !should_include(&opid) {
-                continue;
-            }
-            writer = op.strict_encode(writer)?;
-            writer = aux(opid, &op, writer)?;
+        count.strict_encode(writer)
+    }
+
+    /// Exports operations as a single chunk of a contract history, which can be transferred
+    /// independently of other chunks and accepted with [`Self::accept_chunk`].
+    ///
+    /// # Chunk framing
+    ///
+    /// Each chunk is self-describing and consists of:
+    /// - version number (a single byte, [`DEEDS_VERSION`]);
+    /// - contract id;
+    /// - chunk index (sequence number), as a 32-bit little-endian integer;
+    /// - number of operations in the chunk, as a 32-bit little-endian integer;
+    /// - strict-encoded operations, in the order they were provided.
+    ///
+    /// Unlike [`Self::export`], chunks do not include contract articles, thus the receiving party
+    /// must already have the contract ledger.
+    ///
+    /// # Panics
+    ///
+    /// If some of the `opids` are not known to the ledger.
+    pub fn export_chunk(
+        &self,
+        opids: &[Opid],
+        chunk_index: u32,
+        mut writer: StrictWriter<impl WriteRaw>,
+    ) -> io::Result<()> {
+        writer = (DEEDS_VERSION as u8).strict_encode(writer)?;
+        writer = self.contract_id().strict_encode(writer)?;
+        writer = chunk_index.strict_encode(writer)?;
+        writer = (opids.len() as u32).strict_encode(writer)?;
+        for opid in opids {
+            writer = self.0.operation(*opid).strict_encode(writer)?;
         }
         Ok(())
     }
 
+    /// Accepts a chunk of a contract history produced by [`Self::export_chunk`], returning its
+    /// index.
+    ///
+    /// Chunks may come in any order, and the same chunk may be received multiple times. The
+    /// `buffer` keeps the progress between the calls: the indexes of the chunks which were already
+    /// accepted (repeated chunks are ignored) and the operations whose ancestors are not known
+    /// yet. These operations are applied once a chunk with their ancestors is accepted; the whole
+    /// history is applied when [`ChunkBuffer::is_complete`] returns `true` after all chunks were
+    /// accepted.
+    pub fn accept_chunk(
+        &mut self,
+        reader: &mut StrictReader<impl ReadRaw>,
+        buffer: &mut ChunkBuffer,
+    ) -> Result<u32, MultiError<AcceptError, S::Error>> {
+        // We need this closure to avoid multiple `map_err`.
+        let (index, count) = (|| -> Result<(u32, u32), AcceptError> {
+            // Check version number
+            let _ = ReservedBytes::<1, { DEEDS_VERSION as u8 }>::strict_decode(reader)?;
+            if ContractId::strict_decode(reader)? != self.contract_id() {
+                return Err(AcceptError::Articles(SemanticError::ContractMismatch));
+            }
+            Ok((u32::strict_decode(reader)?, u32::strict_decode(reader)?))
+        })()
+        .map_err(MultiError::A)?;
+
+        if buffer.chunks.contains(&index) {
+            return Ok(index);
+        }
+        for _ in 0..count {
+            let op = Operation::strict_decode(reader).map_err(|e| MultiError::A(e.into()))?;
+            self.apply_or_keep(op, &mut buffer.orphans)?;
+        }
+        self.commit_transaction();
+        buffer.chunks.insert(index);
+        Ok(index)
+    }
+
     pub fn upgrade_apis(&mut self, new_articles: Articles) -> Result<bool, MultiError<SemanticError, S::Error>> {
         self.0
             .update_articles(|articles| articles.upgrade_apis(new_articles))
     }
 
+    /// Accepts a stream of contract articles and operations, produced by one of the export methods.
+    ///
+    /// # Nota bene
+    ///
+    /// The articles and their signature are validated before the ledger is modified: if the
+    /// `sig_validator` fails, the articles are not upgraded and none of the operations is applied.
+    ///
+    /// Operations may come in any order: an operation whose ancestors are not known yet is kept
+    /// aside until they arrive later in the stream. If some of the ancestors never arrive, the
+    /// method fails with [`AcceptError::OrphanOperation`], or with
+    /// [`AcceptError::RolledBackInput`] if the ancestor is known to the ledger, but was rolled
+    /// back.
     pub fn accept<E>(
         &mut self,
         reader: &mut StrictReader<impl ReadRaw>,
         sig_validator: impl FnOnce(StrictHash, &Identity, &SigBlob) -> Result<(), E>,
+    ) -> Result<(), MultiError<AcceptError, S::Error>> {
+        self.accept_bounded(reader, sig_validator, u32::MAX)
+    }
+
+    /// Accepts a stream of operations, like [`Self::accept`] does, rejecting the stream if it
+    /// declares more than `max_ops` operations (not counting genesis).
+    ///
+    /// The check is performed before the articles are upgraded and any of the operations are read,
+    /// such that a peer can't make the ledger process an arbitrary long stream, and a rejected
+    /// stream leaves the ledger unmodified.
+    pub fn accept_bounded<E>(
+        &mut self,
+        reader: &mut StrictReader<impl ReadRaw>,
+        sig_validator: impl FnOnce(StrictHash, &Identity, &SigBlob) -> Result<(), E>,
+        max_ops: u32,
     ) -> Result<(), MultiError<AcceptError, S::Error>> {
         // We need this closure to avoid multiple `map_err`.
         let count = (|| -> Result<u32, AcceptError> {
@@ -430,19 +1380,25 @@ impl<S: Stock> Ledger<S> {
             let semantics = Semantics::strict_decode(reader)?;
             let sig = Option::<SigBlob>::strict_decode(reader)?;
             let issue = Issue::strict_decode(reader)?;
+            // This must happen before any modification of the ledger
             let articles = Articles::with(semantics, issue, sig, sig_validator)?;
             if articles.contract_id() != contract_id {
                 return Err(AcceptError::Articles(SemanticError::ContractMismatch));
             }
 
+            let count = u32::strict_decode(reader)?;
+            if count > max_ops {
+                return Err(AcceptError::TooManyOperations { count, max: max_ops });
+            }
+
             self.upgrade_apis(articles)
                 .map_err(|e| AcceptError::Persistence(e.to_string()))?;
-
-            let count = u32::strict_decode(reader)?;
             Ok(count)
         })()
         .map_err(MultiError::A)?;
 
+        // Operations whose ancestors are not known yet, waiting for them to arrive
+        let mut orphans = Vec::<Operation>::new();
         // We need to account for genesis, which is not included in the `count`
         for _ in 0..=count {
             let op = match Operation::strict_decode(reader) {
@@ -450,7 +1406,16 @@ impl<S: Stock> Ledger<S> {
                 Err(DecodeError::Io(e)) if e.kind() == io::ErrorKind::UnexpectedEof => break,
                 Err(e) => return Err(MultiError::A(e.into())),
             };
-            self.apply_verify(op, false)?;
+            self.apply_or_keep(op, &mut orphans)?;
+        }
+        if let Some(orphan) = orphans.first() {
+            let addr = self
+                .missing_input(orphan)
+                .expect("orphan operations always have a missing input");
+            if self.0.has_operation(addr.opid) {
+                return Err(MultiError::A(AcceptError::RolledBackInput(orphan.opid(), addr)));
+            }
+            return Err(MultiError::A(AcceptError::OrphanOperation(orphan.opid(), addr)));
         }
         // Here we do not check for the end of the stream,
         // so in the future we can have arbitrary extensions
@@ -459,7 +1424,57 @@ impl<S: Stock> Ledger<S> {
         Ok(())
     }
 
+    /// Applies an operation, or keeps it among the `orphans` if some of its ancestors are not known
+    /// yet.
+    fn apply_or_keep(
+        &mut self,
+        op: Operation,
+        orphans: &mut Vec<Operation>,
+    ) -> Result<(), MultiError<AcceptError, S::Error>> {
+        if self.missing_input(&op).is_some() {
+            orphans.push(op);
+            return Ok(());
+        }
+        self.apply_verify(op, false)?;
+        // Applying an operation may have provided the ancestors for some of the orphans
+        while let Some(pos) = orphans
+            .iter()
+            .position(|orphan| self.missing_input(orphan).is_none())
+        {
+            self.apply_verify(orphans.remove(pos), false)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the first input (either read or spent) of the operation which refers to an operation
+    /// not known to the ledger (or not valid in it).
+    fn missing_input(&self, op: &Operation) -> Option<CellAddr> {
+        op.immutable_in
+            .iter()
+            .copied()
+            .chain(op.destructible_in.iter().map(|input| input.addr))
+            .find(|addr| !self.0.is_valid(addr.opid))
+    }
+
     pub fn rollback(&mut self, opids: impl IntoIterator<Item = Opid>) -> Result<(), S::Error> {
+        self.rollback_internal(opids, None)
+    }
+
+    /// Rolls back operations, like [`Self::rollback`] does, recording the reason for the rollback
+    /// for each of the invalidated operations (see [`Self::invalidation_reason`]).
+    pub fn rollback_with_reason(
+        &mut self,
+        opids: impl IntoIterator<Item = Opid>,
+        reason: TinyString,
+    ) -> Result<(), S::Error> {
+        self.rollback_internal(opids, Some(reason))
+    }
+
+    fn rollback_internal(
+        &mut self,
+        opids: impl IntoIterator<Item = Opid>,
+        reason: Option<TinyString>,
+    ) -> Result<(), S::Error> {
         for opid in self.descendants(opids).rev() {
             let mut transition = self.0.transition(opid);
             // We need to filter out already invalidated inputs
@@ -477,7 +1492,10 @@ impl<S: Stock> Ledger<S> {
             self.0.update_state(|state, articles| {
                 state.rollback(transition, articles.semantics());
             })?;
-            self.0.mark_invalid(opid);
+            match &reason {
+                Some(reason) => self.0.mark_invalid_with_reason(opid, reason.clone()),
+                None => self.0.mark_invalid(opid),
+            }
         }
         self.commit_transaction();
         Ok(())
@@ -505,6 +1523,9 @@ impl<S: Stock> Ledger<S> {
         DeedBuilder { builder, ledger: self }
     }
 
+    /// Starts a batch of deeds which are added to the ledger atomically (see [`BatchBuilder`]).
+    pub fn start_batch(&mut self) -> BatchBuilder<'_, S> { BatchBuilder { deeds: none!(), ledger: self } }
+
     pub fn call(&mut self, params: CallParams) -> Result<Opid, MultiError<AcceptError, S::Error>> {
         let mut builder = self.start_deed(params.core.method);
 
@@ -593,6 +1614,9 @@ impl<S: Stock> Ledger<S> {
     ) -> Result<Transition, S::Error> {
         if !present {
             self.0.add_operation(opid, operation.as_operation());
+            if self.0.received_at(opid).is_none() {
+                self.0.set_received_at(opid, Utc::now());
+            }
         }
 
         let op = operation.as_operation();
@@ -615,6 +1639,41 @@ impl<S: Stock> Ledger<S> {
     pub fn commit_transaction(&mut self) { self.0.commit_transaction(); }
 }
 
+/// Verifies an operation against the provided contract `articles` and the prior contract state,
+/// without requiring a [`Ledger`].
+///
+/// This allows lightweight verifiers to check a third-party operation having just the contract
+/// articles and the part of the raw state which is used by the operation.
+///
+/// # Nota bene
+///
+/// The operation is not applied to the `raw_state`.
+pub fn verify_operation(
+    articles: &Articles,
+    raw_state: &RawState,
+    op: &Operation,
+) -> Result<VerifiedOperation, CallError> {
+    articles
+        .codex()
+        .verify(articles.contract_id(), op.clone(), raw_state, articles)
+}
+
+/// Writer passing all the data to the inner writer and to a hasher.
+struct HashingWriter<W: io::Write> {
+    inner: W,
+    hasher: Sha256,
+}
+
+impl<W: io::Write> io::Write for HashingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let len = self.inner.write(buf)?;
+        self.hasher.input_raw(&buf[..len]);
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> { self.inner.flush() }
+}
+
 #[derive(Debug, Display, Error, From)]
 #[display(inner)]
 pub enum AcceptError {
@@ -635,6 +1694,15 @@ pub enum AcceptError {
 
     Persistence(String),
 
+    #[display("the stream declares {count} operations, while at most {max} are allowed")]
+    TooManyOperations { count: u32, max: u32 },
+
+    #[display("operation {0} references {1}, which is not produced by any of the known operations")]
+    OrphanOperation(Opid, CellAddr),
+
+    #[display("operation {0} references {1}, which is produced by an operation that was rolled back")]
+    RolledBackInput(Opid, CellAddr),
+
     #[cfg(feature = "binfile")]
     #[display("Invalid file format")]
     InvalidFileFormat,
@@ -645,12 +1713,28 @@ mod _fs {
     use std::path::Path;
 
     use binfile::BinFile;
-    use strict_encoding::{StreamReader, StreamWriter};
+    use strict_encoding::StreamReader;
 
     use super::*;
 
     pub const DEEDS_MAGIC_NUMBER: u64 = u64::from_be_bytes(*b"DEEDLDGR");
 
+    /// Computes a checksum of a deeds file without accepting (or even parsing) the deeds it
+    /// contains, allowing to verify the integrity of a downloaded file before importing it.
+    ///
+    /// The checksum is computed over the file content following the binary file header, such that
+    /// it matches the hash returned by [`Ledger::export_hashed`] for the same export.
+    ///
+    /// # Errors
+    ///
+    /// If the file can't be read or doesn't have a valid deeds file header.
+    pub fn peek_deeds_checksum(input: impl AsRef<Path>) -> io::Result<StrictHash> {
+        let mut file = BinFile::<DEEDS_MAGIC_NUMBER, DEEDS_VERSION>::open(input)?;
+        let mut writer = HashingWriter { inner: io::sink(), hasher: Sha256::from_tag(StrictHash::TAG) };
+        io::copy(&mut file, &mut writer)?;
+        Ok(StrictHash::from(writer.hasher))
+    }
+
     impl<S: Stock> Ledger<S> {
         pub fn export_all_to_file(&self, output: impl AsRef<Path>) -> io::Result<()> {
             let file = BinFile::<DEEDS_MAGIC_NUMBER, DEEDS_VERSION>::create_new(output)?;
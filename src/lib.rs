@@ -63,9 +63,9 @@ mod ledger;
 #[cfg(feature = "stl")]
 pub mod stl;
 
-pub use deed::{CallParams, DeedBuilder, Satisfaction};
-pub use ledger::{AcceptError, Ledger};
+pub use deed::{BatchBuilder, CallParams, DeedBuilder, Satisfaction};
 #[cfg(feature = "binfile")]
-pub use ledger::{DEEDS_MAGIC_NUMBER, DEEDS_VERSION};
-pub use state::{EffectiveState, ProcessedState, RawState, Transition};
+pub use ledger::{peek_deeds_checksum, DEEDS_MAGIC_NUMBER, DEEDS_VERSION};
+pub use ledger::{verify_operation, AcceptError, ActivityEntry, ChunkBuffer, Ledger, OutputConsumers, OutputView};
+pub use state::{EffectiveState, ProcessedState, RawState, StateSnapshot, Transition};
 pub use stock::{IssueError, Stock};
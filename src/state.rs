@@ -22,16 +22,22 @@
 // the License.
 
 use alloc::collections::BTreeMap;
+use core::cmp::Ordering;
 
 use aluvm::Lib;
 use amplify::confinement::{LargeOrdMap, SmallOrdMap, SmallOrdSet};
+use commit_verify::{CommitEngine, StrictHash};
 use sonicapi::{Api, Articles, Semantics, StateAtom, StateName};
 use strict_encoding::{StrictDeserialize, StrictSerialize, TypeName};
-use strict_types::{StrictVal, TypeSystem};
+use strict_types::value::EnumTag;
+use strict_types::{FieldName, StrictVal, TypeSystem};
 use ultrasonic::{AuthToken, CallError, CellAddr, Memory, Opid, StateCell, StateData, StateValue, VerifiedOperation};
 
 use crate::LIB_NAME_SONIC;
 
+/// Tag used by the commitment engine in [`EffectiveState::state_root`].
+const STATE_ROOT_TAG: &str = "urn:ubideco:sonic:state-root#2026-10-15";
+
 /// State transitions keeping track of the operation reference plus the state destroyed by the
 /// operation.
 #[derive(Clone, PartialEq, Eq, Debug)]
@@ -73,10 +79,11 @@ impl EffectiveState {
 
     pub fn with_raw_state(raw: RawState, articles: &Articles) -> Self {
         let mut me = Self { raw, main: none!(), aux: none!() };
-        me.main = ProcessedState::with(&me.raw, articles.default_api(), articles.types());
+        let libs = &articles.semantics().api_libs;
+        me.main = ProcessedState::with(&me.raw, articles.default_api(), libs, articles.types());
         me.aux.clear();
         for (name, api) in articles.custom_apis() {
-            let state = ProcessedState::with(&me.raw, api, articles.types());
+            let state = ProcessedState::with(&me.raw, api, libs, articles.types());
             me.aux.insert(name.clone(), state);
         }
         me.recompute(articles.semantics());
@@ -94,6 +101,100 @@ impl EffectiveState {
             .unwrap_or_else(|| panic!("Computed state {name} is not known"))
     }
 
+    /// Borrows the global state with a given name, as computed by the default API, without cloning
+    /// it.
+    ///
+    /// The returned reference points directly into the internal state and is valid only while the
+    /// effective state is not mutated: any operation application, rollback or
+    /// [`Self::recompute`] requires a mutable borrow and thus ends the lifetime of the reference.
+    /// To keep the data beyond that point, clone it explicitly.
+    ///
+    /// Returns `None` if the global state with the provided name is not known or is empty.
+    #[inline]
+    pub fn global_ref(&self, name: impl Into<StateName>) -> Option<&BTreeMap<CellAddr, StateAtom>> {
+        self.main.global(&name.into())
+    }
+
+    /// Borrows the aggregated (computed) state with a given name, as computed by the default API,
+    /// without cloning it.
+    ///
+    /// The same lifetime constraints as for [`Self::global_ref`] apply. Unlike [`Self::read`],
+    /// does not panic and returns `None` if the computed state with the provided name is not known.
+    #[inline]
+    pub fn aggregated_ref(&self, name: impl Into<StateName>) -> Option<&StrictVal> {
+        self.main.aggregated.get(&name.into())
+    }
+
+    /// Iterates over the global state with a given name, providing both the verified and unverified
+    /// (decoded from raw data, if present) state values for each of the memory cells.
+    ///
+    /// If the state with the provided name is not known or is empty, returns an empty iterator.
+    pub fn global_full(
+        &self,
+        name: impl Into<StateName>,
+    ) -> impl Iterator<Item = (CellAddr, &StrictVal, Option<&StrictVal>)> {
+        self.main
+            .global
+            .get(&name.into())
+            .into_iter()
+            .flat_map(|cells| cells.iter())
+            .map(|(addr, atom)| (*addr, &atom.verified, atom.unverified.as_ref()))
+    }
+
+    /// Iterates over all owned state of the contract, regardless of the state name.
+    ///
+    /// Yields the owned state of the default API first, followed by the owned state of the custom
+    /// APIs which is not defined by the default API (i.e., having a different state name).
+    pub fn all_owned(&self) -> impl Iterator<Item = (StateName, CellAddr, &StrictVal)> {
+        let aux = self
+            .aux
+            .values()
+            .flat_map(|state| state.owned.iter())
+            .filter(|(name, _)| !self.main.owned.contains_key(*name));
+        self.main
+            .owned
+            .iter()
+            .chain(aux)
+            .flat_map(|(name, cells)| cells.iter().map(|(addr, val)| (name.clone(), *addr, val)))
+    }
+
+    /// Assembles the current contract state, as computed by the default API, into a single
+    /// [`StateSnapshot`], which can be serialized, for instance, as an API response.
+    pub fn to_snapshot(&self) -> StateSnapshot {
+        StateSnapshot {
+            global: self.main.global.clone(),
+            owned: self.main.owned.clone(),
+            aggregated: self.main.aggregated.clone(),
+        }
+    }
+
+    /// Computes a compact commitment to the current global and owned state of the contract, which
+    /// can be used by light clients to verify the state claimed by a server against an anchored
+    /// commitment.
+    ///
+    /// The root commits to the raw global and owned memory cells, each sorted by their
+    /// [`CellAddr`], and does not depend on the APIs, the aggregated state or on the order in which
+    /// operations were applied. Thus, it is stable across ledger reloads and API upgrades, and
+    /// changes only when the set of the contract memory cells or their content changes.
+    pub fn state_root(&self) -> StrictHash {
+        let mut engine = CommitEngine::new(STATE_ROOT_TAG);
+        engine.commit_to_linear_map(&self.raw.global);
+        engine.commit_to_linear_map(&self.raw.owned);
+        engine.set_finished();
+        StrictHash::from(engine.finish())
+    }
+
+    /// Reads computed state in a canonical form, where elements of all maps and sets are sorted
+    /// (with maps sorted by their keys), such that it can be used in the stable API responses.
+    ///
+    /// Unlike [`Self::read`], the order of the elements doesn't depend on the order in which they
+    /// were produced by the aggregator.
+    ///
+    /// # Panics
+    ///
+    /// If the computed state with the provided name is not known.
+    pub fn read_sorted(&self, name: impl Into<StateName>) -> StrictVal { canonical_val(self.read(name).clone()) }
+
     /// Re-evaluates computable part of the state
     pub fn recompute(&mut self, apis: &Semantics) {
         self.main
@@ -108,20 +209,22 @@ impl EffectiveState {
 
     #[must_use]
     pub(crate) fn apply(&mut self, op: VerifiedOperation, apis: &Semantics) -> Transition {
-        self.main.apply(&op, &apis.default, &apis.types);
+        self.main
+            .apply(&op, &apis.default, &apis.api_libs, &apis.types);
         for (name, api) in &apis.custom {
             let state = self.aux.entry(name.clone()).or_default();
-            state.apply(&op, api, &apis.types);
+            state.apply(&op, api, &apis.api_libs, &apis.types);
         }
         self.raw.apply(op)
     }
 
     pub(crate) fn rollback(&mut self, transition: Transition, apis: &Semantics) {
-        self.main.rollback(&transition, &apis.default, &apis.types);
+        self.main
+            .rollback(&transition, &apis.default, &apis.api_libs, &apis.types);
         let mut count = 0usize;
         for (name, api) in &apis.custom {
             let state = self.aux.get_mut(name).expect("unknown aux API");
-            state.rollback(&transition, api, &apis.types);
+            state.rollback(&transition, api, &apis.api_libs, &apis.types);
             count += 1;
         }
         debug_assert_eq!(count, self.aux.len());
@@ -129,6 +232,18 @@ impl EffectiveState {
     }
 }
 
+/// Snapshot of the contract state computed by the default API, which contains the valid global,
+/// owned and aggregated state, but not the invalid or raw state.
+///
+/// Produced with [`EffectiveState::to_snapshot`].
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "camelCase"))]
+pub struct StateSnapshot {
+    pub global: BTreeMap<StateName, BTreeMap<CellAddr, StateAtom>>,
+    pub owned: BTreeMap<StateName, BTreeMap<CellAddr, StrictVal>>,
+    pub aggregated: BTreeMap<StateName, StrictVal>,
+}
+
 #[derive(Clone, Debug, Default)]
 #[derive(StrictType, StrictEncode, StrictDecode)]
 #[strict_type(lib = LIB_NAME_SONIC)]
@@ -222,13 +337,13 @@ pub struct ProcessedState {
 }
 
 impl ProcessedState {
-    pub fn with(raw: &RawState, api: &Api, sys: &TypeSystem) -> Self {
+    pub fn with(raw: &RawState, api: &Api, libs: &SmallOrdSet<Lib>, sys: &TypeSystem) -> Self {
         let mut me = ProcessedState::default();
         for (addr, state) in &raw.global {
-            me.process_global(*addr, state, api, sys);
+            me.process_global(*addr, state, api, libs, sys);
         }
         for (addr, state) in &raw.owned {
-            me.process_owned(*addr, state, api, sys);
+            me.process_owned(*addr, state, api, libs, sys);
         }
         me
     }
@@ -241,11 +356,18 @@ impl ProcessedState {
     ///
     /// Ignores cycle dependencies between aggregators; the computed state with them is not
     /// produced.
+    ///
+    /// AluVM script aggregators are computed once, after all other aggregators, such that their
+    /// values do not depend on each other or on the previously computed values.
     pub(super) fn aggregate(&mut self, api: &Api, libs: &SmallOrdSet<Lib>, types: &TypeSystem) {
         self.aggregated = bmap! {};
+        let (scripts, others): (Vec<_>, Vec<_>) = api
+            .aggregators()
+            .iter()
+            .partition(|(_, aggregator)| aggregator.is_script());
         let mut computed = 0usize;
         loop {
-            for (name, aggregator) in api.aggregators() {
+            for &(name, aggregator) in &others {
                 if aggregator
                     .depends_on()
                     .any(|s| !self.global.contains_key(s) && !self.aggregated.contains_key(s))
@@ -264,14 +386,21 @@ impl ProcessedState {
             }
             computed = 0;
         }
+
+        let aggregated = self.aggregated.clone();
+        for (name, aggregator) in scripts {
+            if let Some(val) = aggregator.aggregate(&self.global, &aggregated, libs, types) {
+                self.aggregated.insert(name.clone(), val);
+            }
+        }
     }
 
-    pub(self) fn apply(&mut self, op: &VerifiedOperation, api: &Api, sys: &TypeSystem) {
+    pub(self) fn apply(&mut self, op: &VerifiedOperation, api: &Api, libs: &SmallOrdSet<Lib>, sys: &TypeSystem) {
         let opid = op.opid();
         let op = op.as_operation();
         for (no, state) in op.immutable_out.iter().enumerate() {
             let addr = CellAddr::new(opid, no as u16);
-            self.process_global(addr, state, api, sys);
+            self.process_global(addr, state, api, libs, sys);
         }
         for input in &op.destructible_in {
             for map in self.owned.values_mut() {
@@ -280,11 +409,11 @@ impl ProcessedState {
         }
         for (no, state) in op.destructible_out.iter().enumerate() {
             let addr = CellAddr::new(opid, no as u16);
-            self.process_owned(addr, state, api, sys);
+            self.process_owned(addr, state, api, libs, sys);
         }
     }
 
-    pub(self) fn rollback(&mut self, transition: &Transition, api: &Api, sys: &TypeSystem) {
+    pub(self) fn rollback(&mut self, transition: &Transition, api: &Api, libs: &SmallOrdSet<Lib>, sys: &TypeSystem) {
         let opid = transition.opid;
 
         self.global
@@ -295,12 +424,19 @@ impl ProcessedState {
             .for_each(|state| state.retain(|addr, _| addr.opid != opid));
 
         for (addr, cell) in &transition.destroyed {
-            self.process_owned(*addr, cell, api, sys);
+            self.process_owned(*addr, cell, api, libs, sys);
         }
     }
 
-    fn process_global(&mut self, addr: CellAddr, state: &StateData, api: &Api, sys: &TypeSystem) {
-        match api.convert_global(state, sys) {
+    fn process_global(
+        &mut self,
+        addr: CellAddr,
+        state: &StateData,
+        api: &Api,
+        libs: &SmallOrdSet<Lib>,
+        sys: &TypeSystem,
+    ) {
+        match api.convert_global(state, libs, sys) {
             // This means this state is unrelated to this API
             Ok(None) => {}
             Ok(Some((name, atom))) => {
@@ -312,8 +448,15 @@ impl ProcessedState {
         }
     }
 
-    fn process_owned(&mut self, addr: CellAddr, state: &StateCell, api: &Api, sys: &TypeSystem) {
-        match api.convert_owned(state.data, sys) {
+    fn process_owned(
+        &mut self,
+        addr: CellAddr,
+        state: &StateCell,
+        api: &Api,
+        libs: &SmallOrdSet<Lib>,
+        sys: &TypeSystem,
+    ) {
+        match api.convert_owned(state.data, libs, sys) {
             // This means this state is unrelated to this API
             Ok(None) => {}
             Ok(Some((name, atom))) => {
@@ -325,3 +468,203 @@ impl ProcessedState {
         }
     }
 }
+
+/// Converts a strict value into a canonical form by sorting all its maps by keys and all its sets by
+/// values, recursively.
+fn canonical_val(val: StrictVal) -> StrictVal {
+    match val {
+        StrictVal::Struct(fields) => {
+            StrictVal::Struct(fields.into_iter().map(|(name, val)| (name, canonical_val(val))).collect())
+        }
+        StrictVal::Union(tag, val) => StrictVal::Union(tag, Box::new(canonical_val(*val))),
+        StrictVal::List(items) => StrictVal::List(items.into_iter().map(canonical_val).collect()),
+        StrictVal::Tuple(items) => StrictVal::Tuple(items.into_iter().map(canonical_val).collect()),
+        StrictVal::Set(items) => {
+            let mut items = items.into_iter().map(canonical_val).collect::<Vec<_>>();
+            items.sort_by(cmp_val);
+            StrictVal::Set(items)
+        }
+        StrictVal::Map(items) => {
+            let mut items = items
+                .into_iter()
+                .map(|(key, val)| (canonical_val(key), canonical_val(val)))
+                .collect::<Vec<_>>();
+            items.sort_by(|(a, _), (b, _)| cmp_val(a, b));
+            StrictVal::Map(items)
+        }
+        val => val,
+    }
+}
+
+/// Total ordering of strict values: values of different kinds are ordered by the kind, and values
+/// of the same kind are compared by their content.
+fn cmp_val(a: &StrictVal, b: &StrictVal) -> Ordering {
+    fn rank(val: &StrictVal) -> u8 {
+        match val {
+            StrictVal::Unit => 0,
+            StrictVal::Number(_) => 1,
+            StrictVal::String(_) => 2,
+            StrictVal::Bytes(_) => 3,
+            StrictVal::Struct(_) => 4,
+            StrictVal::Enum(_) => 5,
+            StrictVal::Union(_, _) => 6,
+            StrictVal::List(_) => 7,
+            StrictVal::Set(_) => 8,
+            StrictVal::Tuple(_) => 9,
+            StrictVal::Map(_) => 10,
+        }
+    }
+    fn cmp_tag(a: &EnumTag, b: &EnumTag) -> Ordering {
+        match (a, b) {
+            (EnumTag::Ord(a), EnumTag::Ord(b)) => a.cmp(b),
+            (EnumTag::Name(a), EnumTag::Name(b)) => a.as_str().cmp(b.as_str()),
+            (EnumTag::Ord(_), EnumTag::Name(_)) => Ordering::Less,
+            (EnumTag::Name(_), EnumTag::Ord(_)) => Ordering::Greater,
+        }
+    }
+    fn cmp_seq<'a>(
+        a: impl IntoIterator<Item = &'a StrictVal>,
+        b: impl IntoIterator<Item = &'a StrictVal>,
+    ) -> Ordering {
+        let mut b = b.into_iter();
+        for a in a {
+            let Some(b) = b.next() else {
+                return Ordering::Greater;
+            };
+            match cmp_val(a, b) {
+                Ordering::Equal => {}
+                ordering => return ordering,
+            }
+        }
+        if b.next().is_some() { Ordering::Less } else { Ordering::Equal }
+    }
+
+    match (a, b) {
+        (StrictVal::Number(a), StrictVal::Number(b)) => a.cmp(b),
+        (StrictVal::String(a), StrictVal::String(b)) => a.cmp(b),
+        (StrictVal::Bytes(a), StrictVal::Bytes(b)) => a.cmp(b),
+        (StrictVal::Struct(a), StrictVal::Struct(b)) => {
+            let names_a = a.keys().map(FieldName::as_str);
+            let names_b = b.keys().map(FieldName::as_str);
+            names_a
+                .cmp(names_b)
+                .then_with(|| cmp_seq(a.values(), b.values()))
+        }
+        (StrictVal::Enum(a), StrictVal::Enum(b)) => cmp_tag(a, b),
+        (StrictVal::Union(tag_a, a), StrictVal::Union(tag_b, b)) => cmp_tag(tag_a, tag_b).then_with(|| cmp_val(a, b)),
+        (StrictVal::List(a), StrictVal::List(b))
+        | (StrictVal::Set(a), StrictVal::Set(b))
+        | (StrictVal::Tuple(a), StrictVal::Tuple(b)) => cmp_seq(a, b),
+        (StrictVal::Map(a), StrictVal::Map(b)) => {
+            cmp_seq(a.iter().flat_map(|(k, v)| [k, v]), b.iter().flat_map(|(k, v)| [k, v]))
+        }
+        (a, b) => rank(a).cmp(&rank(b)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #![cfg_attr(coverage_nightly, coverage(off))]
+    #[cfg(feature = "serde")]
+    use ultrasonic::{fe256, CellLock};
+
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn transition_serde() {
+        let opid = Opid::from([0xA5u8; 32]);
+        let mut transition = Transition::new(opid);
+        transition
+            .destroyed
+            .insert(CellAddr::new(Opid::from([0x01u8; 32]), 0), StateCell {
+                data: StateValue::new(1u8, 1000u64),
+                auth: AuthToken::from([0x02u8; 30]),
+                lock: None,
+            })
+            .unwrap();
+        transition
+            .destroyed
+            .insert(CellAddr::new(Opid::from([0x03u8; 32]), 5), StateCell {
+                data: StateValue::Single { first: fe256::from(7u8) },
+                auth: AuthToken::from([0x04u8; 30]),
+                lock: Some(CellLock { aux: StateValue::None, script: None }),
+            })
+            .unwrap();
+
+        let json = serde_json::to_string(&transition).unwrap();
+        let restored: Transition = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, transition);
+    }
+
+    #[test]
+    fn all_owned() {
+        let addr = |no: u16| CellAddr::new(Opid::from([0xA5u8; 32]), no);
+        let mut state = EffectiveState::default();
+        state.main.owned.insert(vname!("usdt"), bmap! { addr(0) => svnum!(10u64), addr(1) => svnum!(20u64) });
+        state.main.owned.insert(vname!("btc"), bmap! { addr(2) => svnum!(1u64) });
+        let mut aux = ProcessedState::default();
+        aux.owned.insert(vname!("btc"), bmap! { addr(2) => svnum!(1u64) });
+        aux.owned.insert(vname!("nft"), bmap! { addr(3) => svnum!(5u64) });
+        state.aux.insert(tn!("Nft"), aux);
+
+        let all = state.all_owned().collect::<Vec<_>>();
+        assert_eq!(all, vec![
+            (vname!("btc"), addr(2), &svnum!(1u64)),
+            (vname!("usdt"), addr(0), &svnum!(10u64)),
+            (vname!("usdt"), addr(1), &svnum!(20u64)),
+            (vname!("nft"), addr(3), &svnum!(5u64)),
+        ]);
+    }
+
+    #[test]
+    fn read_sorted() {
+        let map = |order: &[u64]| {
+            StrictVal::Map(
+                order
+                    .iter()
+                    .map(|n| (svnum!(*n), StrictVal::set(order.iter().map(|m| svnum!(n * 10 + m)))))
+                    .collect(),
+            )
+        };
+
+        let mut first = EffectiveState::default();
+        first.main.aggregated.insert(vname!("balances"), map(&[3, 1, 2]));
+        let mut second = EffectiveState::default();
+        second.main.aggregated.insert(vname!("balances"), map(&[2, 3, 1]));
+
+        assert_ne!(first.read("balances"), second.read("balances"));
+        assert_eq!(first.read("balances"), &map(&[3, 1, 2]));
+        assert_eq!(first.read_sorted("balances"), map(&[1, 2, 3]));
+        assert_eq!(second.read_sorted("balances"), map(&[1, 2, 3]));
+        #[cfg(feature = "serde")]
+        assert_eq!(
+            serde_json::to_string(&first.read_sorted("balances")).unwrap(),
+            serde_json::to_string(&second.read_sorted("balances")).unwrap()
+        );
+    }
+
+    #[test]
+    fn borrowed_refs() {
+        let addr = |no: u16| CellAddr::new(Opid::from([0xA5u8; 32]), no);
+        let mut state = EffectiveState::default();
+        let global = (0..10_000u16)
+            .map(|no| (addr(no), StateAtom::new_verified(svnum!(no as u64))))
+            .collect::<BTreeMap<_, _>>();
+        state.main.global.insert(vname!("cells"), global);
+        state
+            .main
+            .aggregated
+            .insert(vname!("total"), StrictVal::List((0..10_000u64).map(|n| svnum!(n)).collect()));
+
+        let global = state.global_ref("cells").unwrap();
+        assert_eq!(global.len(), 10_000);
+        assert!(std::ptr::eq(global, &state.main.global[&vname!("cells")]));
+        let total = state.aggregated_ref("total").unwrap();
+        assert!(std::ptr::eq(total, &state.main.aggregated[&vname!("total")]));
+        assert!(std::ptr::eq(total, state.read("total")));
+
+        assert!(state.global_ref("unknown").is_none());
+        assert!(state.aggregated_ref("unknown").is_none());
+    }
+}
@@ -21,13 +21,18 @@
 // or implied. See the License for the specific language governing permissions and limitations under
 // the License.
 
+use core::convert::Infallible;
 use core::error::Error;
+use std::io;
 
+use amplify::confinement::TinyString;
 use amplify::MultiError;
-use sonicapi::SemanticError;
-use ultrasonic::{CallError, CellAddr, ContractName, Operation, Opid};
+use chrono::{DateTime, Utc};
+use sonicapi::{SemanticError, Semantics, SigBlob};
+use strict_encoding::{ReadRaw, StrictDecode, StrictEncode, StrictReader, StrictWriter, WriteRaw};
+use ultrasonic::{CallError, CellAddr, ContractName, Issue, Operation, Opid};
 
-use crate::{Articles, EffectiveState, Transition};
+use crate::{AcceptError, Articles, EffectiveState, RawState, Transition};
 
 /// Stock is a persistence API for keeping and accessing contract data.
 ///
@@ -99,6 +104,56 @@ pub trait Stock {
     fn mark_valid(&mut self, opid: Opid);
     fn mark_invalid(&mut self, opid: Opid);
 
+    /// Marks an operation with a given `opid` as invalid, recording the reason for that (like
+    /// "rolled back due to reorg at height H").
+    ///
+    /// Stocks which do not keep track of invalidation reasons may ignore the reason, which is the
+    /// default behavior forwarding the call to [`Self::mark_invalid`].
+    ///
+    /// # Nota bene
+    ///
+    /// The reason is a local bookkeeping information, and it is not a part of consensus. Marking
+    /// the operation as valid, or invalid without a reason, discards the previously recorded
+    /// reason.
+    ///
+    /// # Blocking I/O
+    ///
+    /// This call MAY BE blocking.
+    fn mark_invalid_with_reason(&mut self, opid: Opid, _reason: TinyString) { self.mark_invalid(opid) }
+
+    /// Returns the reason recorded for the most recent invalidation of an operation with a given
+    /// `opid`, if the operation is invalid and the stock keeps track of it.
+    ///
+    /// See [`Self::mark_invalid_with_reason`] for the details.
+    ///
+    /// # Blocking I/O
+    ///
+    /// This call MAY BE blocking.
+    fn invalidation_reason(&self, _opid: Opid) -> Option<TinyString> { None }
+
+    /// Returns the local time at which an operation with a given `opid` was first received by the
+    /// stock, if the stock keeps track of it.
+    ///
+    /// # Nota bene
+    ///
+    /// Receive-time is a local bookkeeping information, kept aside of the contract data. It is not
+    /// a part of consensus, is not committed to by the operation, and varies between peers.
+    ///
+    /// # Blocking I/O
+    ///
+    /// This call MAY BE blocking.
+    fn received_at(&self, _opid: Opid) -> Option<DateTime<Utc>> { None }
+
+    /// Records the local time at which an operation with a given `opid` was received.
+    ///
+    /// Stocks which do not keep track of receive-time may ignore the call, which is the default
+    /// behavior. See [`Self::received_at`] for the details.
+    ///
+    /// # Blocking I/O
+    ///
+    /// This call MAY BE blocking.
+    fn set_received_at(&mut self, _opid: Opid, _time: DateTime<Utc>) {}
+
     /// Detects whether an operation with a given `opid` is known to the contract.
     ///
     /// # Nota bene
@@ -382,6 +437,133 @@ pub trait Stock {
     ///
     /// This call MAY BE blocking.
     fn commit_transaction(&mut self);
+
+    /// Writes a complete snapshot of the contract, which includes contract articles, state, all
+    /// known operations with their traces, validity status, invalidation reasons and receive-times
+    /// (as milliseconds since the UNIX epoch), and spending and reading indexes.
+    ///
+    /// Unlike exports, which include only a contract history subset (and can be accepted by any
+    /// other party), the snapshot is intended for backup purposes; the contract restored from it
+    /// with [`Self::restore`] is identical to the original one.
+    ///
+    /// # Blocking I/O
+    ///
+    /// This call MAY BE blocking.
+    fn snapshot(&self, mut writer: StrictWriter<impl WriteRaw>) -> io::Result<()> {
+        writer = self.articles().strict_encode(writer)?;
+        writer = self.state().raw.strict_encode(writer)?;
+
+        let mut readings = Vec::new();
+        let mut spendings = Vec::new();
+        writer = self.operation_count().strict_encode(writer)?;
+        for (opid, op) in self.operations() {
+            for addr in &op.immutable_in {
+                readings.extend(self.read_by(*addr).map(|reader| (*addr, reader)));
+            }
+            for input in &op.destructible_in {
+                spendings.extend(self.spent_by(input.addr).map(|spender| (input.addr, spender)));
+            }
+            writer = op.strict_encode(writer)?;
+            writer = self.transition(opid).strict_encode(writer)?;
+            writer = self.is_valid(opid).strict_encode(writer)?;
+            writer = self.invalidation_reason(opid).strict_encode(writer)?;
+            writer = self
+                .received_at(opid)
+                .map(|time| time.timestamp_millis())
+                .strict_encode(writer)?;
+        }
+
+        // The same output may be read or spent by multiple operations
+        readings.sort_unstable();
+        readings.dedup();
+        spendings.sort_unstable();
+        spendings.dedup();
+        writer = (readings.len() as u64).strict_encode(writer)?;
+        for (addr, reader) in readings {
+            writer = addr.strict_encode(writer)?;
+            writer = reader.strict_encode(writer)?;
+        }
+        writer = (spendings.len() as u64).strict_encode(writer)?;
+        for (addr, spender) in spendings {
+            writer = addr.strict_encode(writer)?;
+            writer = spender.strict_encode(writer)?;
+        }
+        Ok(())
+    }
+
+    /// Restores a contract from a snapshot created with [`Self::snapshot`], creating its
+    /// persistence using a given implementation-specific configuration.
+    ///
+    /// # Nota bene
+    ///
+    /// The snapshot data are trusted: neither articles signature nor operations are verified.
+    ///
+    /// # Blocking I/O
+    ///
+    /// This call MAY perform any I/O operations.
+    fn restore(
+        reader: &mut StrictReader<impl ReadRaw>,
+        conf: Self::Conf,
+    ) -> Result<Self, MultiError<AcceptError, Self::Error>>
+    where
+        Self: Sized,
+    {
+        let (articles, raw) = (|| -> Result<_, AcceptError> {
+            let semantics = Semantics::strict_decode(reader)?;
+            let sig = Option::<SigBlob>::strict_decode(reader)?;
+            let issue = Issue::strict_decode(reader)?;
+            // We trust the snapshot
+            let articles = Articles::with(semantics, issue, sig, |_, _, _| -> Result<_, Infallible> { Ok(()) })?;
+            let raw = RawState::strict_decode(reader)?;
+            Ok((articles, raw))
+        })()
+        .map_err(MultiError::A)?;
+
+        let state = EffectiveState::with_raw_state(raw, &articles);
+        let mut stock = Self::new(articles, state, conf).map_err(MultiError::B)?;
+
+        (|| -> Result<_, AcceptError> {
+            let count = u64::strict_decode(reader)?;
+            for _ in 0..count {
+                let op = Operation::strict_decode(reader)?;
+                let transition = Transition::strict_decode(reader)?;
+                let valid = bool::strict_decode(reader)?;
+                let reason = Option::<TinyString>::strict_decode(reader)?;
+                let received = Option::<i64>::strict_decode(reader)?;
+                let opid = op.opid();
+                stock.add_operation(opid, &op);
+                stock.add_transition(opid, &transition);
+                match (valid, reason) {
+                    (true, _) => stock.mark_valid(opid),
+                    (false, Some(reason)) => stock.mark_invalid_with_reason(opid, reason),
+                    (false, None) => stock.mark_invalid(opid),
+                }
+                if let Some(time) = received.and_then(DateTime::from_timestamp_millis) {
+                    stock.set_received_at(opid, time);
+                }
+            }
+
+            let count = u64::strict_decode(reader)?;
+            for _ in 0..count {
+                let addr = CellAddr::strict_decode(reader)?;
+                let reader_id = Opid::strict_decode(reader)?;
+                stock.add_reading(addr, reader_id);
+            }
+            let count = u64::strict_decode(reader)?;
+            for _ in 0..count {
+                let addr = CellAddr::strict_decode(reader)?;
+                let spender = Opid::strict_decode(reader)?;
+                stock.add_spending(addr, spender);
+            }
+            Ok(())
+        })()
+        .map_err(MultiError::A)?;
+
+        // Genesis is not a part of the snapshotted operations, but it is always valid
+        stock.mark_valid(stock.articles().genesis_opid());
+        stock.commit_transaction();
+        Ok(stock)
+    }
 }
 
 #[derive(Clone, PartialEq, Eq, Debug, Display, Error)]
@@ -389,4 +571,7 @@ pub trait Stock {
 pub enum IssueError {
     /// unable to issue a new contract '{0}' due to invalid genesis data. Specifically, {1}
     Genesis(ContractName, CallError),
+
+    /// unable to issue a new contract due to invalid contract semantics. Specifically, {0}
+    Semantics(SemanticError),
 }
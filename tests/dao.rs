@@ -28,19 +28,22 @@ extern crate amplify;
 #[macro_use]
 extern crate strict_types;
 
+use std::collections::BTreeMap;
 use std::convert::Infallible;
 use std::fs;
 use std::path::Path;
 
 use aluvm::{CoreConfig, LibSite};
+use amplify::confinement::TinyBlob;
 use amplify::num::u256;
 use commit_verify::{Digest, Sha256, StrictHash};
-use hypersonic::{Api, GlobalApi, OwnedApi};
+use hypersonic::{Api, EffectiveState, GlobalApi, OwnedApi};
 use sonic_persist_fs::LedgerDir;
 use sonicapi::{
-    Aggregator, Issuer, RawBuilder, RawConvertor, Semantics, SigBlob, StateArithm, StateBuilder, StateConvertor,
-    SubAggregator,
+    Aggregator, Issuer, RawBuilder, RawConvertor, SemanticError, Semantics, SigBlob, StateArithm, StateAtom,
+    StateBuildError, StateBuilder, StateConvertor, StateSelector, SubAggregator,
 };
+use strict_encoding::{StreamReader, StreamWriter, StrictEncode, StrictReader, StrictWriter};
 use strict_types::{SemId, StrictVal};
 use ultrasonic::aluvm::FIELD_ORDER_SECP;
 use ultrasonic::{AuthToken, CellAddr, Codex, Consensus, Identity};
@@ -79,6 +82,7 @@ fn api() -> Api {
                 published: true,
                 sem_id: types.get("DAO.PartyId"),
                 convertor: StateConvertor::TypedEncoder(u256::ZERO),
+                fallback_convertors: none!(),
                 builder: StateBuilder::TypedEncoder(u256::ZERO),
                 raw_convertor: RawConvertor::StrictDecode(types.get("DAO.Party")),
                 raw_builder: RawBuilder::StrictEncode(types.get("DAO.Party")),
@@ -87,6 +91,7 @@ fn api() -> Api {
                 published: true,
                 sem_id: types.get("DAO.VoteId"),
                 convertor: StateConvertor::TypedEncoder(u256::ONE),
+                fallback_convertors: none!(),
                 builder: StateBuilder::TypedEncoder(u256::ONE),
                 raw_convertor: RawConvertor::StrictDecode(types.get("DAO.Voting")),
                 raw_builder: RawBuilder::StrictEncode(types.get("DAO.Voting")),
@@ -95,6 +100,7 @@ fn api() -> Api {
                 published: true,
                 sem_id: types.get("DAO.CastVote"),
                 convertor: StateConvertor::TypedEncoder(u256::from(2u8)),
+                fallback_convertors: none!(),
                 builder: StateBuilder::TypedEncoder(u256::from(2u8)),
                 raw_convertor: RawConvertor::StrictDecode(SemId::unit()),
                 raw_builder: RawBuilder::StrictEncode(SemId::unit()),
@@ -105,6 +111,7 @@ fn api() -> Api {
                 sem_id: types.get("DAO.PartyId"),
                 arithmetics: StateArithm::NonFungible,
                 convertor: StateConvertor::TypedEncoder(u256::ZERO),
+                fallback_convertors: none!(),
                 builder: StateBuilder::TypedEncoder(u256::ZERO),
                 witness_sem_id: SemId::unit(),
                 witness_builder: StateBuilder::TypedEncoder(u256::ZERO),
@@ -125,6 +132,399 @@ fn api() -> Api {
     }
 }
 
+#[test]
+fn aggregator_plan() {
+    let mut api = api();
+    api.aggregators
+        .insert(vname!("alpha"), Aggregator::Take(SubAggregator::Neg(StateSelector::Aggregated(vname!("beta")))))
+        .unwrap();
+    api.aggregators
+        .insert(
+            vname!("beta"),
+            Aggregator::Take(SubAggregator::Neg(StateSelector::Aggregated(vname!("votingCount")))),
+        )
+        .unwrap();
+
+    let plan = api.aggregator_plan();
+    assert_eq!(plan.len(), 6);
+    let pos = |name: &str| plan.iter().position(|(n, _, _)| n.as_str() == name).unwrap();
+    assert!(pos("votingCount") < pos("beta"));
+    assert!(pos("beta") < pos("alpha"));
+
+    for (name, aggregator, deps) in &plan {
+        assert_eq!(api.aggregators.get(name), Some(aggregator));
+        assert_eq!(deps, &aggregator.depends_on().cloned().collect::<Vec<_>>());
+        for dep in deps {
+            assert!(pos(dep.as_str()) < pos(name.as_str()));
+        }
+    }
+    assert_eq!(plan[pos("beta")].2, vec![vname!("votingCount")]);
+    assert!(plan[pos("votings")].2.is_empty());
+
+    // Scripts are computed after all other aggregators
+    let types = stl::DaoTypes::new();
+    api.aggregators
+        .insert(
+            vname!("computed"),
+            Aggregator::AluVM(LibSite::new(libs::success().lib_id(), 0), types.get("DAO.PartyId")),
+        )
+        .unwrap();
+    let plan = api.aggregator_plan();
+    assert_eq!(plan.len(), 7);
+    let (name, _, deps) = plan.last().unwrap();
+    assert_eq!(name, &vname!("computed"));
+    assert_eq!(deps.len(), 6);
+    assert!(!deps.contains(&vname!("computed")));
+}
+
+#[test]
+fn cyclic_aggregators() {
+    let types = stl::DaoTypes::new();
+    let mut semantics = Semantics {
+        version: 0,
+        default: api(),
+        custom: none!(),
+        codex_libs: small_bset![libs::success()],
+        api_libs: none!(),
+        types: types.type_system(),
+    };
+    semantics.check(&codex()).unwrap();
+
+    let aggregators = &mut semantics.default.aggregators;
+    aggregators
+        .insert(vname!("alpha"), Aggregator::Take(SubAggregator::Copy(vname!("beta"))))
+        .unwrap();
+    aggregators
+        .insert(vname!("beta"), Aggregator::Take(SubAggregator::Copy(vname!("alpha"))))
+        .unwrap();
+    assert_eq!(semantics.check(&codex()).unwrap_err(), SemanticError::CyclicAggregator(vname!("alpha")));
+
+    // Self-dependency is a cycle as well
+    let aggregators = &mut semantics.default.aggregators;
+    aggregators
+        .insert(vname!("alpha"), Aggregator::Take(SubAggregator::Copy(vname!("votingCount"))))
+        .unwrap();
+    aggregators
+        .insert(
+            vname!("beta"),
+            Aggregator::Or(SubAggregator::Copy(vname!("beta")), SubAggregator::Count(vname!("_votes"))),
+        )
+        .unwrap();
+    assert_eq!(semantics.check(&codex()).unwrap_err(), SemanticError::CyclicAggregator(vname!("beta")));
+
+    // Scripts implicitly depend on all non-script aggregators
+    let aggregators = &mut semantics.default.aggregators;
+    aggregators
+        .insert(vname!("beta"), Aggregator::Take(SubAggregator::Copy(vname!("gamma"))))
+        .unwrap();
+    aggregators
+        .insert(vname!("gamma"), Aggregator::AluVM(LibSite::new(libs::success().lib_id(), 0), types.get("DAO.PartyId")))
+        .unwrap();
+    assert_eq!(semantics.check(&codex()).unwrap_err(), SemanticError::CyclicAggregator(vname!("beta")));
+}
+
+#[test]
+fn published_global_names() {
+    let mut api = api();
+    assert_eq!(api.published_global_names().collect::<Vec<_>>(), [
+        &vname!("_parties"),
+        &vname!("_votes"),
+        &vname!("_votings")
+    ]);
+    assert_eq!(api.private_global_names().count(), 0);
+
+    api.global.get_mut(&vname!("_votes")).unwrap().published = false;
+    assert_eq!(api.published_global_names().count(), 2);
+    assert_eq!(api.private_global_names().collect::<Vec<_>>(), [&vname!("_votes")]);
+}
+
+#[test]
+fn can_encode() {
+    let types = stl::DaoTypes::new();
+    let sys = types.type_system();
+    let mut api = api();
+    api.global
+        .insert(vname!("partyInfo"), GlobalApi {
+            published: true,
+            sem_id: types.get("DAO.Party"),
+            convertor: StateConvertor::TypedEncoder(u256::from(3u8)),
+            fallback_convertors: none!(),
+            builder: StateBuilder::TypedEncoder(u256::from(3u8)),
+            raw_convertor: RawConvertor::StrictDecode(SemId::unit()),
+            raw_builder: RawBuilder::StrictEncode(SemId::unit()),
+        })
+        .unwrap();
+
+    api.can_encode("signers", &svnum!(0u64), [], &sys).unwrap();
+    api.can_encode("partyInfo", &ston!(name "alice", identity "Alice"), [], &sys)
+        .unwrap();
+
+    let name = "a".repeat(100);
+    let err = api
+        .can_encode("partyInfo", &ston!(name name.as_str(), identity "Alice"), [], &sys)
+        .unwrap_err();
+    assert_eq!(err, StateBuildError::TooLarge(107));
+    assert_eq!(
+        api.can_encode("unknown", &svnum!(0u64), [], &sys)
+            .unwrap_err(),
+        StateBuildError::UnknownStateName(vname!("unknown"))
+    );
+}
+
+#[test]
+fn fallback_convertors() {
+    let types = stl::DaoTypes::new();
+    let sys = types.type_system();
+    let legacy = api();
+    let mut api = api();
+
+    // Move the signers state to a new type id, keeping the legacy encoding decodable
+    let mut signers = api.owned.get(&vname!("signers")).unwrap().clone();
+    signers.convertor = StateConvertor::TypedEncoder(u256::from(7u8));
+    signers.builder = StateBuilder::TypedEncoder(u256::from(7u8));
+    signers.fallback_convertors = tiny_vec![StateConvertor::TypedEncoder(u256::ZERO)];
+    api.owned.insert(vname!("signers"), signers).unwrap();
+
+    let old = legacy
+        .build_destructible("signers", svnum!(5u64), [], &sys)
+        .unwrap();
+    let new = api
+        .build_destructible("signers", svnum!(5u64), [], &sys)
+        .unwrap();
+    assert_ne!(old, new);
+    assert_eq!(legacy.convert_owned(new, [], &sys).unwrap(), None);
+    assert_eq!(api.convert_owned(old, [], &sys).unwrap(), Some((vname!("signers"), svnum!(5u64))));
+    assert_eq!(api.convert_owned(new, [], &sys).unwrap(), Some((vname!("signers"), svnum!(5u64))));
+
+    let mut votings = api.global.get(&vname!("_votings")).unwrap().clone();
+    votings.convertor = StateConvertor::TypedEncoder(u256::from(8u8));
+    votings.builder = StateBuilder::TypedEncoder(u256::from(8u8));
+    votings.fallback_convertors = tiny_vec![StateConvertor::TypedEncoder(u256::ONE)];
+    api.global.insert(vname!("_votings"), votings).unwrap();
+
+    let old = legacy
+        .build_immutable("_votings", svnum!(3u64), None, [], &sys)
+        .unwrap();
+    let new = api
+        .build_immutable("_votings", svnum!(3u64), None, [], &sys)
+        .unwrap();
+    assert_ne!(old, new);
+    for data in [old, new] {
+        let (name, atom) = api.convert_global(&data, [], &sys).unwrap().unwrap();
+        assert_eq!(name, vname!("_votings"));
+        assert_eq!(atom.verified, svnum!(3u64));
+        assert_eq!(atom.unverified, None);
+    }
+}
+
+#[test]
+fn encode_state() {
+    let types = stl::DaoTypes::new();
+    let sys = types.type_system();
+    let sem_id = types.get("DAO.CastVote");
+    let vote = ston!(voteId 100u64, vote svenum!(1u8), partyId 2u64);
+
+    let builder = StateBuilder::TypedEncoder(u256::from(2u8));
+    let state = sonicapi::encode_state(&builder, sem_id, vote.clone(), [], &sys).unwrap();
+    let data = api()
+        .build_immutable("_votes", vote, None, [], &sys)
+        .unwrap();
+    assert_eq!(state, data.value);
+
+    let decoded = builder
+        .to_convertor()
+        .convert(sem_id, state, [], &sys)
+        .unwrap()
+        .unwrap();
+    assert_eq!(decoded.unwrap_struct("vote"), &svenum!(pro));
+    assert_eq!(sonicapi::encode_state(&builder, sem_id, decoded, [], &sys).unwrap(), state);
+
+    assert!(sonicapi::encode_state(&builder, sem_id, svnum!(100u64), [], &sys).is_err());
+}
+
+#[test]
+fn uses_aluvm() {
+    let types = stl::DaoTypes::new();
+    let mut semantics = Semantics {
+        version: 0,
+        default: api(),
+        custom: none!(),
+        codex_libs: small_bset![libs::success()],
+        api_libs: none!(),
+        types: types.type_system(),
+    };
+    assert!(!semantics.uses_aluvm());
+
+    let mut custom = api();
+    custom
+        .aggregators
+        .insert(
+            vname!("computed"),
+            Aggregator::AluVM(LibSite::new(libs::success().lib_id(), 0), types.get("DAO.PartyId")),
+        )
+        .unwrap();
+    semantics.custom.insert(tn!("Computed"), custom).unwrap();
+    assert!(semantics.uses_aluvm());
+}
+
+#[test]
+fn cached_apis_checksum() {
+    // The number of the checksum computations is counted by the `Articles` unit tests, since the
+    // instrumentation is not available to the integration tests.
+    let types = stl::DaoTypes::new();
+    let semantics = |version: u16| Semantics {
+        version,
+        default: api(),
+        custom: none!(),
+        codex_libs: small_bset![libs::success()],
+        api_libs: none!(),
+        types: types.type_system(),
+    };
+    let issue = |semantics: Semantics| {
+        Issuer::new(codex(), semantics)
+            .unwrap()
+            .start_issue_testnet("setup", Consensus::None)
+            .finish("CachedDAO", 1732529307)
+    };
+
+    let mut articles = issue(semantics(0));
+    assert_eq!(articles.apis_checksum(), articles.semantics().apis_checksum());
+    assert_eq!(articles.articles_id().checksum, articles.semantics().apis_checksum());
+    assert_eq!(articles.apis_checksum(), articles.clone().apis_checksum());
+
+    let mut custom = api();
+    custom
+        .aggregators
+        .insert(vname!("partyCount"), Aggregator::Take(SubAggregator::Count(vname!("_parties"))))
+        .unwrap();
+    let mut upgraded = semantics(1);
+    upgraded.custom.insert(tn!("Custom"), custom).unwrap();
+    let checksum = upgraded.apis_checksum();
+    assert_ne!(articles.apis_checksum(), checksum);
+
+    assert!(articles.upgrade_apis(issue(upgraded)).unwrap());
+    assert_eq!(articles.apis_checksum(), checksum);
+    assert_eq!(articles.articles_id().checksum, checksum);
+}
+
+#[test]
+fn project_api() {
+    let types = stl::DaoTypes::new();
+    let party = |id: u64| {
+        let mut data = Vec::new();
+        stl::PartyId::from(id)
+            .strict_encode(StrictWriter::with(StreamWriter::new::<{ usize::MAX }>(&mut data)))
+            .unwrap();
+        TinyBlob::from_checked(data)
+    };
+    let mut counting = api();
+    counting
+        .aggregators
+        .insert(vname!("partyCount"), Aggregator::Take(SubAggregator::Count(vname!("_parties"))))
+        .unwrap();
+    counting
+        .aggregators
+        .insert(
+            vname!("hasBob"),
+            Aggregator::Take(SubAggregator::Any(vname!("_parties"), types.get("DAO.PartyId"), party(1))),
+        )
+        .unwrap();
+    counting
+        .aggregators
+        .insert(
+            vname!("hasAlice"),
+            Aggregator::Take(SubAggregator::Any(vname!("_parties"), types.get("DAO.PartyId"), party(0))),
+        )
+        .unwrap();
+    counting
+        .aggregators
+        .insert(
+            vname!("onlyAlice"),
+            Aggregator::Take(SubAggregator::All(vname!("_parties"), types.get("DAO.PartyId"), party(0))),
+        )
+        .unwrap();
+    let mut summing = api();
+    summing
+        .aggregators
+        .insert(vname!("voteSum"), Aggregator::Take(SubAggregator::SumOrDefault(vname!("_votes"))))
+        .unwrap();
+    let semantics = Semantics {
+        version: 0,
+        default: api(),
+        custom: small_bmap! { tn!("Counting") => counting.clone(), tn!("Summing") => summing },
+        codex_libs: small_bset![libs::success()],
+        api_libs: none!(),
+        types: types.type_system(),
+    };
+    let articles = Issuer::new(codex(), semantics)
+        .unwrap()
+        .start_issue_testnet("setup", Consensus::None)
+        .append("_parties", svnum!(0u64), Some(ston!(name "alice", identity "Alice Wonderland")))
+        .finish("ProjectedDAO", 1732529307);
+
+    let projected = articles.project_api(Some(&tn!("Counting"))).unwrap();
+    projected.semantics().check(projected.codex()).unwrap();
+    assert_eq!(projected.custom_apis().collect::<Vec<_>>(), vec![(&tn!("Counting"), &counting)]);
+    assert_eq!(projected.default_api(), articles.default_api());
+    assert_eq!(projected.contract_id(), articles.contract_id());
+    assert_ne!(projected.articles_id(), articles.articles_id());
+    assert!(projected.types().len() <= articles.types().len());
+    for api in projected.apis() {
+        for glob in api.global.values() {
+            assert!(projected.types().get(glob.sem_id).is_some());
+        }
+    }
+    assert!(projected.types().get(types.get("DAO.Party")).is_some());
+
+    // Raw state and aggregators referencing types must work with the projected types
+    let mut state = EffectiveState::with_articles(&projected).unwrap();
+    state.recompute(projected.semantics());
+    let aggregate = |name: &'static str| {
+        counting.aggregators[&vname!(name)].aggregate(
+            &state.main.global,
+            &state.main.aggregated,
+            &projected.semantics().api_libs,
+            projected.types(),
+        )
+    };
+    assert_eq!(aggregate("hasBob"), Some(StrictVal::bool(false)));
+    assert_eq!(aggregate("hasAlice"), Some(StrictVal::bool(true)));
+    assert_eq!(aggregate("onlyAlice"), Some(StrictVal::bool(true)));
+    let parties = state.global_ref("_parties").unwrap();
+    assert_eq!(
+        parties
+            .values()
+            .map(|atom| atom.unverified.clone())
+            .collect::<Vec<_>>(),
+        vec![Some(ston!(name "alice", identity "Alice Wonderland"))]
+    );
+
+    let default_only = articles.project_api(None).unwrap();
+    assert_eq!(default_only.custom_apis().count(), 0);
+
+    assert_eq!(articles.project_api(Some(&tn!("Unknown"))).unwrap_err(), SemanticError::UnknownApi(tn!("Unknown")));
+}
+
+#[test]
+fn collect_libs() {
+    let dep = libs::success();
+    let mut lib = dep.clone();
+    lib.data = small_blob!(0xCA);
+    lib.libs = tiny_bset![dep.lib_id()];
+    let mut unrelated = dep.clone();
+    unrelated.data = small_blob!(0xFE);
+
+    let mut api = api();
+    api.owned.get_mut(&vname!("signers")).unwrap().builder = StateBuilder::AluVM(LibSite::new(lib.lib_id(), 0));
+
+    let available = [unrelated, lib.clone(), dep.clone()];
+    let collected = Semantics::collect_libs([&api], &available).unwrap();
+    assert_eq!(collected, small_bset![lib.clone(), dep.clone()]);
+
+    let err = Semantics::collect_libs([&api], &[lib]).unwrap_err();
+    assert_eq!(err, SemanticError::MissedApiLib(dep.lib_id()));
+}
+
 #[test]
 fn main() {
     let types = stl::DaoTypes::new();
@@ -202,6 +602,13 @@ fn main() {
     fs::create_dir_all(contract_path).expect("Unable to create a contract folder");
     let mut ledger = LedgerDir::new(articles.clone(), contract_path.to_path_buf()).expect("Can't issue contract");
 
+    let parties = ledger.state().global_full("_parties").collect::<Vec<_>>();
+    assert_eq!(parties.len(), 3);
+    assert!(parties.iter().all(|(addr, _, _)| addr.opid == opid));
+    let bob = ston!(name "bob", identity "Bob Capricorn");
+    assert!(parties.contains(&(CellAddr::new(opid, 1), &svnum!(1u64), Some(&bob))));
+    assert_eq!(ledger.state().global_full("_unknown").count(), 0);
+
     // Proposing vote
     let votings = ledger
         .start_deed("proposal")
@@ -245,6 +652,55 @@ fn main() {
         .commit()
         .unwrap();
 
+    let (alice_vote, _) = ledger.operations().nth(1).unwrap();
+    let (bob_vote, _) = ledger.operations().nth(2).unwrap();
+    assert_eq!(ledger.read_conflicts(alice_vote, bob_vote), bset![CellAddr::new(votings, 0)]);
+    assert!(ledger.read_conflicts(alice_vote, votings).is_empty());
+    assert!(ledger.read_conflicts(opid, bob_vote).is_empty());
+    assert_eq!(ledger.total_burned("signers"), None);
+
+    let log = ledger.activity_log().collect::<Vec<_>>();
+    let methods = log
+        .iter()
+        .map(|entry| entry.method.clone().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(methods, [
+        vname!("setup"),
+        vname!("proposal"),
+        vname!("castVote"),
+        vname!("castVote"),
+        vname!("castVote")
+    ]);
+    assert_eq!(log[0].opid, opid);
+    assert_eq!(log[0].global.len(), 3);
+    assert_eq!(log[1].opid, votings);
+    assert_eq!(log[1].global, vec![(CellAddr::new(votings, 0), vname!("_votings"), StateAtom {
+        verified: svnum!(100u64),
+        unverified: Some(ston!(title "Is Alice on duty today?", text "Vote 'pro' if Alice should be on duty today")),
+    })]);
+    assert!(log[1].owned.is_empty() && log[1].consumed.is_empty());
+    for (party, entry) in log[2..].iter().enumerate() {
+        let party = party as u64;
+        let vote = if party == 0 { svenum!("contra") } else { svenum!("pro") };
+        let vote =
+            ston!(voteId StrictVal::newtype(svnum!(100u64)), vote vote, partyId StrictVal::newtype(svnum!(party)));
+        assert_eq!(entry.global, vec![(CellAddr::new(entry.opid, 0), vname!("_votes"), StateAtom::new_verified(vote))]);
+        assert_eq!(entry.owned.len(), 1);
+        assert_eq!(entry.owned[0].1, vname!("signers"));
+        assert_eq!(entry.owned[0].2.data, svnum!(party));
+        assert_eq!(entry.consumed.len(), 1);
+        let (addr, name, cell) = &entry.consumed[0];
+        assert_eq!((*addr, name, &cell.data), (CellAddr::new(opid, party as u16), &vname!("signers"), &svnum!(party)));
+    }
+
+    let snapshot = serde_json::to_value(ledger.state().to_snapshot()).unwrap();
+    assert_eq!(snapshot["global"]["_votings"].as_object().unwrap().len(), 1);
+    assert_eq!(snapshot["global"]["_votes"].as_object().unwrap().len(), 3);
+    assert_eq!(snapshot["aggregated"]["votingCount"], 1);
+    assert_eq!(snapshot["aggregated"]["votings"].as_array().unwrap().len(), 1);
+    assert_eq!(snapshot["aggregated"]["votes"].as_array().unwrap().len(), 3);
+    assert!(snapshot["owned"]["signers"].is_object());
+
     let StrictVal::Map(votings) = ledger.state().read("votings") else {
         panic!("invalid data")
     };
@@ -258,6 +714,42 @@ fn main() {
         println!("- {vote}");
     }
 
+    // Bob's vote is "pro"; Alice has voted "contra", so not all the votes are the same
+    let pro = stl::CastVote {
+        vote_id: 100.into(),
+        vote: stl::Vote::Pro,
+        party_id: 1.into(),
+    };
+    let mut data = Vec::new();
+    pro.strict_encode(StrictWriter::with(StreamWriter::new::<{ usize::MAX }>(&mut data)))
+        .unwrap();
+    let pro = TinyBlob::from_checked(data);
+    let (state, sys) = (&ledger.state().main, types.type_system());
+    let any = SubAggregator::Any(vname!("_votes"), types.get("DAO.CastVote"), pro.clone());
+    assert_eq!(any.aggregate(&state.global, &state.aggregated, &sys), Some(StrictVal::bool(true)));
+    let all = SubAggregator::All(vname!("_votes"), types.get("DAO.CastVote"), pro);
+    assert_eq!(all.aggregate(&state.global, &state.aggregated, &sys), Some(StrictVal::bool(false)));
+
+    // Counting only "pro" votes, whichever way the enum tag is represented
+    let votes = state.global["_votes"]
+        .iter()
+        .map(|(addr, atom)| (*addr, StateAtom::new_verified(atom.verified.unwrap_struct("vote").clone())))
+        .collect::<BTreeMap<_, _>>();
+    let ords = votes
+        .iter()
+        .map(|(addr, atom)| {
+            (*addr, StateAtom::new_verified(StrictVal::enumer(atom.verified.unwrap_enum::<stl::Vote>() as u8)))
+        })
+        .collect::<BTreeMap<_, _>>();
+    let global = bmap! { vname!("votes") => votes, vname!("ords") => ords };
+    let count = |name: &'static str, variant: &'static str, ord: stl::Vote| {
+        SubAggregator::CountWhereTag(vname!(name), vname!(variant), ord as u8).aggregate(&global, &none!(), &sys)
+    };
+    assert_eq!(count("votes", "pro", stl::Vote::Pro), Some(svnum!(2u64)));
+    assert_eq!(count("votes", "contra", stl::Vote::Contra), Some(svnum!(1u64)));
+    assert_eq!(count("ords", "pro", stl::Vote::Pro), Some(svnum!(2u64)));
+    assert_eq!(count("nonExisting", "pro", stl::Vote::Pro), Some(svnum!(0u64)));
+
     // Now anybody accessing this file can figure out who is on duty today, by the decision of DAO.
     let deeds_path = Path::new("tests/data/voting.deeds");
     if deeds_path.exists() {
@@ -273,7 +765,7 @@ fn main() {
         fs::remove_dir_all(contract_path).expect("Unable to remove a contract file");
     }
     fs::create_dir_all(contract_path).expect("Unable to create a contract folder");
-    let mut ledger2 = LedgerDir::new(articles, contract_path.to_path_buf()).expect("Can't issue contract");
+    let mut ledger2 = LedgerDir::new(articles.clone(), contract_path.to_path_buf()).expect("Can't issue contract");
     ledger2
         .accept_from_file(deeds_path, |_, _, _| Result::<_, Infallible>::Ok(()))
         .unwrap();
@@ -281,6 +773,34 @@ fn main() {
     let deeds_path = "tests/data/votings-all.deeds";
     fs::remove_file(deeds_path).ok();
     ledger2.export_all_to_file(deeds_path).unwrap();
+
+    // A minimal export doesn't include votes of other parties, but still can be validated
+    let mut full = Vec::new();
+    ledger
+        .export([alice_auth2], StrictWriter::with(StreamWriter::new::<{ usize::MAX }>(&mut full)))
+        .unwrap();
+    let mut minimal = Vec::new();
+    ledger
+        .export_minimal([alice_auth2], StrictWriter::with(StreamWriter::new::<{ usize::MAX }>(&mut minimal)))
+        .unwrap();
+    assert!(minimal.len() < full.len());
+
+    let contract_path = Path::new("tests/data/WonderlandDAO-3.contract");
+    if contract_path.exists() {
+        fs::remove_dir_all(contract_path).expect("Unable to remove a contract file");
+    }
+    fs::create_dir_all(contract_path).expect("Unable to create a contract folder");
+    let mut ledger3 = LedgerDir::new(articles, contract_path.to_path_buf()).expect("Can't issue contract");
+    ledger3
+        .accept(
+            &mut StrictReader::with(StreamReader::cursor::<{ usize::MAX }>(minimal)),
+            |_, _, _| Result::<_, Infallible>::Ok(()),
+        )
+        .unwrap();
+    let alice_addr = ledger.state().addr(alice_auth2);
+    assert!(ledger3.is_valid(alice_addr.opid));
+    assert_eq!(ledger3.state().main.global["_votings"].len(), 1);
+    assert_eq!(ledger3.state().main.global["_votes"].len(), 1);
 }
 
 mod libs {
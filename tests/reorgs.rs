@@ -29,13 +29,21 @@ extern crate amplify;
 extern crate strict_types;
 
 use std::collections::BTreeSet;
-use std::fs;
+use std::convert::Infallible;
 use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+use std::{fs, io, slice, thread};
 
-use aluvm::{CoreConfig, LibSite};
+use aluvm::{CoreConfig, Lib, LibSite};
 use amplify::num::u256;
-use commit_verify::{Digest, Sha256};
-use hypersonic::{Api, OwnedApi};
+use amplify::MultiError;
+use chrono::Utc;
+use commit_verify::{CommitmentId, Digest, DigestExt, Sha256, StrictHash};
+use hypersonic::{
+    peek_deeds_checksum, verify_operation, AcceptError, Api, CallParams, ChunkBuffer, GlobalApi, IssueError, Ledger,
+    OwnedApi, Satisfaction, Stock, DEEDS_VERSION,
+};
 use indexmap::{indexset, IndexSet};
 use petgraph::dot::{Config, Dot};
 use petgraph::graph::EdgeReference;
@@ -43,15 +51,24 @@ use petgraph::prelude::NodeIndex;
 use petgraph::Graph;
 use rand::rng;
 use rand::seq::SliceRandom;
-use sonic_persist_fs::LedgerDir;
-use sonicapi::{IssueParams, Issuer, Semantics, StateArithm, StateBuilder, StateConvertor};
+use sonic_callreq::{CallRequest, CallScope, CallState, Layer1};
+use sonic_persist_fs::{LedgerDir, StockFs};
+use sonicapi::{
+    Aggregator, Articles, CoreParams, CoreParamsError, IssueParams, Issuer, NamedState, OpBuilder, RawBuilder,
+    RawConvertor, SemanticError, Semantics, SigBlob, StateArithm, StateBuilder, StateConvertor, SubAggregator,
+};
 use sonix::dump_ledger;
-use strict_types::SemId;
+use strict_encoding::{StreamReader, StreamWriter, StrictDecode, StrictEncode, StrictReader, StrictWriter};
+use strict_types::{SemId, StrictVal};
 use ultrasonic::aluvm::FIELD_ORDER_SECP;
-use ultrasonic::{AuthToken, CellAddr, Codex, Consensus, Identity, Operation};
+use ultrasonic::{
+    AuthToken, CallError, CellAddr, Codex, Consensus, ContractId, Genesis, Identity, Input, Issue, Operation,
+    StateValue,
+};
 
 mod libs {
     use aluvm::{aluasm, Lib};
+    use ultrasonic::uasm;
 
     pub fn success() -> Lib {
         let code = aluasm! {
@@ -59,9 +76,29 @@ mod libs {
         };
         Lib::assemble(&code).unwrap()
     }
+
+    /// Verifiers accepting everything at offset 0, and a lock script at offset 1.
+    ///
+    /// The VM has no hashing instructions, thus the lock script uses squaring in the field as the
+    /// hash function: it checks that the square of the input witness value (following its state
+    /// type in `EA`) matches the lock data.
+    pub fn hash_lock() -> Lib {
+        let code = uasm! {
+            stop;
+            ldi     witness;
+            mov     EE, EB;
+            mul     EE, EB;
+            ldi     lock;
+            put     E8, 1;
+            eq      EA, EE;
+            chk     CO;
+        };
+        Lib::assemble(&code).unwrap()
+    }
 }
 
 mod stl {
+    use amplify::confinement::TinyString;
     use strict_types::stl::std_stl;
     use strict_types::{LibBuilder, SemId, SymbolicSys, SystemBuilder, TypeLib, TypeSystem};
 
@@ -75,9 +112,16 @@ mod stl {
     #[strict_type(lib = LIB_NAME_FUNGIBLE)]
     pub struct Amount(u64);
 
+    #[derive(Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Display, From)]
+    #[display(inner)]
+    #[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+    #[strict_type(lib = LIB_NAME_FUNGIBLE)]
+    pub struct Ticker(TinyString);
+
     pub fn stl() -> TypeLib {
         LibBuilder::with(libname!(LIB_NAME_FUNGIBLE), [std_stl().to_dependency_types()])
             .transpile::<Amount>()
+            .transpile::<Ticker>()
             .compile()
             .expect("invalid Fungible type library")
     }
@@ -151,8 +195,9 @@ fn api() -> Api {
                 sem_id: types.get("Fungible.Amount"),
                 arithmetics: StateArithm::Fungible,
                 convertor: StateConvertor::TypedEncoder(u256::ZERO),
+                fallback_convertors: none!(),
                 builder: StateBuilder::TypedEncoder(u256::ZERO),
-                witness_sem_id: SemId::unit(),
+                witness_sem_id: types.get("Fungible.Amount"),
                 witness_builder: StateBuilder::TypedEncoder(u256::ZERO),
             }
         },
@@ -165,20 +210,29 @@ fn api() -> Api {
     }
 }
 
-fn setup(name: &str) -> LedgerDir {
+fn semantics(api: Api) -> Semantics {
     let types = stl::FungibleTypes::new();
-    let codex = codex();
-    let api = api();
-
-    let semantics = Semantics {
+    Semantics {
         version: 0,
         default: api,
         custom: none!(),
         codex_libs: small_bset![libs::success()],
         api_libs: none!(),
         types: types.type_system(),
-    };
-    let issuer = Issuer::new(codex, semantics).unwrap();
+    }
+}
+
+fn contract_dir(name: &str) -> PathBuf {
+    let contract_path = PathBuf::from(format!("tests/data/{name}.contract"));
+    if contract_path.exists() {
+        fs::remove_dir_all(&contract_path).expect("Unable to remove a contract file");
+    }
+    fs::create_dir_all(&contract_path).expect("Unable to create a contract folder");
+    contract_path
+}
+
+fn setup(name: &str) -> LedgerDir {
+    let issuer = Issuer::new(codex(), semantics(api())).unwrap();
     issuer.save("tests/data/Test.issuer").ok();
 
     let seed = &[0xCA; 30][..];
@@ -198,11 +252,7 @@ fn setup(name: &str) -> LedgerDir {
     let articles = issuer.issue(issue);
     let opid = articles.genesis_opid();
 
-    let contract_path = PathBuf::from(format!("tests/data/{name}.contract"));
-    if contract_path.exists() {
-        fs::remove_dir_all(&contract_path).expect("Unable to remove a contract file");
-    }
-    fs::create_dir_all(&contract_path).expect("Unable to create a contract folder");
+    let contract_path = contract_dir(name);
     let mut ledger = LedgerDir::new(articles, contract_path).expect("Can't issue a contract");
 
     let owned = &ledger.state().main.owned;
@@ -280,6 +330,131 @@ fn graph(name: &str, ledger: &LedgerDir) {
     fs::write(format!("tests/data/{name}.dot"), graph).unwrap();
 }
 
+#[test]
+fn issue_testnet() {
+    let semantics = semantics(api());
+
+    let contract_path = contract_dir("OneCall");
+    let auth = AuthToken::from([0xAA; 30]);
+    let ledger = Ledger::<StockFs>::issue_testnet(
+        codex(),
+        semantics.clone(),
+        "issue",
+        Consensus::None,
+        contract_path,
+        |issue| {
+            issue
+                .assign("amount", auth, svnum!(1000u64), None)
+                .finish("OneCall", 1732529307)
+        },
+    )
+    .unwrap();
+    assert!(ledger.articles().issue().meta.testnet);
+    let owned = &ledger.state().main.owned["amount"];
+    assert_eq!(owned.len(), 1);
+    assert_eq!(owned.get(&CellAddr::new(ledger.articles().genesis_opid(), 0)), Some(&svnum!(1000u64)));
+
+    let mut codex = codex();
+    codex.name = tiny_s!("OtherCodex");
+    let err = Ledger::<StockFs>::issue_testnet(codex, semantics, "issue", Consensus::None, default!(), |issue| {
+        issue.finish("OneCall", 1732529307)
+    })
+    .unwrap_err();
+    assert!(matches!(err, MultiError::A(IssueError::Semantics(SemanticError::CodexMismatch))));
+}
+
+#[test]
+fn ticker() {
+    let types = stl::FungibleTypes::new();
+    let mut api = api();
+    api.global
+        .insert(vname!("ticker"), GlobalApi {
+            published: true,
+            sem_id: types.get("Fungible.Ticker"),
+            convertor: StateConvertor::TypedEncoder(u256::ONE),
+            fallback_convertors: none!(),
+            builder: StateBuilder::TypedEncoder(u256::ONE),
+            raw_convertor: RawConvertor::StrictDecode(SemId::unit()),
+            raw_builder: RawBuilder::StrictEncode(SemId::unit()),
+        })
+        .unwrap();
+    let semantics = semantics(api);
+
+    let contract_path = contract_dir("Ticker");
+    let ledger =
+        Ledger::<StockFs>::issue_testnet(codex(), semantics, "issue", Consensus::None, contract_path, |issue| {
+            issue
+                .append("ticker", svstr!("DEMO"), None)
+                .assign("amount", AuthToken::from([0xAA; 30]), svnum!(1000u64), None)
+                .finish("Ticker", 1732529307)
+        })
+        .unwrap();
+    assert_eq!(ledger.ticker().as_deref(), Some("DEMO"));
+
+    let ledger = setup("NoTicker");
+    assert_eq!(ledger.ticker(), None);
+}
+
+#[test]
+fn aggregated_delta() {
+    let types = stl::FungibleTypes::new();
+    let mut api = api();
+    api.global
+        .insert(vname!("_supply"), GlobalApi {
+            published: true,
+            sem_id: types.get("Fungible.Amount"),
+            convertor: StateConvertor::TypedEncoder(u256::ONE),
+            fallback_convertors: none!(),
+            builder: StateBuilder::TypedEncoder(u256::ONE),
+            raw_convertor: RawConvertor::StrictDecode(SemId::unit()),
+            raw_builder: RawBuilder::StrictEncode(SemId::unit()),
+        })
+        .unwrap();
+    api.aggregators
+        .insert(vname!("supply"), Aggregator::Take(SubAggregator::SumOrDefault(vname!("_supply"))))
+        .unwrap();
+    let semantics = semantics(api);
+
+    let contract_path = contract_dir("AggregatedDelta");
+    let mut ledger =
+        Ledger::<StockFs>::issue_testnet(codex(), semantics, "issue", Consensus::None, contract_path, |issue| {
+            issue
+                .append("_supply", svnum!(1000u64), None)
+                .assign("amount", AuthToken::from([0xAA; 30]), svnum!(1000u64), None)
+                .finish("AggregatedDelta", 1732529307)
+        })
+        .unwrap();
+    let genesis = CellAddr::new(ledger.articles().genesis_opid(), 0);
+
+    // Height 1: transfer not changing the supply
+    let transfer = ledger
+        .start_deed("transfer")
+        .using(genesis)
+        .assign("amount", AuthToken::from([0xBB; 30]), svnum!(1000u64), None)
+        .commit()
+        .unwrap();
+    // Height 2: mint of 500 more tokens; it spends the transfer output, so it always follows the
+    // transfer in the canonical order
+    ledger
+        .start_deed("issue")
+        .using(CellAddr::new(transfer, 0))
+        .append("_supply", svnum!(500u64), None)
+        .assign("amount", AuthToken::from([0xCC; 30]), svnum!(500u64), None)
+        .commit()
+        .unwrap();
+    assert_eq!(ledger.state().read("supply"), &svnum!(1500u64));
+
+    assert_eq!(ledger.state_at(0).unwrap().read("supply"), &svnum!(1000u64));
+    assert_eq!(ledger.state_at(2).unwrap().main, ledger.state().main);
+    assert!(ledger.state_at(3).is_none());
+
+    assert_eq!(ledger.aggregated_delta("supply", 1, 2), Some(svnum!(500u64)));
+    assert_eq!(ledger.aggregated_delta("supply", 0, 1), Some(svnum!(0u64)));
+    assert_eq!(ledger.aggregated_delta("supply", 2, 0), Some(StrictVal::num(-500i64)));
+    assert_eq!(ledger.aggregated_delta("supply", 0, 3), None);
+    assert_eq!(ledger.aggregated_delta("unknown", 0, 2), None);
+}
+
 #[test]
 fn no_reorgs() {
     setup("NoReorgs");
@@ -403,3 +578,1200 @@ fn partial_forward() {
     graph("PartialForward", &ledger);
     assert_eq!(ledger.state().main, mid_state);
 }
+
+#[test]
+fn affected_auths() {
+    let mut ledger = setup("AffectedAuths");
+    let genesis = ledger.articles().genesis().clone();
+    let (mid_opid, _) = ledger.operations().nth(50).unwrap();
+
+    let mut expected = BTreeSet::new();
+    for opid in ledger.descendants([mid_opid]) {
+        let op = ledger.operation(opid);
+        expected.extend(op.destructible_out.iter().map(|cell| cell.auth));
+        for input in &op.destructible_in {
+            let outputs = if input.addr.opid == genesis.opid(ledger.contract_id()) {
+                genesis.destructible_out.clone()
+            } else {
+                ledger.operation(input.addr.opid).destructible_out
+            };
+            expected.insert(outputs[input.addr.pos as usize].auth);
+        }
+    }
+
+    assert_eq!(ledger.affected_auths([mid_opid]), expected);
+    ledger.rollback([mid_opid]).unwrap();
+    assert_eq!(ledger.affected_auths([mid_opid]), expected);
+}
+
+#[test]
+fn all_auths() {
+    let ledger = setup("AllAuths");
+    let unspent = ledger
+        .state()
+        .raw
+        .owned
+        .values()
+        .map(|cell| cell.auth)
+        .collect::<BTreeSet<_>>();
+    let all = ledger.all_auths();
+    assert!(all.is_superset(&unspent));
+    assert!(all.len() > unspent.len());
+    for cell in &ledger.articles().genesis().destructible_out {
+        assert!(all.contains(&cell.auth));
+    }
+}
+
+#[test]
+fn output_at() {
+    let ledger = setup("OutputAt");
+    let (opid, op) = ledger.operations().nth(10).unwrap();
+
+    let addr = CellAddr::new(opid, 1);
+    let view = ledger.output_at(addr).unwrap();
+    assert_eq!(view.addr, addr);
+    assert_eq!(view.global, None);
+    let (name, cell) = view.owned.unwrap();
+    assert_eq!(name, vname!("amount"));
+    assert_eq!(cell.auth, op.destructible_out[1].auth);
+    assert_eq!(cell.lock, None);
+    assert!(matches!(cell.data, StrictVal::Number(_)));
+
+    let genesis_opid = ledger.articles().genesis_opid();
+    let view = ledger.output_at(CellAddr::new(genesis_opid, 19)).unwrap();
+    assert_eq!(view.owned.unwrap().1.data, svnum!(100u64));
+
+    assert_eq!(ledger.output_at(CellAddr::new(opid, 2)), None);
+    assert_eq!(ledger.output_at(CellAddr::new(genesis_opid, 20)), None);
+}
+
+#[test]
+fn invalid_operations() {
+    let mut ledger = setup("InvalidOperations");
+    assert_eq!(ledger.invalid_operations().count(), 0);
+
+    let (mid_opid, _) = ledger.operations().nth(50).unwrap();
+    let descendants = ledger.descendants([mid_opid]).collect::<BTreeSet<_>>();
+    ledger.rollback([mid_opid]).unwrap();
+    let invalid = ledger
+        .invalid_operations()
+        .map(|(opid, _)| opid)
+        .collect::<BTreeSet<_>>();
+    assert_eq!(invalid, descendants);
+
+    ledger.forward([mid_opid]).unwrap();
+    assert_eq!(ledger.invalid_operations().count(), 0);
+}
+
+#[test]
+fn invalidation_reason() {
+    let mut ledger = setup("InvalidationReason");
+    let (mid_opid, _) = ledger.operations().nth(50).unwrap();
+    let reason = tiny_s!("rolled back due to reorg at height 800000");
+    assert_eq!(ledger.invalidation_reason(mid_opid), None);
+
+    let descendants = ledger.descendants([mid_opid]).collect::<Vec<_>>();
+    ledger
+        .rollback_with_reason([mid_opid], reason.clone())
+        .unwrap();
+
+    let path = ledger.path().to_path_buf();
+    drop(ledger);
+    let mut ledger = LedgerDir::load(path).unwrap();
+    for opid in &descendants {
+        assert_eq!(ledger.invalidation_reason(*opid), Some(reason.clone()));
+    }
+
+    ledger.forward([mid_opid]).unwrap();
+    assert_eq!(ledger.invalidation_reason(mid_opid), None);
+
+    // Rolling back without a reason discards the previous one
+    ledger.rollback([mid_opid]).unwrap();
+    assert!(!ledger.is_valid(mid_opid));
+    assert_eq!(ledger.invalidation_reason(mid_opid), None);
+}
+
+#[test]
+fn state_root() {
+    let ledger = setup("StateRoot");
+    let root = ledger.state().state_root();
+    assert_eq!(ledger.state().state_root(), root);
+
+    let path = ledger.path().to_path_buf();
+    drop(ledger);
+    let mut ledger = LedgerDir::load(path).unwrap();
+    assert_eq!(ledger.state().state_root(), root);
+
+    let mut iter = ledger.state().main.owned["amount"].keys().copied();
+    let (first, second) = (iter.next().unwrap(), iter.next().unwrap());
+    ledger
+        .start_deed("transfer")
+        .using(first)
+        .using(second)
+        .assign("amount", AuthToken::from([0xAB; 30]), svnum!(182u64), None)
+        .commit()
+        .unwrap();
+    assert_ne!(ledger.state().state_root(), root);
+}
+
+#[test]
+fn batch_deeds() {
+    let mut ledger = setup("BatchDeeds");
+    let mut iter = ledger.state().main.owned["amount"].keys().copied();
+    let (first, second) = (iter.next().unwrap(), iter.next().unwrap());
+    let root = ledger.state().state_root();
+
+    // The second deed spends an output which the first deed doesn't produce, so the whole batch
+    // must fail leaving the ledger intact.
+    let mut batch = ledger.start_batch();
+    let opid1 = batch.deed("transfer", |deed| {
+        deed.using(first)
+            .using(second)
+            .assign("amount", AuthToken::from([0xA1; 30]), svnum!(182u64), None)
+    });
+    batch.deed("transfer", |deed| {
+        deed.using(CellAddr::new(opid1, 1))
+            .assign("amount", AuthToken::from([0xA2; 30]), svnum!(182u64), None)
+    });
+    assert!(batch.commit_all().is_err());
+    assert!(!ledger.has_operation(opid1));
+    assert!(!ledger.is_valid(opid1));
+    assert_eq!(ledger.state().state_root(), root);
+
+    let mut batch = ledger.start_batch();
+    let opid1 = batch.deed("transfer", |deed| {
+        deed.using(first)
+            .using(second)
+            .assign("amount", AuthToken::from([0xA1; 30]), svnum!(182u64), None)
+    });
+    let opid2 = batch.deed("transfer", |deed| {
+        deed.using(CellAddr::new(opid1, 0))
+            .assign("amount", AuthToken::from([0xA2; 30]), svnum!(182u64), None)
+    });
+    assert_eq!(batch.commit_all().unwrap(), vec![opid1, opid2]);
+    assert!(ledger.is_valid(opid1));
+    assert!(ledger.is_valid(opid2));
+    let owned = &ledger.state().main.owned["amount"];
+    assert!(!owned.contains_key(&CellAddr::new(opid1, 0)));
+    assert_eq!(owned[&CellAddr::new(opid2, 0)], svnum!(182u64));
+}
+
+#[test]
+fn hash_lock() {
+    let lib = libs::hash_lock();
+    let lib_id = lib.lib_id();
+    let mut codex = codex();
+    codex.verifiers = tiny_bmap! {
+        0 => LibSite::new(lib_id, 0),
+        1 => LibSite::new(lib_id, 0),
+    };
+    let mut api = api();
+    api.codex_id = codex.codex_id();
+    let semantics = Semantics { codex_libs: small_bset![lib], ..semantics(api) };
+
+    let contract_path = contract_dir("HashLock");
+    let mut ledger =
+        Ledger::<StockFs>::issue_testnet(codex, semantics, "issue", Consensus::None, contract_path, |issue| {
+            issue
+                .assign("amount", AuthToken::from([0xAA; 30]), svnum!(100u64), None)
+                .finish("HashLock", 1732529307)
+        })
+        .unwrap();
+    let addr = CellAddr::new(ledger.articles().genesis_opid(), 0);
+
+    let preimage = 0xC0FFEEu64;
+    let mut digest = [0u8; 32];
+    digest[..16].copy_from_slice(&(preimage as u128 * preimage as u128).to_le_bytes());
+    let lock = Satisfaction::hash_lock(digest, LibSite::new(lib_id, 1));
+
+    let auth = AuthToken::from([0xAB; 30]);
+    let opid = ledger
+        .start_deed("transfer")
+        .using(addr)
+        .assign("amount", auth, svnum!(91u64), Some(lock))
+        .commit()
+        .unwrap();
+    let locked = CellAddr::new(opid, 0);
+    assert_eq!(ledger.output_at(locked).unwrap().owned.unwrap().1.lock, Some(lock));
+
+    // A wrong preimage is rejected by the lock script
+    let mut core = CoreParams::new("transfer");
+    core.push_owned_unlocked("amount", AuthToken::from([0xAC; 30]), svnum!(91u64));
+    let params = |preimage: u64| CallParams {
+        core: core.clone(),
+        using: bmap! { locked => Some(Satisfaction::preimage(vname!("amount"), svnum!(preimage))) },
+        reading: none!(),
+    };
+    let err = ledger.call(params(preimage + 1)).unwrap_err();
+    assert!(matches!(err, MultiError::A(AcceptError::Verify(CallError::Lock(_)))), "{err:?}");
+    assert_eq!(ledger.spent_by(locked), None);
+
+    let opid = ledger.call(params(preimage)).unwrap();
+
+    assert_eq!(ledger.spent_by(locked), Some(opid));
+    let op = ledger.operation(opid);
+    assert_eq!(op.destructible_in.len(), 1);
+    assert_ne!(op.destructible_in[0].witness, StateValue::None);
+    assert_eq!(ledger.input_witness(opid, 0), Some(svnum!(preimage)));
+    assert_eq!(ledger.input_witness(opid, 1), None);
+    assert_eq!(ledger.input_witness(locked.opid, 0), None);
+}
+
+#[test]
+fn diff_semantics() {
+    let mut semantics = semantics(api());
+    let issuer = Issuer::new(codex(), semantics.clone()).unwrap();
+    let params = IssueParams::new_testnet(issuer.codex_id(), "DiffTest", Consensus::None);
+    let articles = issuer.issue(params);
+    assert!(articles.diff_semantics(&articles).is_empty());
+
+    let mut custom = api();
+    custom.verifiers.insert(vname!("burn"), 1).unwrap();
+    semantics.custom.insert(tn!("Burnable"), custom).unwrap();
+    semantics.version = 1;
+    let upgraded =
+        Articles::with(semantics, articles.issue().clone(), None, |_, _, _| Result::<_, Infallible>::Ok(())).unwrap();
+
+    let diff = articles.diff_semantics(&upgraded);
+    assert_eq!(diff.added_apis, bset![tn!("Burnable")]);
+    assert!(diff.removed_apis.is_empty());
+    assert!(diff.default.is_empty());
+    assert!(diff.changed_apis.is_empty());
+
+    let diff = upgraded.diff_semantics(&articles);
+    assert!(diff.added_apis.is_empty());
+    assert_eq!(diff.removed_apis, bset![tn!("Burnable")]);
+}
+
+#[test]
+fn articles_signature() {
+    let semantics = semantics(api());
+    let issuer = Issuer::new(codex(), semantics.clone()).unwrap();
+    let params = IssueParams::new_testnet(issuer.codex_id(), "SigTest", Consensus::None);
+    let articles = issuer.issue(params);
+    assert!(!articles.is_signed());
+    assert_eq!(articles.signature(), None);
+
+    let mut issue = articles.issue().clone();
+    issue.meta.issuer = Identity::from("ssi:alice");
+    let sig = SigBlob::from_slice_checked(*b"alice");
+    let signed = Articles::with(semantics, issue, Some(sig.clone()), |_, identity, sig| {
+        if identity == &Identity::from("ssi:alice") && sig.as_slice() == b"alice" {
+            Ok(())
+        } else {
+            Err(())
+        }
+    })
+    .unwrap();
+    assert!(signed.is_signed());
+    assert_eq!(signed.signature(), Some((&Identity::from("ssi:alice"), &sig)));
+}
+
+#[test]
+fn method_call_id_conflict() {
+    let mut custom = api();
+    custom.verifiers.insert(vname!("transfer"), 0).unwrap();
+    let mut semantics = semantics(api());
+    semantics.custom.insert(tn!("Conflicting"), custom).unwrap();
+    assert_eq!(semantics.check(&codex()), Err(SemanticError::MethodCallIdConflict(vname!("transfer"))));
+
+    let custom = semantics.custom.get_mut(&tn!("Conflicting")).unwrap();
+    custom.verifiers.insert(vname!("transfer"), 1).unwrap();
+    custom.verifiers.insert(vname!("burn"), 0).unwrap();
+    semantics.check(&codex()).unwrap();
+}
+
+#[test]
+fn invalid_default_call() {
+    let mut semantics = semantics(api());
+    semantics.default.default_call = Some(CallState::with("transfer", "amount"));
+    semantics.check(&codex()).unwrap();
+
+    semantics.default.default_call = Some(CallState::with("mint", "amount"));
+    assert_eq!(semantics.check(&codex()), Err(SemanticError::InvalidDefaultCall(vname!("mint"))));
+
+    semantics.default.default_call = Some(CallState::with("transfer", "unknown"));
+    assert_eq!(semantics.check(&codex()), Err(SemanticError::InvalidDefaultCall(vname!("transfer"))));
+}
+
+#[test]
+fn issue_from_snapshot() {
+    let semantics = semantics(api());
+    let issuer = Issuer::new(codex(), semantics).unwrap();
+
+    let owned = (1u8..=5)
+        .map(|no| NamedState::new_unlocked("amount", AuthToken::from([no; 30]), svnum!(no as u64 * 10)))
+        .collect::<Vec<_>>();
+
+    let mut fluent = issuer.clone().start_issue_testnet("issue", Consensus::None);
+    for NamedState { name, state } in owned.clone() {
+        fluent = fluent.assign(name, state.auth, state.data, state.lock);
+    }
+    let fluent = fluent.finish("Snapshot", 1732529307);
+
+    let snapshot = issuer
+        .start_issue_testnet("issue", Consensus::None)
+        .from_snapshot(none!(), owned)
+        .finish("Snapshot", 1732529307);
+
+    assert_eq!(snapshot.genesis(), fluent.genesis());
+    assert_eq!(snapshot.contract_id(), fluent.contract_id());
+}
+
+#[test]
+fn issue_streaming() {
+    let types = stl::FungibleTypes::new();
+    let mut api = api();
+    api.global
+        .insert(vname!("ticker"), GlobalApi {
+            published: true,
+            sem_id: types.get("Fungible.Ticker"),
+            convertor: StateConvertor::TypedEncoder(u256::ONE),
+            fallback_convertors: none!(),
+            builder: StateBuilder::TypedEncoder(u256::ONE),
+            raw_convertor: RawConvertor::StrictDecode(SemId::unit()),
+            raw_builder: RawBuilder::StrictEncode(SemId::unit()),
+        })
+        .unwrap();
+    let semantics = semantics(api);
+    let issuer = Issuer::new(codex(), semantics).unwrap();
+
+    let mut builder = issuer.clone().start_issue_testnet("issue", Consensus::None);
+    let mut streaming = issuer
+        .clone()
+        .start_issue_streaming("issue", Consensus::None, true);
+    for no in 0u16..500 {
+        let mut auth = [0u8; 30];
+        auth[..2].copy_from_slice(&no.to_le_bytes());
+        let auth = AuthToken::from(auth);
+        builder = builder.assign("amount", auth, svnum!(no as u64 * 10), None);
+        streaming = streaming.assign("amount", auth, svnum!(no as u64 * 10), None);
+    }
+    let articles = builder.finish("Streaming", 1732529307);
+    assert_eq!(articles.genesis().destructible_out.len(), 500);
+
+    assert_eq!(streaming.finish("Streaming", 1732529307), articles.contract_id());
+
+    // Merkle trees of all shapes must match the ones of the genesis operation
+    for len in [0u16, 1, 2, 3, 4, 5, 7, 8, 9, 15, 16, 17, 31, 32, 33, 63, 64, 65] {
+        let mut builder = issuer.clone().start_issue_testnet("issue", Consensus::None);
+        let mut streaming = issuer
+            .clone()
+            .start_issue_streaming("issue", Consensus::None, true);
+        for no in 0..len {
+            let mut auth = [0u8; 30];
+            auth[..2].copy_from_slice(&no.to_le_bytes());
+            let auth = AuthToken::from(auth);
+            builder = builder.assign("amount", auth, svnum!(no as u64), None);
+            streaming = streaming.assign("amount", auth, svnum!(no as u64), None);
+        }
+        // Use a different number of immutable outputs, so the trees are independent
+        for no in 0..len.div_ceil(2) {
+            builder = builder.append("ticker", svstr!(format!("T{no}")), None);
+            streaming = streaming.append("ticker", svstr!(format!("T{no}")), None);
+        }
+        let articles = builder.finish("Streaming", 1732529307);
+        assert_eq!(articles.genesis().destructible_out.len(), len as usize);
+        assert_eq!(articles.genesis().immutable_out.len(), len.div_ceil(2) as usize);
+        assert_eq!(streaming.finish("Streaming", 1732529307), articles.contract_id(), "{len} outputs");
+    }
+}
+
+#[test]
+fn codex_libs() {
+    let ledger = setup("CodexLibs");
+    let expected = libs::success().lib_id();
+    assert_eq!(ledger.codex_libs().map(Lib::lib_id).collect::<Vec<_>>(), vec![expected]);
+    assert_eq!(
+        ledger
+            .articles()
+            .codex_libs()
+            .map(Lib::lib_id)
+            .collect::<Vec<_>>(),
+        vec![expected]
+    );
+}
+
+#[test]
+fn codex_verifiers() {
+    let ledger = setup("CodexVerifiers");
+    let lib_id = libs::success().lib_id();
+    let verifiers = ledger.codex_verifiers();
+    assert_eq!(verifiers.len(), 2);
+    assert_eq!(verifiers.get(&0), Some(&LibSite::new(lib_id, 0)));
+    assert_eq!(verifiers.get(&1), Some(&LibSite::new(lib_id, 0)));
+    assert_eq!(verifiers.get(&ledger.articles().call_id("transfer")), Some(&LibSite::new(lib_id, 0)));
+}
+
+#[test]
+fn operation_method() {
+    let ledger = setup("OperationMethod");
+    let articles = ledger.articles();
+    let (addr, _) = ledger.state().main.owned["amount"]
+        .first_key_value()
+        .unwrap();
+
+    let transfer = OpBuilder::new(ledger.contract_id(), articles.call_id("transfer"))
+        .destroy(*addr)
+        .finalize();
+    assert_eq!(ledger.operation_method(&transfer), Some(vname!("transfer")));
+
+    let unknown = OpBuilder::new(ledger.contract_id(), 0xFF).finalize();
+    assert_eq!(ledger.operation_method(&unknown), None);
+}
+
+#[test]
+fn output_consumers() {
+    let ledger = setup("OutputConsumers");
+    let genesis_opid = ledger.articles().genesis_opid();
+    let consumers = ledger.output_consumers(genesis_opid);
+    assert_eq!(consumers.len(), 20);
+
+    let operations = ledger.operations().collect::<Vec<_>>();
+    for (opid, op) in &operations {
+        let consumers = ledger.output_consumers(*opid);
+        assert_eq!(consumers.len(), op.destructible_out.len());
+        for (pos, (addr, consumer)) in consumers.into_iter().enumerate() {
+            assert_eq!(addr, CellAddr::new(*opid, pos as u16));
+            assert!(consumer.readers.is_empty());
+            assert_eq!(consumer.spender, ledger.spent_by(addr));
+            if let Some(spender) = consumer.spender {
+                let spender = ledger.operation(spender);
+                assert!(spender
+                    .destructible_in
+                    .iter()
+                    .any(|input| input.addr == addr));
+            }
+        }
+    }
+
+    // All operations except the last round are fully spent
+    let spent = operations
+        .iter()
+        .filter(|(opid, _)| {
+            ledger
+                .output_consumers(*opid)
+                .iter()
+                .all(|(_, consumer)| consumer.spender.is_some())
+        })
+        .count();
+    assert_eq!(spent, 90);
+}
+
+#[test]
+fn dag_tips() {
+    let ledger = setup("DagTips");
+    let tips = ledger.dag_tips();
+    assert_eq!(tips.len(), 10);
+    let latest = ledger.state().main.owned["amount"]
+        .keys()
+        .map(|addr| addr.opid)
+        .collect::<BTreeSet<_>>();
+    assert_eq!(tips, latest);
+}
+
+#[test]
+fn active_operations() {
+    let ledger = setup("ActiveOperations");
+    let active = ledger.active_operations();
+    assert_eq!(active.len(), 10);
+    assert_eq!(active, ledger.dag_tips());
+    assert!(!active.contains(&ledger.articles().genesis_opid()));
+}
+
+#[test]
+fn depth() {
+    let ledger = setup("Depth");
+    let genesis_opid = ledger.articles().genesis_opid();
+    assert_eq!(ledger.depth(genesis_opid), 100);
+
+    let ops = ledger.operations().collect::<Vec<_>>();
+    let (first, _) = ops.first().unwrap();
+    assert!(ledger.depth(*first) >= 9);
+    for tip in ledger.dag_tips() {
+        assert_eq!(ledger.depth(tip), 0);
+    }
+}
+
+#[test]
+fn accept_bounded() {
+    let mut ledger = setup("AcceptBounded");
+    let operations = ledger.operations().count();
+
+    // The stream comes with newer articles, which must not be applied
+    let articles = ledger.articles().clone();
+    let mut semantics = articles.semantics().clone();
+    let mut custom = api();
+    custom.verifiers.insert(vname!("burn"), 1).unwrap();
+    semantics.custom.insert(tn!("Burnable"), custom).unwrap();
+    semantics.version = 1;
+    let upgraded =
+        Articles::with(semantics, articles.issue().clone(), None, |_, _, _| Result::<_, Infallible>::Ok(())).unwrap();
+
+    let mut data = Vec::new();
+    let mut writer = StrictWriter::with(StreamWriter::new::<{ usize::MAX }>(&mut data));
+    writer = (DEEDS_VERSION as u8).strict_encode(writer).unwrap();
+    writer = ledger.contract_id().strict_encode(writer).unwrap();
+    writer = 0u8.strict_encode(writer).unwrap();
+    writer = upgraded.strict_encode(writer).unwrap();
+    u32::MAX.strict_encode(writer).unwrap();
+
+    let mut reader = StrictReader::with(StreamReader::cursor::<{ usize::MAX }>(data));
+    let err = ledger
+        .accept_bounded(&mut reader, |_, _, _| Result::<_, Infallible>::Ok(()), 1000)
+        .unwrap_err();
+    assert!(matches!(err, MultiError::A(AcceptError::TooManyOperations { count: u32::MAX, max: 1000 })));
+    assert_eq!(ledger.operations().count(), operations);
+    assert_eq!(ledger.articles().semantics().version, 0);
+    assert!(ledger.articles().semantics().custom.is_empty());
+}
+
+#[test]
+fn accept_out_of_order() {
+    let mut ledger = setup("AcceptOutOfOrder");
+    let articles = ledger.articles().clone();
+    let contract_id = ledger.contract_id();
+    let (api, types) = (articles.default_api(), articles.types());
+
+    let (addr, _) = ledger.state().main.owned["amount"]
+        .first_key_value()
+        .unwrap();
+    let parent = OpBuilder::new(contract_id, articles.call_id("transfer"))
+        .destroy(*addr)
+        .add_owned("amount", AuthToken::from([0xAB; 30]), svnum!(91u64), None, api, [], types)
+        .finalize();
+    let prevout = CellAddr::new(parent.opid(), 0);
+    let child = OpBuilder::new(contract_id, articles.call_id("transfer"))
+        .destroy(prevout)
+        .add_owned("amount", AuthToken::from([0xCD; 30]), svnum!(91u64), None, api, [], types)
+        .finalize();
+
+    let stream = |ops: &[&Operation]| {
+        let mut data = Vec::new();
+        let mut writer = StrictWriter::with(StreamWriter::new::<{ usize::MAX }>(&mut data));
+        writer = 0u8.strict_encode(writer).unwrap();
+        writer = contract_id.strict_encode(writer).unwrap();
+        writer = 0u8.strict_encode(writer).unwrap();
+        writer = articles.strict_encode(writer).unwrap();
+        writer = (ops.len() as u32).strict_encode(writer).unwrap();
+        for op in ops {
+            writer = op.strict_encode(writer).unwrap();
+        }
+        StrictReader::with(StreamReader::cursor::<{ usize::MAX }>(data))
+    };
+
+    // The parent never arrives
+    let err = ledger
+        .accept(&mut stream(&[&child]), |_, _, _| Result::<_, Infallible>::Ok(()))
+        .unwrap_err();
+    assert!(
+        matches!(err, MultiError::A(AcceptError::OrphanOperation(opid, addr)) if opid == child.opid() && addr == prevout)
+    );
+    assert!(!ledger.has_operation(child.opid()));
+
+    // The child comes before its parent
+    ledger
+        .accept(&mut stream(&[&child, &parent]), |_, _, _| Result::<_, Infallible>::Ok(()))
+        .unwrap();
+    assert!(ledger.is_valid(parent.opid()));
+    assert!(ledger.is_valid(child.opid()));
+    assert!(!ledger.state().raw.owned.contains_key(&prevout));
+
+    // The parent is known, but rolled back
+    ledger.rollback([parent.opid()]).unwrap();
+    let sibling = OpBuilder::new(contract_id, articles.call_id("transfer"))
+        .destroy(prevout)
+        .add_owned("amount", AuthToken::from([0xEF; 30]), svnum!(91u64), None, api, [], types)
+        .finalize();
+    let err = ledger
+        .accept(&mut stream(&[&sibling]), |_, _, _| Result::<_, Infallible>::Ok(()))
+        .unwrap_err();
+    assert!(
+        matches!(err, MultiError::A(AcceptError::RolledBackInput(opid, addr)) if opid == sibling.opid() && addr == prevout)
+    );
+    assert!(!ledger.has_operation(sibling.opid()));
+}
+
+#[test]
+fn issue_request() {
+    let semantics = semantics(api());
+    let issuer = Issuer::new(codex(), semantics).unwrap();
+    let codex_id = issuer.codex_id();
+
+    let auth = AuthToken::from([0xAB; 30]);
+    let mut params = IssueParams::new_testnet(codex_id, "FungibleTest", Consensus::None);
+    params.push_owned_unlocked("amount", auth, svnum!(100u64));
+    params.push_owned_unlocked("amount", AuthToken::from([0xCD; 30]), svnum!(50u64));
+
+    let req = params.to_request(codex_id);
+    assert_eq!(req.scope, CallScope::ContractQuery(format!("{codex_id:-#}")));
+    assert_eq!(req.layer1, Layer1::new(Consensus::None, true));
+    assert_eq!(req.call, Some(CallState::with("issue", "amount")));
+    assert_eq!(req.auth, auth);
+    assert_eq!(req.data, vec![svnum!(100u64)]);
+
+    let mut req = req;
+    req.api = Some(tn!("FungibleTest"));
+    let s = req.to_string();
+    assert_eq!(CallRequest::<CallScope, AuthToken>::from_str(&s).unwrap(), req);
+}
+
+#[test]
+#[should_panic(expected = "invalid issue parameters: authority token")]
+fn issue_repeated_auth() {
+    let semantics = semantics(api());
+    let issuer = Issuer::new(codex(), semantics).unwrap();
+
+    let auth = AuthToken::from([0xAB; 30]);
+    let mut params = IssueParams::new_testnet(issuer.codex_id(), "FungibleTest", Consensus::None);
+    params.push_owned_unlocked("amount", auth, svnum!(100u64));
+    params.push_owned_unlocked("amount", AuthToken::from([0xCD; 30]), svnum!(50u64));
+    assert_eq!(params.validate(), Ok(()));
+    params.push_owned_unlocked("amount", auth, svnum!(10u64));
+    assert_eq!(params.validate(), Err(CoreParamsError::RepeatedAuthToken(auth)));
+
+    issuer.issue(params);
+}
+
+#[test]
+fn stateless_operation() {
+    let mut ledger = setup("Stateless");
+    let (addr, _) = ledger.state().main.owned["amount"]
+        .first_key_value()
+        .unwrap();
+    let addr = *addr;
+    assert!(!ledger.is_stateless(addr.opid));
+    assert!(!ledger.is_stateless(ledger.articles().genesis_opid()));
+
+    let opid = ledger.start_deed("transfer").using(addr).commit().unwrap();
+    assert!(ledger.is_stateless(opid));
+}
+
+#[test]
+fn export_hashed() {
+    let ledger = setup("ExportHashed");
+    let terminals = ledger.state().main.owned["amount"]
+        .keys()
+        .take(3)
+        .map(|addr| ledger.operation(addr.opid).destructible_out[addr.pos as usize].auth)
+        .collect::<Vec<_>>();
+
+    let deeds_path = "tests/data/ExportHashed.deeds";
+    fs::remove_file(deeds_path).ok();
+    ledger.export_to_file(&terminals, deeds_path).unwrap();
+    let file = fs::read(deeds_path).unwrap();
+
+    let mut data = Vec::new();
+    let hash = ledger.export_hashed(&terminals, &mut data).unwrap();
+    // Skip the binary file header, consisting of the magic number and version
+    assert_eq!(&file[10..], data.as_slice());
+    let expected = StrictHash::from(Sha256::from_tag(StrictHash::TAG).with_raw(&file[10..]));
+    assert_eq!(hash, expected);
+}
+
+#[test]
+fn deeds_checksum() {
+    let ledger = setup("DeedsChecksum");
+    let terminals = ledger.state().main.owned["amount"]
+        .keys()
+        .take(3)
+        .map(|addr| ledger.operation(addr.opid).destructible_out[addr.pos as usize].auth)
+        .collect::<Vec<_>>();
+
+    let deeds_path = "tests/data/DeedsChecksum.deeds";
+    fs::remove_file(deeds_path).ok();
+    ledger.export_to_file(&terminals, deeds_path).unwrap();
+    let hash = ledger.export_hashed(&terminals, io::sink()).unwrap();
+    assert_eq!(peek_deeds_checksum(deeds_path).unwrap(), hash);
+
+    let other_path = "tests/data/DeedsChecksumAll.deeds";
+    fs::remove_file(other_path).ok();
+    ledger.export_all_to_file(other_path).unwrap();
+    assert_ne!(peek_deeds_checksum(other_path).unwrap(), hash);
+
+    assert!(peek_deeds_checksum("tests/data/DeedsChecksum.absent").is_err());
+}
+
+#[test]
+fn snapshot_restore() {
+    let mut ledger = setup("Snapshot");
+    let genesis_opid = ledger.articles().genesis_opid();
+    let (mid_opid1, _) = ledger.operations().nth(50).unwrap();
+    let (mid_opid2, _) = ledger.operations().nth(30).unwrap();
+    ledger.rollback([mid_opid1]).unwrap();
+    ledger.forward([mid_opid1]).unwrap();
+    ledger.rollback([mid_opid2]).unwrap();
+    // Releasing some of the genesis outputs
+    let (first_opid, first) = ledger
+        .operations()
+        .find(|(_, op)| {
+            op.destructible_in
+                .iter()
+                .any(|input| input.addr.opid == genesis_opid)
+        })
+        .unwrap();
+    ledger
+        .rollback_with_reason([first_opid], tiny_s!("released genesis"))
+        .unwrap();
+
+    let mut snapshot = Vec::new();
+    ledger
+        .snapshot(StrictWriter::with(StreamWriter::new::<{ usize::MAX }>(&mut snapshot)))
+        .unwrap();
+
+    let contract_path = contract_dir("Snapshot-restored");
+    let mut reader = StrictReader::with(StreamReader::cursor::<{ usize::MAX }>(snapshot.as_slice()));
+    let mut restored = Ledger::<StockFs>::restore(&mut reader, contract_path).unwrap();
+
+    assert_eq!(restored.contract_id(), ledger.contract_id());
+    assert!(restored.is_valid(genesis_opid));
+    assert_eq!(restored.state().main, ledger.state().main);
+    assert_eq!(restored.operations().collect::<Vec<_>>(), ledger.operations().collect::<Vec<_>>());
+    assert_eq!(restored.trace().collect::<Vec<_>>(), ledger.trace().collect::<Vec<_>>());
+    for (opid, op) in ledger.operations() {
+        assert_eq!(restored.is_valid(opid), ledger.is_valid(opid));
+        assert_eq!(restored.invalidation_reason(opid), ledger.invalidation_reason(opid));
+        assert_eq!(
+            restored
+                .stock()
+                .received_at(opid)
+                .map(|time| time.timestamp_millis()),
+            ledger
+                .stock()
+                .received_at(opid)
+                .map(|time| time.timestamp_millis())
+        );
+        for input in op.destructible_in {
+            assert_eq!(restored.spent_by(input.addr), ledger.spent_by(input.addr));
+        }
+    }
+
+    assert_eq!(restored.invalidation_reason(first_opid), Some(tiny_s!("released genesis")));
+
+    let mut copy = Vec::new();
+    restored
+        .snapshot(StrictWriter::with(StreamWriter::new::<{ usize::MAX }>(&mut copy)))
+        .unwrap();
+    assert_eq!(copy, snapshot);
+
+    // The restored contract accepts new operations spending genesis outputs
+    let auth = AuthToken::from([0xAB; 30]);
+    let opid = ledger
+        .start_deed("transfer")
+        .using(first.destructible_in[0].addr)
+        .assign("amount", auth, svnum!(100u64), None)
+        .commit()
+        .unwrap();
+    let mut data = Vec::new();
+    ledger
+        .export([auth], StrictWriter::with(StreamWriter::new::<{ usize::MAX }>(&mut data)))
+        .unwrap();
+    restored
+        .accept(&mut StrictReader::with(StreamReader::cursor::<{ usize::MAX }>(data)), |_, _, _| {
+            Result::<_, Infallible>::Ok(())
+        })
+        .unwrap();
+    assert!(restored.is_valid(opid));
+}
+
+#[test]
+fn preview_state() {
+    let ledger = setup("Preview");
+    let articles = ledger.articles();
+    let contract_id = ledger.contract_id();
+    let call_id = articles.call_id("transfer");
+    let api = articles.default_api();
+    let types = articles.types();
+
+    let owned = ledger.state().main.owned.get("amount").unwrap().clone();
+    let (first, second) = {
+        let mut iter = owned.keys();
+        (*iter.next().unwrap(), *iter.next().unwrap())
+    };
+
+    let transfer = OpBuilder::new(contract_id, call_id)
+        .destroy(first)
+        .add_owned("amount", AuthToken::from([0x01; 30]), svnum!(50u64), None, api, [], types)
+        .add_owned("amount", AuthToken::from([0x02; 30]), svnum!(41u64), None, api, [], types)
+        .finalize();
+    let change = CellAddr::new(transfer.opid(), 1);
+    let transfer = articles
+        .codex()
+        .verify(contract_id, transfer, &ledger.state().raw, articles)
+        .unwrap();
+
+    let preview = ledger.preview_state(slice::from_ref(&transfer));
+    let second_transfer = OpBuilder::new(contract_id, call_id)
+        .destroy(change)
+        .destroy(second)
+        .add_owned("amount", AuthToken::from([0x03; 30]), svnum!(132u64), None, api, [], types)
+        .finalize();
+    let target = CellAddr::new(second_transfer.opid(), 0);
+    let second_transfer = articles
+        .codex()
+        .verify(contract_id, second_transfer, &preview.raw, articles)
+        .unwrap();
+
+    let preview = ledger.preview_state(&[transfer, second_transfer]);
+    let pending = preview.main.owned.get("amount").unwrap();
+    assert_eq!(pending.len(), 20);
+    assert!(!pending.contains_key(&first));
+    assert!(!pending.contains_key(&second));
+    assert!(!pending.contains_key(&change));
+    assert_eq!(pending.get(&target), Some(&svnum!(132u64)));
+
+    assert_eq!(ledger.state().main.owned.get("amount"), Some(&owned));
+    assert_eq!(ledger.operations().count(), 100);
+    assert!(!ledger.is_valid(target.opid));
+}
+
+#[test]
+fn total_burned() {
+    let mut ledger = setup("TotalBurned");
+    // Each transfer after the first round burns one unit per output
+    assert_eq!(ledger.total_burned("amount"), Some(9 * 10 * 2));
+    assert_eq!(ledger.total_burned("unknown"), None);
+
+    let owned = ledger.state().main.owned.get("amount").unwrap().clone();
+    let mut iter = owned.keys();
+    let (first, second) = (*iter.next().unwrap(), *iter.next().unwrap());
+    ledger
+        .start_deed("transfer")
+        .using(first)
+        .using(second)
+        .assign("amount", AuthToken::from([0x01; 30]), svnum!(150u64), None)
+        .commit()
+        .unwrap();
+    assert_eq!(ledger.total_burned("amount"), Some(9 * 10 * 2 + 32));
+}
+
+#[test]
+fn cancel_deed() {
+    let mut ledger = setup("CancelDeed");
+    let state = ledger.state().clone();
+    let operations = ledger.operations().count();
+    let trace = ledger.trace().count();
+
+    let (addr, _) = ledger.state().main.owned["amount"]
+        .first_key_value()
+        .unwrap();
+    let addr = *addr;
+    ledger
+        .start_deed("transfer")
+        .using(addr)
+        .assign("amount", AuthToken::from([0xAB; 30]), svnum!(91u64), None)
+        .cancel();
+
+    assert_eq!(ledger.state().main, state.main);
+    assert_eq!(ledger.state().raw.owned, state.raw.owned);
+    assert_eq!(ledger.operations().count(), operations);
+    assert_eq!(ledger.trace().count(), trace);
+    assert_eq!(ledger.spent_by(addr), None);
+}
+
+#[test]
+fn export_since() {
+    let mut ledger = setup("ExportSince");
+    let since = Utc::now();
+    thread::sleep(Duration::from_millis(5));
+
+    let owned = ledger.state().main.owned.get("amount").unwrap().clone();
+    let mut iter = owned.keys();
+    let mut new = vec![];
+    for byte in [0x01u8, 0x02] {
+        let (first, second) = (*iter.next().unwrap(), *iter.next().unwrap());
+        let opid = ledger
+            .start_deed("transfer")
+            .using(first)
+            .using(second)
+            .assign("amount", AuthToken::from([byte; 30]), svnum!(182u64), None)
+            .commit()
+            .unwrap();
+        new.push(opid);
+    }
+    assert!(ledger.stock().received_at(new[0]).unwrap() > since);
+
+    let mut data = Vec::new();
+    let writer = StrictWriter::with(StreamWriter::new::<{ usize::MAX }>(&mut data));
+    ledger.export_since(since, writer).unwrap();
+
+    let mut reader = StrictReader::with(StreamReader::cursor::<{ usize::MAX }>(data));
+    assert_eq!(u8::strict_decode(&mut reader).unwrap(), 0);
+    assert_eq!(ContractId::strict_decode(&mut reader).unwrap(), ledger.contract_id());
+    assert_eq!(u8::strict_decode(&mut reader).unwrap(), 0);
+    Semantics::strict_decode(&mut reader).unwrap();
+    Option::<SigBlob>::strict_decode(&mut reader).unwrap();
+    Issue::strict_decode(&mut reader).unwrap();
+    assert_eq!(u32::strict_decode(&mut reader).unwrap(), 2);
+    let exported = (0..2)
+        .map(|_| Operation::strict_decode(&mut reader).unwrap().opid())
+        .collect::<Vec<_>>();
+    assert_eq!(exported, new);
+}
+
+#[test]
+fn accept_invalid_signature() {
+    let mut ledger = setup("AcceptInvalidSig");
+    let articles = ledger.articles().clone();
+    let state = ledger.state().clone();
+    let operations = ledger.operations().count();
+    let contract_id = ledger.contract_id();
+
+    let (addr, _) = ledger.state().main.owned["amount"]
+        .first_key_value()
+        .unwrap();
+    let transfer = OpBuilder::new(contract_id, articles.call_id("transfer"))
+        .destroy(*addr)
+        .add_owned(
+            "amount",
+            AuthToken::from([0xAB; 30]),
+            svnum!(91u64),
+            None,
+            articles.default_api(),
+            [],
+            articles.types(),
+        )
+        .finalize();
+    let opid = transfer.opid();
+
+    // Newer signed articles, which would be upgraded to if the signature were valid
+    let mut semantics = articles.semantics().clone();
+    semantics.version = 1;
+    let sig = SigBlob::from_slice_checked(*b"sig");
+    let signed =
+        Articles::with(semantics, articles.issue().clone(), Some(sig), |_, _, _| Result::<_, Infallible>::Ok(()))
+            .unwrap();
+
+    let mut data = Vec::new();
+    let mut writer = StrictWriter::with(StreamWriter::new::<{ usize::MAX }>(&mut data));
+    writer = 0u8.strict_encode(writer).unwrap();
+    writer = contract_id.strict_encode(writer).unwrap();
+    writer = 0u8.strict_encode(writer).unwrap();
+    writer = signed.strict_encode(writer).unwrap();
+    writer = 1u32.strict_encode(writer).unwrap();
+    transfer.strict_encode(writer).unwrap();
+
+    let mut reader = StrictReader::with(StreamReader::cursor::<{ usize::MAX }>(data.clone()));
+    let err = ledger.accept(&mut reader, |_, _, _| Err(())).unwrap_err();
+    assert!(matches!(err, MultiError::A(AcceptError::Articles(SemanticError::InvalidSignature))));
+
+    let check = |ledger: &LedgerDir| {
+        assert_eq!(ledger.articles(), &articles);
+        assert_eq!(ledger.state().main, state.main);
+        assert_eq!(ledger.state().raw.owned, state.raw.owned);
+        assert_eq!(ledger.state().raw.global, state.raw.global);
+        assert_eq!(ledger.operations().count(), operations);
+        assert!(!ledger.has_operation(opid));
+    };
+    check(&ledger);
+    // Nothing must be persisted either
+    let path = ledger.path().to_path_buf();
+    drop(ledger);
+    let mut ledger = LedgerDir::load(path).unwrap();
+    check(&ledger);
+
+    let mut reader = StrictReader::with(StreamReader::cursor::<{ usize::MAX }>(data));
+    ledger
+        .accept(&mut reader, |_, _, _| Result::<_, Infallible>::Ok(()))
+        .unwrap();
+    assert_eq!(ledger.articles().semantics().version, 1);
+    assert!(ledger.is_valid(opid));
+}
+
+#[test]
+fn genesis_with_inputs() {
+    let ledger = setup("GenesisInputs");
+    let genesis = ledger.articles().genesis();
+    let addr = CellAddr::new(ledger.articles().genesis_opid(), 0);
+    let decode = |op: &Operation| {
+        let mut data = Vec::new();
+        op.strict_encode(StrictWriter::with(StreamWriter::new::<{ usize::MAX }>(&mut data)))
+            .unwrap();
+        Genesis::strict_decode(&mut StrictReader::with(StreamReader::cursor::<{ usize::MAX }>(data)))
+    };
+
+    // Genesis is encoded the same way as an operation, and reserves zero bytes at the place of the
+    // operation inputs; thus, a genesis carrying inputs is rejected when decoded.
+    let op = genesis.to_operation(ledger.contract_id());
+    decode(&op).unwrap();
+
+    let mut malformed = op.clone();
+    malformed
+        .destructible_in
+        .push(Input { addr, witness: StateValue::None })
+        .unwrap();
+    assert!(decode(&malformed).is_err());
+
+    let mut malformed = op;
+    malformed.immutable_in.push(addr).unwrap();
+    assert!(decode(&malformed).is_err());
+}
+
+#[test]
+fn verify_standalone() {
+    let ledger = setup("VerifyStandalone");
+    let articles = ledger.articles().clone();
+    let raw = ledger.state().raw.clone();
+    drop(ledger);
+
+    let call_id = articles.call_id("transfer");
+    let (api, types) = (articles.default_api(), articles.types());
+    let (addr, _) = raw.owned.first_key_value().unwrap();
+    let op = OpBuilder::new(articles.contract_id(), call_id)
+        .destroy(*addr)
+        .add_owned("amount", AuthToken::from([0xAB; 30]), svnum!(91u64), None, api, [], types)
+        .finalize();
+    let verified = verify_operation(&articles, &raw, &op).unwrap();
+    assert_eq!(verified.opid(), op.opid());
+
+    let missing = CellAddr::new(op.opid(), 0);
+    let op = OpBuilder::new(articles.contract_id(), call_id)
+        .destroy(missing)
+        .add_owned("amount", AuthToken::from([0xAB; 30]), svnum!(91u64), None, api, [], types)
+        .finalize();
+    let err = verify_operation(&articles, &raw, &op).unwrap_err();
+    assert!(matches!(err, CallError::NoReadOnceInput(addr) if addr == missing));
+}
+
+#[test]
+fn export_canonical() {
+    let ledger = setup("CanonicalA");
+
+    let contract_path = contract_dir("CanonicalB");
+    let mut other = LedgerDir::new(ledger.articles().clone(), contract_path).unwrap();
+
+    // Apply the same operations in a different order, preferring the most recent ones
+    let mut pending = ledger.operations().map(|(_, op)| op).collect::<Vec<_>>();
+    let mut applied = bset![ledger.articles().genesis_opid()];
+    while let Some(pos) = pending.iter().rposition(|op| {
+        op.destructible_in
+            .iter()
+            .map(|input| input.addr)
+            .chain(op.immutable_in.iter().copied())
+            .all(|addr| applied.contains(&addr.opid))
+    }) {
+        let op = pending.remove(pos);
+        applied.insert(op.opid());
+        other.apply_verify(op, false).unwrap();
+    }
+    other.commit_transaction();
+    assert!(pending.is_empty());
+
+    let terminals = ledger
+        .state()
+        .raw
+        .owned
+        .values()
+        .map(|cell| cell.auth)
+        .collect::<Vec<_>>();
+    let export = |ledger: &LedgerDir, canonical: bool| {
+        let mut data = Vec::new();
+        let writer = StrictWriter::with(StreamWriter::new::<{ usize::MAX }>(&mut data));
+        if canonical {
+            ledger.export_canonical(&terminals, writer).unwrap();
+        } else {
+            ledger.export(&terminals, writer).unwrap();
+        }
+        data
+    };
+    assert_ne!(export(&ledger, false), export(&other, false));
+    assert_eq!(export(&ledger, true), export(&other, true));
+
+    // Canonical export must be acceptable
+    let mut reader = StrictReader::with(StreamReader::cursor::<{ usize::MAX }>(export(&ledger, true)));
+    other
+        .accept(&mut reader, |_, _, _| Result::<_, Infallible>::Ok(()))
+        .unwrap();
+}
+
+#[test]
+fn export_chunks() {
+    let ledger = setup("ChunksA");
+
+    let contract_path = contract_dir("ChunksB");
+    let mut other = LedgerDir::new(ledger.articles().clone(), contract_path).unwrap();
+
+    let opids = ledger
+        .operations()
+        .map(|(opid, _)| opid)
+        .collect::<Vec<_>>();
+    let mut chunks = opids
+        .chunks(opids.len().div_ceil(3))
+        .enumerate()
+        .map(|(index, opids)| {
+            let mut data = Vec::new();
+            let writer = StrictWriter::with(StreamWriter::new::<{ usize::MAX }>(&mut data));
+            ledger.export_chunk(opids, index as u32, writer).unwrap();
+            data
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(chunks.len(), 3);
+    // Deliver the chunks in a random order, repeating one of them
+    chunks.shuffle(&mut rng());
+    chunks.insert(1, chunks[0].clone());
+
+    let mut buffer = ChunkBuffer::default();
+    for chunk in chunks {
+        let mut reader = StrictReader::with(StreamReader::cursor::<{ usize::MAX }>(chunk));
+        other.accept_chunk(&mut reader, &mut buffer).unwrap();
+    }
+    assert!(buffer.is_complete());
+    assert_eq!(buffer.accepted_chunks(), &bset![0, 1, 2]);
+    assert_eq!(buffer.orphan_count(), 0);
+    assert_eq!(other.state().main, ledger.state().main);
+    assert_eq!(other.operations().count(), opids.len());
+}
+
+#[test]
+fn immutable_contract() {
+    let types = stl::FungibleTypes::new();
+    let mut codex = codex();
+    codex.verifiers = tiny_bmap! { 0 => LibSite::new(libs::success().lib_id(), 0) };
+    let api = Api {
+        codex_id: codex.codex_id(),
+        conforms: none!(),
+        default_call: None,
+        global: tiny_bmap! {
+            vname!("record") => GlobalApi {
+                published: true,
+                sem_id: types.get("Fungible.Amount"),
+                convertor: StateConvertor::TypedEncoder(u256::ZERO),
+                fallback_convertors: none!(),
+                builder: StateBuilder::TypedEncoder(u256::ZERO),
+                raw_convertor: RawConvertor::StrictDecode(SemId::unit()),
+                raw_builder: RawBuilder::StrictEncode(SemId::unit()),
+            }
+        },
+        owned: none!(),
+        aggregators: none!(),
+        verifiers: tiny_bmap! { vname!("issue") => 0 },
+        errors: Default::default(),
+    };
+    let semantics = semantics(api);
+
+    let contract_path = contract_dir("Immutable");
+    let ledger =
+        Ledger::<StockFs>::issue_testnet(codex, semantics, "issue", Consensus::None, contract_path.clone(), |issue| {
+            issue
+                .append("record", svnum!(1u64), None)
+                .append("record", svnum!(2u64), None)
+                .finish("Immutable", 1732529307)
+        })
+        .unwrap();
+    assert!(ledger.is_immutable());
+    drop(ledger);
+
+    let ledger = LedgerDir::load(contract_path).unwrap();
+    assert!(ledger.is_immutable());
+    assert_eq!(ledger.operations().count(), 0);
+    let record = ledger
+        .state()
+        .global_ref("record")
+        .unwrap()
+        .values()
+        .map(|atom| atom.verified.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(record, vec![svnum!(1u64), svnum!(2u64)]);
+
+    assert!(!setup("Mutable").is_immutable());
+}